@@ -0,0 +1,377 @@
+//! Fits the linearly transformed cosine tables used by `RectAreaLight` to the GGX BRDF, following
+//! "Real-Time Polygonal-Light Shading with Linearly Transformed Cosines" (Heitz et al. 2016).
+//!
+//! `cargo run --release -p sand-castle-core --example fit_ltc -- src/shaders/ltc.bin`
+//!
+//! Writes two `SIZE` x `SIZE` `Rgba16Float` tables back to back, indexed by roughness along `x`
+//! and `sqrt(1 - cos(theta))` along `y`. The first holds the `m00`, `m02`, `m20` and `m22`
+//! terms of the normalized inverse matrix, the second the BRDF norm and its Schlick Fresnel
+//! part.
+
+use std::f64::consts::PI;
+
+use glam::{DMat3, DVec3};
+
+const SIZE: usize = 64;
+const SAMPLES: usize = 32;
+const MIN_ALPHA: f64 = 0.00001;
+
+struct Ltc {
+  magnitude: f64,
+  m11: f64,
+  m22: f64,
+  m13: f64,
+  basis: DMat3,
+  m: DMat3,
+  inv_m: DMat3,
+  det_m: f64,
+}
+
+impl Ltc {
+  fn new() -> Self {
+    let mut ltc = Self {
+      magnitude: 1.0,
+      m11: 1.0,
+      m22: 1.0,
+      m13: 0.0,
+      basis: DMat3::IDENTITY,
+      m: DMat3::IDENTITY,
+      inv_m: DMat3::IDENTITY,
+      det_m: 1.0,
+    };
+    ltc.update();
+    ltc
+  }
+
+  fn update(&mut self) {
+    self.m = self.basis
+      * DMat3::from_cols(
+        DVec3::new(self.m11, 0.0, 0.0),
+        DVec3::new(0.0, self.m22, 0.0),
+        DVec3::new(self.m13, 0.0, 1.0),
+      );
+    self.inv_m = self.m.inverse();
+    self.det_m = self.m.determinant().abs();
+  }
+
+  fn eval(&self, l: DVec3) -> f64 {
+    let original = (self.inv_m * l).normalize();
+    let transformed = self.m * original;
+    let length = transformed.length();
+    let jacobian = self.det_m / (length * length * length);
+
+    self.magnitude * original.z.max(0.0) / PI / jacobian
+  }
+
+  fn sample(&self, u1: f64, u2: f64) -> DVec3 {
+    let theta = u1.sqrt().acos();
+    let phi = 2.0 * PI * u2;
+
+    (self.m
+      * DVec3::new(
+        theta.sin() * phi.cos(),
+        theta.sin() * phi.sin(),
+        theta.cos(),
+      ))
+    .normalize()
+  }
+}
+
+fn lambda(alpha: f64, cos_theta: f64) -> f64 {
+  if cos_theta >= 1.0 {
+    return 0.0;
+  }
+
+  let a = 1.0 / alpha / cos_theta.acos().tan();
+  0.5 * (-1.0 + (1.0 + 1.0 / a / a).sqrt())
+}
+
+/// GGX BRDF times the cosine of `l`, and the pdf of sampling `l`.
+fn ggx_eval(v: DVec3, l: DVec3, alpha: f64) -> (f64, f64) {
+  if v.z <= 0.0 {
+    return (0.0, 0.0);
+  }
+
+  let lambda_v = lambda(alpha, v.z);
+  let g2 = if l.z <= 0.0 {
+    0.0
+  } else {
+    1.0 / (1.0 + lambda_v + lambda(alpha, l.z))
+  };
+
+  let h = (v + l).normalize();
+  let slope_x = h.x / h.z;
+  let slope_y = h.y / h.z;
+  let d = 1.0 / (1.0 + (slope_x * slope_x + slope_y * slope_y) / alpha / alpha);
+  let d = d * d / (PI * alpha * alpha * h.z.powi(4));
+
+  let pdf = (d * h.z / 4.0 / v.dot(h)).abs();
+  (d * g2 / 4.0 / v.z, pdf)
+}
+
+fn ggx_sample(v: DVec3, alpha: f64, u1: f64, u2: f64) -> DVec3 {
+  let phi = 2.0 * PI * u1;
+  let r = alpha * (u2 / (1.0 - u2)).sqrt();
+  let n = DVec3::new(r * phi.cos(), r * phi.sin(), 1.0).normalize();
+
+  -v + 2.0 * n * n.dot(v)
+}
+
+fn sample_grid() -> impl Iterator<Item = (f64, f64)> {
+  (0..SAMPLES).flat_map(|j| {
+    (0..SAMPLES).map(move |i| {
+      (
+        (i as f64 + 0.5) / SAMPLES as f64,
+        (j as f64 + 0.5) / SAMPLES as f64,
+      )
+    })
+  })
+}
+
+/// Norm of the BRDF, its Schlick Fresnel part and its average direction.
+fn average_terms(v: DVec3, alpha: f64) -> (f64, f64, DVec3) {
+  let mut norm = 0.0;
+  let mut fresnel = 0.0;
+  let mut direction = DVec3::ZERO;
+
+  for (u1, u2) in sample_grid() {
+    let l = ggx_sample(v, alpha, u1, u2);
+    let (eval, pdf) = ggx_eval(v, l, alpha);
+
+    if pdf > 0.0 {
+      let weight = eval / pdf;
+      let h = (v + l).normalize();
+
+      norm += weight;
+      fresnel += weight * (1.0 - v.dot(h).max(0.0)).powi(5);
+      direction += weight * l;
+    }
+  }
+
+  let count = (SAMPLES * SAMPLES) as f64;
+  direction.y = 0.0;
+
+  (norm / count, fresnel / count, direction.normalize())
+}
+
+/// Cubed difference between the BRDF and the LTC, multiple importance sampled from both.
+fn error(ltc: &Ltc, v: DVec3, alpha: f64) -> f64 {
+  let mut error = 0.0;
+
+  for (u1, u2) in sample_grid() {
+    for l in [ltc.sample(u1, u2), ggx_sample(v, alpha, u1, u2)] {
+      let (eval_brdf, pdf_brdf) = ggx_eval(v, l, alpha);
+      let eval_ltc = ltc.eval(l);
+      let pdf_ltc = eval_ltc / ltc.magnitude;
+
+      error += (eval_brdf - eval_ltc).abs().powi(3) / (pdf_ltc + pdf_brdf);
+    }
+  }
+
+  error / (SAMPLES * SAMPLES) as f64
+}
+
+fn apply(ltc: &mut Ltc, params: [f64; 3], isotropic: bool) {
+  ltc.m11 = params[0].max(1e-7);
+  ltc.m22 = if isotropic {
+    ltc.m11
+  } else {
+    params[1].max(1e-7)
+  };
+  ltc.m13 = if isotropic { 0.0 } else { params[2] };
+  ltc.update();
+}
+
+fn nelder_mead(
+  start: [f64; 3],
+  delta: f64,
+  tolerance: f64,
+  iterations: usize,
+  mut objective: impl FnMut([f64; 3]) -> f64,
+) -> [f64; 3] {
+  let mut simplex = [start; 4];
+  for (i, point) in simplex.iter_mut().skip(1).enumerate() {
+    point[i] += delta;
+  }
+  let mut values = simplex.map(&mut objective);
+
+  let along = |from: [f64; 3], to: [f64; 3], t: f64| -> [f64; 3] {
+    std::array::from_fn(|i| from[i] + t * (to[i] - from[i]))
+  };
+
+  for _ in 0..iterations {
+    let mut order = [0, 1, 2, 3];
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+    let (lo, next_highest, hi) = (order[0], order[2], order[3]);
+
+    let (a, b) = (values[lo].abs(), values[hi].abs());
+    if 2.0 * (a - b).abs() < (a + b) * tolerance {
+      break;
+    }
+
+    let centroid: [f64; 3] = std::array::from_fn(|i| {
+      (0..4)
+        .filter(|k| *k != hi)
+        .map(|k| simplex[k][i])
+        .sum::<f64>()
+        / 3.0
+    });
+
+    let reflected = along(centroid, simplex[hi], -1.0);
+    let reflected_value = objective(reflected);
+
+    if reflected_value < values[next_highest] {
+      if reflected_value < values[lo] {
+        let expanded = along(centroid, simplex[hi], -2.0);
+        let expanded_value = objective(expanded);
+
+        if expanded_value < reflected_value {
+          simplex[hi] = expanded;
+          values[hi] = expanded_value;
+          continue;
+        }
+      }
+
+      simplex[hi] = reflected;
+      values[hi] = reflected_value;
+      continue;
+    }
+
+    let contracted = along(centroid, simplex[hi], 0.5);
+    let contracted_value = objective(contracted);
+
+    if contracted_value < values[hi] {
+      simplex[hi] = contracted;
+      values[hi] = contracted_value;
+      continue;
+    }
+
+    for k in (0..4).filter(|k| *k != lo) {
+      simplex[k] = along(simplex[lo], simplex[k], 0.5);
+      values[k] = objective(simplex[k]);
+    }
+  }
+
+  let lo = (0..4)
+    .min_by(|a, b| values[*a].total_cmp(&values[*b]))
+    .unwrap_or(0);
+  simplex[lo]
+}
+
+fn fit() -> (Vec<[f64; 4]>, Vec<[f64; 4]>) {
+  let mut matrices = vec![DMat3::IDENTITY; SIZE * SIZE];
+  let mut norms = vec![[0.0; 4]; SIZE * SIZE];
+  let mut ltc = Ltc::new();
+
+  for a in (0..SIZE).rev() {
+    for t in 0..SIZE {
+      let x = t as f64 / (SIZE - 1) as f64;
+      let theta = (1.0 - x * x).acos().min(1.57);
+      let v = DVec3::new(theta.sin(), 0.0, theta.cos());
+
+      let roughness = a as f64 / (SIZE - 1) as f64;
+      let alpha = (roughness * roughness).max(MIN_ALPHA);
+
+      let (norm, fresnel, direction) = average_terms(v, alpha);
+      ltc.magnitude = norm;
+
+      // the lobe is rotationally symmetric at normal incidence, otherwise the previous fit is
+      // the first guess
+      let isotropic = t == 0;
+      if isotropic {
+        ltc.basis = DMat3::IDENTITY;
+        if a == SIZE - 1 {
+          ltc.m11 = 1.0;
+          ltc.m22 = 1.0;
+        } else {
+          let previous = matrices[a + 1];
+          ltc.m11 = previous.x_axis.x;
+          ltc.m22 = previous.y_axis.y;
+        }
+        ltc.m13 = 0.0;
+      } else {
+        ltc.basis = DMat3::from_cols(
+          DVec3::new(direction.z, 0.0, -direction.x),
+          DVec3::Y,
+          direction,
+        );
+      }
+      ltc.update();
+
+      let start = [ltc.m11, ltc.m22, ltc.m13];
+      let params = nelder_mead(start, 0.05, 1e-5, 100, |params| {
+        apply(&mut ltc, params, isotropic);
+        error(&ltc, v, alpha)
+      });
+      apply(&mut ltc, params, isotropic);
+
+      matrices[a + t * SIZE] = ltc.m;
+      norms[a + t * SIZE] = [norm, fresnel, 0.0, 0.0];
+    }
+
+    eprintln!("fitted roughness {a}");
+  }
+
+  let inverses = matrices
+    .iter()
+    .map(|m| {
+      let mut m = *m;
+      // only the terms in the plane of incidence are kept
+      m.x_axis.y = 0.0;
+      m.y_axis.x = 0.0;
+      m.z_axis.y = 0.0;
+      m.y_axis.z = 0.0;
+
+      let inverse = m.inverse() / m.inverse().y_axis.y;
+      [
+        inverse.x_axis.x,
+        inverse.x_axis.z,
+        inverse.z_axis.x,
+        inverse.z_axis.z,
+      ]
+    })
+    .collect();
+
+  (inverses, norms)
+}
+
+fn f16_bits(value: f64) -> u16 {
+  let bits = (value as f32).to_bits();
+  let sign = ((bits >> 16) & 0x8000) as u16;
+  let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+  let mantissa = bits & 0x7f_ffff;
+
+  if exponent <= 0 {
+    // subnormal, rounded to nearest
+    if exponent < -10 {
+      return sign;
+    }
+    let mantissa = mantissa | 0x80_0000;
+    let shift = (14 - exponent) as u32;
+    return sign | ((mantissa + (1 << (shift - 1))) >> shift) as u16;
+  }
+
+  if exponent >= 31 {
+    return sign | 0x7c00;
+  }
+
+  let rounded = ((exponent as u32) << 10 | mantissa >> 13) + ((mantissa >> 12) & 1);
+  sign | rounded as u16
+}
+
+fn main() {
+  let path = std::env::args()
+    .nth(1)
+    .expect("usage: fit_ltc <output path>");
+
+  let (inverses, norms) = fit();
+
+  let bytes = inverses
+    .iter()
+    .chain(norms.iter())
+    .flatten()
+    .flat_map(|value| f16_bits(*value).to_le_bytes())
+    .collect::<Vec<_>>();
+
+  std::fs::write(&path, bytes).expect("failed to write the tables");
+}
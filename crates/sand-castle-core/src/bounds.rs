@@ -0,0 +1,122 @@
+use getset::Getters;
+use glam::{Mat4, Vec3, Vec4};
+
+/// Axis aligned bounding box.
+#[derive(Getters, Default, Clone, Copy, Debug, PartialEq)]
+#[getset(get = "pub")]
+pub struct Aabb {
+  min: Vec3,
+  max: Vec3,
+}
+
+#[derive(Getters, Default, Clone, Copy, Debug, PartialEq)]
+#[getset(get = "pub")]
+pub struct BoundingSphere {
+  center: Vec3,
+  radius: f32,
+}
+
+/// Planes of a view frustum, pointing inwards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+  planes: [Vec4; 6],
+}
+
+impl Aabb {
+  pub fn new(min: Vec3, max: Vec3) -> Self {
+    Self { min, max }
+  }
+
+  /// `None` for an empty set of points.
+  pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+    points.into_iter().fold(None, |aabb, point| {
+      Some(match aabb {
+        Some(Aabb { min, max }) => Aabb::new(min.min(point), max.max(point)),
+        None => Aabb::new(point, point),
+      })
+    })
+  }
+
+  pub fn center(&self) -> Vec3 {
+    (self.min + self.max) * 0.5
+  }
+
+  pub fn size(&self) -> Vec3 {
+    self.max - self.min
+  }
+
+  pub fn union(&self, other: &Aabb) -> Aabb {
+    Aabb::new(self.min.min(other.min), self.max.max(other.max))
+  }
+
+  /// Box enclosing this one once transformed by `matrix`.
+  pub fn transform(&self, matrix: Mat4) -> Aabb {
+    let center = matrix.transform_point3(self.center());
+    let half_size = self.size() * 0.5;
+
+    let extent = matrix.x_axis.truncate().abs() * half_size.x
+      + matrix.y_axis.truncate().abs() * half_size.y
+      + matrix.z_axis.truncate().abs() * half_size.z;
+
+    Aabb::new(center - extent, center + extent)
+  }
+
+  pub fn bounding_sphere(&self) -> BoundingSphere {
+    BoundingSphere::new(self.center(), self.size().length() * 0.5)
+  }
+}
+
+impl BoundingSphere {
+  pub fn new(center: Vec3, radius: f32) -> Self {
+    Self { center, radius }
+  }
+
+  /// Sphere centered on the bounding box of `points`, enclosing all of them.
+  pub fn from_points(points: impl IntoIterator<Item = Vec3> + Clone) -> Option<Self> {
+    let center = Aabb::from_points(points.clone())?.center();
+    let radius = points
+      .into_iter()
+      .map(|point| point.distance(center))
+      .fold(0.0, f32::max);
+
+    Some(BoundingSphere::new(center, radius))
+  }
+}
+
+impl Frustum {
+  /// Extracts the planes of a view-projection matrix with a `0..w` clip space depth range, like
+  /// the ones returned by `Camera::to_matrix`.
+  pub fn from_matrix(view_projection: Mat4) -> Self {
+    let rows = [0, 1, 2, 3].map(|index| view_projection.row(index));
+
+    let planes = [
+      rows[3] + rows[0],
+      rows[3] - rows[0],
+      rows[3] + rows[1],
+      rows[3] - rows[1],
+      rows[2],
+      rows[3] - rows[2],
+    ]
+    .map(|plane| plane / plane.truncate().length().max(f32::EPSILON));
+
+    Self { planes }
+  }
+
+  /// Conservative, boxes near the frustum corners may be reported inside.
+  pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+    self.planes.iter().all(|plane| {
+      let normal = plane.truncate();
+      // corner of the box furthest along the plane normal
+      let corner = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+
+      normal.dot(corner) + plane.w >= 0.0
+    })
+  }
+
+  pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+    self
+      .planes
+      .iter()
+      .all(|plane| plane.truncate().dot(sphere.center) + plane.w >= -sphere.radius)
+  }
+}
@@ -0,0 +1,168 @@
+use getset::Getters;
+use wgpu::{
+  Buffer, BufferAsyncError, BufferDescriptor, BufferUsages, CommandEncoder, Extent3d,
+  ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, Maintain, MapMode, Origin3d, Texture,
+  TextureAspect, TextureFormat, TextureUsages, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+use crate::renderer::Renderer;
+
+/// RGBA8 pixels read back from a rendered frame, rows are tightly packed from top to bottom.
+#[derive(Getters, Clone, Debug, PartialEq)]
+#[getset(get = "pub")]
+pub struct FrameCapture {
+  width: u32,
+  height: u32,
+  pixels: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum CaptureError {
+  NoFrame,
+  NotCopyable,
+  UnsupportedFormat(TextureFormat),
+  BufferAsync(BufferAsyncError),
+  Cancelled,
+}
+
+#[cfg(feature = "encode_png")]
+#[derive(Debug)]
+pub enum EncodePngError {
+  InvalidDimensions,
+  Image(image::ImageError),
+}
+
+impl FrameCapture {
+  pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+    Self {
+      width,
+      height,
+      pixels,
+    }
+  }
+
+  pub fn into_pixels(self) -> Vec<u8> {
+    self.pixels
+  }
+
+  #[cfg(feature = "encode_png")]
+  pub fn to_png(&self) -> Result<Vec<u8>, EncodePngError> {
+    let image = image::RgbaImage::from_raw(self.width, self.height, self.pixels.clone())
+      .ok_or(EncodePngError::InvalidDimensions)?;
+
+    let mut bytes = std::io::Cursor::new(vec![]);
+    image
+      .write_to(&mut bytes, image::ImageFormat::Png)
+      .map_err(EncodePngError::Image)?;
+
+    Ok(bytes.into_inner())
+  }
+}
+
+/// A pending copy of a frame texture into a mappable buffer.
+pub(crate) struct Readback {
+  buffer: Buffer,
+  width: u32,
+  height: u32,
+  padded_bytes_per_row: u32,
+  swizzle: bool,
+}
+
+impl Readback {
+  pub(crate) fn encode(
+    renderer: &Renderer,
+    encoder: &mut CommandEncoder,
+    texture: &Texture,
+  ) -> Result<Self, CaptureError> {
+    if !texture.usage().contains(TextureUsages::COPY_SRC) {
+      return Err(CaptureError::NotCopyable);
+    }
+
+    let swizzle = match texture.format() {
+      TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+      TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+      format => return Err(CaptureError::UnsupportedFormat(format)),
+    };
+
+    let (width, height) = (texture.width(), texture.height());
+
+    let unpadded_bytes_per_row = 4 * width;
+    let padded_bytes_per_row =
+      unpadded_bytes_per_row.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    let buffer = renderer.device().create_buffer(&BufferDescriptor {
+      label: Some("frame capture buffer"),
+      size: (padded_bytes_per_row * height) as u64,
+      usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+      mapped_at_creation: false,
+    });
+
+    encoder.copy_texture_to_buffer(
+      ImageCopyTexture {
+        aspect: TextureAspect::All,
+        texture,
+        mip_level: 0,
+        origin: Origin3d::ZERO,
+      },
+      ImageCopyBuffer {
+        buffer: &buffer,
+        layout: ImageDataLayout {
+          offset: 0,
+          bytes_per_row: Some(padded_bytes_per_row),
+          rows_per_image: Some(height),
+        },
+      },
+      Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+      },
+    );
+
+    Ok(Self {
+      buffer,
+      width,
+      height,
+      padded_bytes_per_row,
+      swizzle,
+    })
+  }
+
+  /// Must be called after the command buffer holding the copy has been submitted.
+  pub(crate) async fn read(self, renderer: &Renderer) -> Result<FrameCapture, CaptureError> {
+    let slice = self.buffer.slice(..);
+
+    let (sender, receiver) = futures_channel::oneshot::channel();
+    slice.map_async(MapMode::Read, move |result| {
+      _ = sender.send(result);
+    });
+
+    renderer.device().poll(Maintain::Wait);
+
+    receiver
+      .await
+      .map_err(|_| CaptureError::Cancelled)?
+      .map_err(CaptureError::BufferAsync)?;
+
+    let unpadded_bytes_per_row = (4 * self.width) as usize;
+    let mut pixels = Vec::with_capacity(unpadded_bytes_per_row * self.height as usize);
+
+    {
+      let data = slice.get_mapped_range();
+
+      for row in data.chunks(self.padded_bytes_per_row as usize) {
+        pixels.extend_from_slice(&row[..unpadded_bytes_per_row]);
+      }
+    }
+
+    self.buffer.unmap();
+
+    if self.swizzle {
+      for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+      }
+    }
+
+    Ok(FrameCapture::new(self.width, self.height, pixels))
+  }
+}
@@ -0,0 +1,59 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+
+/// WGSL declaring the fog of the scene at group 0 binding 1, next to the camera, and
+/// `apply_fog(color, world_position)` blending a fragment towards the fog color. Prepend it to
+/// the fragment shader of a `ShaderMaterial` to fog it like the built-in materials.
+pub const FOG_SHADER: &str = include_str!("shaders/fog.wgsl");
+
+/// How fast the view fades into `Scene::fog_color` with the distance to the camera.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Fog {
+  /// No fog up to `near`, fully fogged from `far` on.
+  Linear { near: f32, far: f32 },
+  /// `1 - e^(-density * distance)`, thick right away.
+  Exponential { density: f32 },
+  /// `1 - e^(-(density * distance)^2)`, clear close to the camera then quickly thicker.
+  ExponentialSquared { density: f32 },
+}
+
+/// `Fog` in `fog.wgsl`.
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+pub(crate) struct FogBuffer {
+  color: Vec3,
+  mode: u32,
+  camera_position: Vec3,
+  density: f32,
+  near: f32,
+  far: f32,
+  height_falloff: f32,
+  _padding: f32,
+}
+
+impl FogBuffer {
+  pub(crate) fn new(
+    fog: Option<Fog>,
+    color: Vec3,
+    height_falloff: f32,
+    camera_position: Vec3,
+  ) -> Self {
+    let (mode, density, near, far) = match fog {
+      None => (0, 0.0, 0.0, 0.0),
+      Some(Fog::Linear { near, far }) => (1, 0.0, near, far),
+      Some(Fog::Exponential { density }) => (2, density, 0.0, 0.0),
+      Some(Fog::ExponentialSquared { density }) => (3, density, 0.0, 0.0),
+    };
+
+    Self {
+      color,
+      mode,
+      camera_position,
+      density,
+      near,
+      far,
+      height_falloff,
+      _padding: 0.0,
+    }
+  }
+}
@@ -0,0 +1,346 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+  include_wgsl,
+  util::{BufferInitDescriptor, DeviceExt},
+  AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+  BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, BufferBindingType,
+  BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder, Extent3d, FragmentState,
+  LoadOp, MultisampleState, Operations, PipelineLayoutDescriptor, PrimitiveState,
+  RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor,
+  Sampler, SamplerBindingType, ShaderModule, ShaderStages, StoreOp, Texture, TextureDescriptor,
+  TextureDimension, TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor,
+  TextureViewDimension, VertexState,
+};
+
+use crate::{
+  renderer::Renderer,
+  skybox::{face_view, linear_sampler},
+};
+
+const IRRADIANCE_SIZE: u32 = 32;
+const IRRADIANCE_SAMPLES: u32 = 256;
+const PREFILTERED_SIZE: u32 = 128;
+/// Levels of the prefiltered map, from a mirror down to a roughness of one.
+const PREFILTERED_LEVELS: u32 = 6;
+const PREFILTERED_SAMPLES: u32 = 128;
+const BRDF_LUT_SIZE: u32 = 64;
+
+/// Parameters of a single bake, see `Bake` in `ibl.wgsl`.
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+struct BakeBuffer {
+  roughness: f32,
+  source_size: f32,
+  target_size: f32,
+  sample_count: u32,
+}
+
+/// Diffuse irradiance, specular prefiltered by roughness and split sum BRDF lighting the PBR
+/// materials from the environment.
+pub(crate) struct EnvironmentLighting {
+  irradiance: Texture,
+  prefiltered: Texture,
+  brdf_lut: Texture,
+  pub(crate) irradiance_view: TextureView,
+  pub(crate) prefiltered_view: TextureView,
+  pub(crate) brdf_lut_view: TextureView,
+  pub(crate) sampler: Sampler,
+}
+
+fn create_texture(renderer: &Renderer, label: &str, size: Extent3d, levels: u32) -> Texture {
+  renderer.device().create_texture(&TextureDescriptor {
+    label: Some(label),
+    size,
+    mip_level_count: levels,
+    sample_count: 1,
+    dimension: TextureDimension::D2,
+    // clamped to `[0, 1]` like the scene where the adapter can't render into `Rgba16Float`
+    format: *renderer.hdr_format(),
+    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+    view_formats: &[],
+  })
+}
+
+fn create_cube(renderer: &Renderer, label: &str, size: u32, levels: u32) -> Texture {
+  create_texture(
+    renderer,
+    label,
+    Extent3d {
+      width: size,
+      height: size,
+      depth_or_array_layers: 6,
+    },
+    levels,
+  )
+}
+
+fn cube_view(texture: &Texture) -> TextureView {
+  texture.create_view(&TextureViewDescriptor {
+    label: Some("environment lighting view"),
+    dimension: Some(TextureViewDimension::Cube),
+    ..Default::default()
+  })
+}
+
+fn create_pipeline(
+  renderer: &Renderer,
+  shader: &ShaderModule,
+  layouts: &[&BindGroupLayout],
+  entry_point: &str,
+) -> RenderPipeline {
+  renderer
+    .device()
+    .create_render_pipeline(&RenderPipelineDescriptor {
+      label: Some(entry_point),
+      layout: Some(
+        &renderer
+          .device()
+          .create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("environment lighting pipeline layout"),
+            bind_group_layouts: layouts,
+            push_constant_ranges: &[],
+          }),
+      ),
+      vertex: VertexState {
+        module: shader,
+        entry_point: "vs_main",
+        buffers: &[],
+        compilation_options: Default::default(),
+      },
+      primitive: PrimitiveState::default(),
+      depth_stencil: None,
+      multisample: MultisampleState::default(),
+      fragment: Some(FragmentState {
+        module: shader,
+        entry_point,
+        targets: &[Some(ColorTargetState {
+          format: *renderer.hdr_format(),
+          blend: None,
+          write_mask: ColorWrites::ALL,
+        })],
+        compilation_options: Default::default(),
+      }),
+      multiview: None,
+      cache: None,
+    })
+}
+
+/// Draws `face`, picked from the vertex index by the shader, into `target`.
+fn draw_face(
+  encoder: &mut CommandEncoder,
+  pipeline: &RenderPipeline,
+  bind_group: Option<&BindGroup>,
+  target: &TextureView,
+  face: u32,
+) {
+  let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+    label: Some("environment lighting pass"),
+    color_attachments: &[Some(RenderPassColorAttachment {
+      view: target,
+      resolve_target: None,
+      ops: Operations {
+        load: LoadOp::Clear(Color::BLACK),
+        store: StoreOp::Store,
+      },
+    })],
+    ..Default::default()
+  });
+
+  render_pass.set_pipeline(pipeline);
+  if let Some(bind_group) = bind_group {
+    render_pass.set_bind_group(0, bind_group, &[]);
+  }
+  render_pass.draw(face * 3..face * 3 + 3, 0..1);
+}
+
+impl EnvironmentLighting {
+  /// Black maps lighting nothing, and the BRDF which doesn't depend on the environment.
+  pub(crate) fn new(renderer: &Renderer) -> Self {
+    let brdf_lut = create_texture(
+      renderer,
+      "brdf lut",
+      Extent3d {
+        width: BRDF_LUT_SIZE,
+        height: BRDF_LUT_SIZE,
+        depth_or_array_layers: 1,
+      },
+      1,
+    );
+    let brdf_lut_view = brdf_lut.create_view(&Default::default());
+
+    let shader = renderer
+      .device()
+      .create_shader_module(include_wgsl!("shaders/ibl.wgsl"));
+    let pipeline = create_pipeline(renderer, &shader, &[], "fs_brdf");
+
+    let mut encoder = renderer
+      .device()
+      .create_command_encoder(&Default::default());
+    draw_face(&mut encoder, &pipeline, None, &brdf_lut_view, 0);
+    renderer.queue().submit([encoder.finish()]);
+
+    let irradiance = create_cube(renderer, "irradiance", 1, 1);
+    let prefiltered = create_cube(renderer, "prefiltered", 1, 1);
+
+    Self {
+      irradiance_view: cube_view(&irradiance),
+      prefiltered_view: cube_view(&prefiltered),
+      brdf_lut_view,
+      sampler: linear_sampler(
+        renderer,
+        "environment lighting sampler",
+        AddressMode::ClampToEdge,
+      ),
+      irradiance,
+      prefiltered,
+      brdf_lut,
+    }
+  }
+
+  /// Bakes the irradiance and prefiltered maps from the mip chain of an environment cubemap
+  /// whose faces are `source_size` wide.
+  pub(crate) fn bake(&mut self, renderer: &Renderer, environment: &TextureView, source_size: u32) {
+    let layout = renderer
+      .device()
+      .create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("environment lighting bake layout"),
+        entries: &[
+          BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+              multisampled: false,
+              view_dimension: TextureViewDimension::Cube,
+              sample_type: TextureSampleType::Float { filterable: true },
+            },
+            count: None,
+          },
+          BindGroupLayoutEntry {
+            binding: 1,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Sampler(SamplerBindingType::Filtering),
+            count: None,
+          },
+          BindGroupLayoutEntry {
+            binding: 2,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+              ty: BufferBindingType::Uniform,
+              has_dynamic_offset: false,
+              min_binding_size: None,
+            },
+            count: None,
+          },
+        ],
+      });
+
+    let bind_group = |bake: BakeBuffer| {
+      let buffer = renderer.device().create_buffer_init(&BufferInitDescriptor {
+        label: Some("environment lighting bake buffer"),
+        contents: bytemuck::cast_slice(&[bake]),
+        usage: BufferUsages::UNIFORM,
+      });
+
+      renderer.device().create_bind_group(&BindGroupDescriptor {
+        label: Some("environment lighting bake bind group"),
+        layout: &layout,
+        entries: &[
+          BindGroupEntry {
+            binding: 0,
+            resource: BindingResource::TextureView(environment),
+          },
+          BindGroupEntry {
+            binding: 1,
+            resource: BindingResource::Sampler(&self.sampler),
+          },
+          BindGroupEntry {
+            binding: 2,
+            resource: buffer.as_entire_binding(),
+          },
+        ],
+      })
+    };
+
+    let shader = renderer
+      .device()
+      .create_shader_module(include_wgsl!("shaders/ibl.wgsl"));
+
+    let irradiance = create_cube(renderer, "irradiance", IRRADIANCE_SIZE, 1);
+    // never sharper than the environment itself
+    let prefiltered_size = PREFILTERED_SIZE.min(source_size).max(1);
+    let prefiltered_levels = PREFILTERED_LEVELS.min(prefiltered_size.ilog2() + 1);
+    let prefiltered = create_cube(
+      renderer,
+      "prefiltered",
+      prefiltered_size,
+      prefiltered_levels,
+    );
+
+    let mut encoder = renderer
+      .device()
+      .create_command_encoder(&Default::default());
+
+    let pipeline = create_pipeline(renderer, &shader, &[&layout], "fs_irradiance");
+    let irradiance_bind_group = bind_group(BakeBuffer {
+      roughness: 1.0,
+      source_size: source_size as f32,
+      target_size: IRRADIANCE_SIZE as f32,
+      sample_count: IRRADIANCE_SAMPLES,
+    });
+
+    for face in 0..6 {
+      draw_face(
+        &mut encoder,
+        &pipeline,
+        Some(&irradiance_bind_group),
+        &face_view(&irradiance, face, 0),
+        face,
+      );
+    }
+
+    let pipeline = create_pipeline(renderer, &shader, &[&layout], "fs_prefiltered");
+
+    for level in 0..prefiltered_levels {
+      let level_bind_group = bind_group(BakeBuffer {
+        roughness: level as f32 / (prefiltered_levels - 1).max(1) as f32,
+        source_size: source_size as f32,
+        target_size: (prefiltered_size >> level) as f32,
+        sample_count: PREFILTERED_SAMPLES,
+      });
+
+      for face in 0..6 {
+        draw_face(
+          &mut encoder,
+          &pipeline,
+          Some(&level_bind_group),
+          &face_view(&prefiltered, face, level),
+          face,
+        );
+      }
+    }
+
+    renderer.queue().submit([encoder.finish()]);
+
+    self.irradiance_view = cube_view(&irradiance);
+    self.prefiltered_view = cube_view(&prefiltered);
+    self.irradiance = irradiance;
+    self.prefiltered = prefiltered;
+  }
+
+  /// Back to black maps once the environment is removed.
+  pub(crate) fn clear(&mut self, renderer: &Renderer) {
+    self.irradiance = create_cube(renderer, "irradiance", 1, 1);
+    self.prefiltered = create_cube(renderer, "prefiltered", 1, 1);
+    self.irradiance_view = cube_view(&self.irradiance);
+    self.prefiltered_view = cube_view(&self.prefiltered);
+  }
+
+  /// Level of the prefiltered map reflecting a roughness of one.
+  pub(crate) fn max_level(&self) -> f32 {
+    (self.prefiltered.mip_level_count() - 1) as f32
+  }
+
+  pub(crate) fn textures(&self) -> [&Texture; 3] {
+    [&self.irradiance, &self.prefiltered, &self.brdf_lut]
+  }
+}
@@ -1,5 +1,20 @@
-pub use glam::{Quat, Vec2, Vec3, Vec4};
+pub use glam::{Mat4, Quat, Vec2, Vec3, Vec4};
 
+pub mod bounds;
+pub mod capture;
+pub mod fog;
+mod ibl;
+pub mod light_storage;
+mod ltc;
+pub mod picking;
+pub mod post_processing;
+pub mod raycast;
 pub mod renderer;
 pub mod resource;
 pub mod scene;
+mod shadow;
+mod skybox;
+pub mod stats;
+
+#[cfg(feature = "test_support")]
+pub mod test_support;
@@ -0,0 +1,207 @@
+use std::{borrow::Cow, fmt, mem::size_of};
+
+use bytemuck::Pod;
+use wgpu::{
+  util::{BufferInitDescriptor, DeviceExt},
+  Buffer, BufferBindingType, BufferUsages, ShaderModuleDescriptor, ShaderSource,
+};
+
+use crate::renderer::Renderer;
+
+/// WGSL declaring the lights, their arrays at group 5 and the shadow sampling and rect area
+/// integration the phong and pbr fragment shaders share.
+pub(crate) const LIGHTS_SHADER: &str = include_str!("shaders/lights.wgsl");
+
+/// Lights of each kind that fit into the uniform arrays declared by the shaders.
+pub const MAX_UNIFORM_LIGHTS: usize = 16;
+
+/// Names and element types of the light arrays declared by the lit shaders, as
+/// `var<uniform> name: array<Type, 16>;` so they stay valid WGSL without storage buffers.
+const LIGHT_ARRAYS: [(&str, &str); 4] = [
+  ("directional_lights", "DirectionalLight"),
+  ("point_lights", "PointLight"),
+  ("spot_lights", "SpotLight"),
+  ("rect_area_lights", "RectAreaLight"),
+];
+
+/// How the directional, point, spot and rect area lights are bound to the shaders.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightStorage {
+  /// Read only storage buffers growing with the number of lights.
+  Storage,
+  /// Arrays of `MAX_UNIFORM_LIGHTS` uniforms, used where storage buffers are unavailable such as
+  /// on WebGL.
+  Uniform,
+}
+
+impl LightStorage {
+  pub(crate) fn for_renderer(renderer: &Renderer) -> Self {
+    if renderer
+      .device()
+      .limits()
+      .max_storage_buffers_per_shader_stage
+      >= LIGHT_ARRAYS.len() as u32
+    {
+      Self::Storage
+    } else {
+      Self::Uniform
+    }
+  }
+
+  /// Maximum number of lights of each kind, `None` when only limited by memory.
+  pub fn max_lights(&self) -> Option<usize> {
+    match self {
+      Self::Storage => None,
+      Self::Uniform => Some(MAX_UNIFORM_LIGHTS),
+    }
+  }
+
+  pub(crate) fn binding_type(&self) -> BufferBindingType {
+    match self {
+      Self::Storage => BufferBindingType::Storage { read_only: true },
+      Self::Uniform => BufferBindingType::Uniform,
+    }
+  }
+
+  fn usage(&self) -> BufferUsages {
+    match self {
+      Self::Storage => BufferUsages::STORAGE | BufferUsages::COPY_DST,
+      Self::Uniform => BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+    }
+  }
+
+  /// Creates a zeroed buffer holding `capacity` lights of type `T`.
+  pub(crate) fn create_buffer<T: Pod + Default>(
+    &self,
+    renderer: &Renderer,
+    label: &str,
+    capacity: usize,
+  ) -> Buffer {
+    renderer.device().create_buffer_init(&BufferInitDescriptor {
+      label: Some(label),
+      contents: bytemuck::cast_slice(&vec![T::default(); capacity]),
+      usage: self.usage(),
+    })
+  }
+
+  /// Makes room for `count` lights of type `T` in `buffer`, returns true when it was reallocated
+  /// and has to be bound again. Uniform buffers never grow.
+  pub(crate) fn reserve<T: Pod + Default>(
+    &self,
+    renderer: &Renderer,
+    buffer: &mut Buffer,
+    label: &str,
+    count: usize,
+  ) -> bool {
+    if *self == Self::Uniform || count <= capacity::<T>(buffer) {
+      return false;
+    }
+
+    *buffer = self.create_buffer::<T>(renderer, label, count.next_power_of_two());
+    true
+  }
+
+  /// Rewrites the light arrays declared by a WGSL shader to match the binding type.
+  pub(crate) fn shader(
+    &self,
+    descriptor: &ShaderModuleDescriptor<'static>,
+  ) -> Result<ShaderModuleDescriptor<'static>, LightArrayError> {
+    let ShaderSource::Wgsl(source) = &descriptor.source else {
+      return Ok(descriptor.clone());
+    };
+
+    let mut source = source.to_string();
+    let mut declared = 0;
+
+    for (name, element) in LIGHT_ARRAYS {
+      let uniform = format!("var<uniform> {name}: array<{element}, {MAX_UNIFORM_LIGHTS}>;");
+
+      if source.contains(&uniform) {
+        source = source.replace(
+          &uniform,
+          &format!("var<storage, read> {name}: array<{element}>;"),
+        );
+        declared += 1;
+      }
+
+      if source.contains(&format!("var<uniform> {name}")) {
+        return Err(LightArrayError::Malformed(name));
+      }
+    }
+
+    // the lit shaders read every kind of light, the others none of them
+    if declared != 0 && declared != LIGHT_ARRAYS.len() {
+      return Err(LightArrayError::Partial { declared });
+    }
+
+    if *self == Self::Uniform {
+      return Ok(descriptor.clone());
+    }
+
+    Ok(ShaderModuleDescriptor {
+      label: descriptor.label,
+      source: ShaderSource::Wgsl(Cow::Owned(source)),
+    })
+  }
+}
+
+/// Number of lights of type `T` that fit into `buffer`.
+pub(crate) fn capacity<T>(buffer: &Buffer) -> usize {
+  buffer.size() as usize / size_of::<T>()
+}
+
+/// Kinds of lights bound in arrays, in the order of their counts in the shaders.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightKind {
+  Directional,
+  Point,
+  Spot,
+  RectArea,
+}
+
+/// More lights were bound than `LightStorage::Uniform` holds, only the first `max` are lit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooManyLights {
+  pub kind: LightKind,
+  pub count: usize,
+  pub max: usize,
+}
+
+impl fmt::Display for TooManyLights {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} {:?} lights were bound but only {} are supported without storage buffers, the rest are ignored",
+      self.count, self.kind, self.max
+    )
+  }
+}
+
+impl std::error::Error for TooManyLights {}
+
+/// A fragment shader declares the light arrays in a form `LightStorage` can't bind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightArrayError {
+  /// The array isn't declared as `var<uniform> name: array<Type, 16>;`.
+  Malformed(&'static str),
+  /// Only `declared` of the light arrays are declared, lit shaders declare all of them.
+  Partial { declared: usize },
+}
+
+impl fmt::Display for LightArrayError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Malformed(name) => write!(
+        f,
+        "`{name}` has to be declared as `var<uniform> {name}: array<Type, {MAX_UNIFORM_LIGHTS}>;`"
+      ),
+      Self::Partial { declared } => write!(
+        f,
+        "a shader declares only {declared} of the {} light arrays",
+        LIGHT_ARRAYS.len()
+      ),
+    }
+  }
+}
+
+impl std::error::Error for LightArrayError {}
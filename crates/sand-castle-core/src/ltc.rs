@@ -0,0 +1,84 @@
+use wgpu::{
+  AddressMode, Extent3d, FilterMode, ImageCopyTexture, ImageDataLayout, Origin3d, Sampler,
+  SamplerDescriptor, Texture, TextureAspect, TextureDescriptor, TextureDimension, TextureFormat,
+  TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
+};
+
+use crate::renderer::Renderer;
+
+const LTC_SIZE: u32 = 64;
+
+/// Two `Rgba16Float` layers fitted by the `fit_ltc` example: the inverse matrices and the BRDF
+/// norm with its Fresnel part.
+const LTC_TABLES: &[u8] = include_bytes!("shaders/ltc.bin");
+
+/// Linearly transformed cosine tables shading the rect area lights.
+pub(crate) struct LtcTables {
+  texture: Texture,
+  pub(crate) view: TextureView,
+  pub(crate) sampler: Sampler,
+}
+
+impl LtcTables {
+  pub(crate) fn new(renderer: &Renderer) -> Self {
+    let size = Extent3d {
+      width: LTC_SIZE,
+      height: LTC_SIZE,
+      depth_or_array_layers: 2,
+    };
+
+    let texture = renderer.device().create_texture(&TextureDescriptor {
+      label: Some("ltc tables"),
+      size,
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: TextureDimension::D2,
+      format: TextureFormat::Rgba16Float,
+      usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+      view_formats: &[],
+    });
+
+    renderer.queue().write_texture(
+      ImageCopyTexture {
+        aspect: TextureAspect::All,
+        texture: &texture,
+        mip_level: 0,
+        origin: Origin3d::ZERO,
+      },
+      LTC_TABLES,
+      ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(8 * LTC_SIZE),
+        rows_per_image: Some(LTC_SIZE),
+      },
+      size,
+    );
+
+    let view = texture.create_view(&TextureViewDescriptor {
+      label: Some("ltc tables view"),
+      dimension: Some(TextureViewDimension::D2Array),
+      ..Default::default()
+    });
+
+    let sampler = renderer.device().create_sampler(&SamplerDescriptor {
+      label: Some("ltc sampler"),
+      address_mode_u: AddressMode::ClampToEdge,
+      address_mode_v: AddressMode::ClampToEdge,
+      address_mode_w: AddressMode::ClampToEdge,
+      mag_filter: FilterMode::Linear,
+      min_filter: FilterMode::Linear,
+      mipmap_filter: FilterMode::Nearest,
+      ..Default::default()
+    });
+
+    Self {
+      texture,
+      view,
+      sampler,
+    }
+  }
+
+  pub(crate) fn texture(&self) -> &Texture {
+    &self.texture
+  }
+}
@@ -0,0 +1,395 @@
+use getset::Getters;
+use glam::{Mat4, UVec4, Vec2, Vec3};
+use wgpu::{
+  include_wgsl,
+  util::{BufferInitDescriptor, DeviceExt},
+  BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+  BindGroupLayoutEntry, BindingType, Buffer, BufferAsyncError, BufferBindingType, BufferDescriptor,
+  BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder, CompareFunction,
+  DepthBiasState, DepthStencilState, Extent3d, FragmentState, FrontFace, ImageCopyBuffer,
+  ImageCopyTexture, ImageDataLayout, LoadOp, Maintain, MapMode, MultisampleState, Operations,
+  Origin3d, PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, RenderPass,
+  RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor,
+  RenderPipeline, RenderPipelineDescriptor, ShaderStages, StencilState, StoreOp, Texture,
+  TextureAspect, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
+  TextureViewDescriptor, VertexState, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+use crate::{
+  renderer::Renderer,
+  resource::{geometry::Geometry, Id},
+};
+
+/// Object found under a pixel by `Scene::pick`.
+#[derive(Getters, Clone, Copy, Debug, PartialEq)]
+#[getset(get = "pub")]
+pub struct PickResult {
+  id: Id,
+  /// Normalized device depth of the picked surface, `0.0` on the near plane and `1.0` on the far
+  /// one.
+  depth: f32,
+  /// World space position of the picked surface.
+  position: Vec3,
+}
+
+#[derive(Debug)]
+pub enum PickError {
+  /// `Scene::enable_picking` was never called.
+  NotEnabled,
+  BufferAsync(BufferAsyncError),
+  Cancelled,
+}
+
+impl PickResult {
+  pub(crate) fn new(id: Id, depth: f32, position: Vec3) -> Self {
+    Self {
+      id,
+      depth,
+      position,
+    }
+  }
+}
+
+/// Value written into the id target for a subject, `0` is left where nothing was drawn.
+pub(crate) struct PickIndex {
+  pub(crate) index: u32,
+  // kept alive alongside the bind group reading it
+  _buffer: Buffer,
+  pub(crate) bind_group: BindGroup,
+}
+
+/// Renders subject indices and depth into a single pixel, the camera is zoomed onto the picked
+/// pixel so the targets never have to follow the size of the scene.
+pub(crate) struct Picker {
+  pipeline: RenderPipeline,
+  camera: (Buffer, BindGroup),
+  index_layout: BindGroupLayout,
+  ids: Texture,
+  ids_view: TextureView,
+  depth_bits: Texture,
+  depth_bits_view: TextureView,
+  depth_view: TextureView,
+  next_index: u32,
+}
+
+const ID_FORMAT: TextureFormat = TextureFormat::R32Uint;
+
+/// Center of pixel `(x, y)` of a `size` target in normalized device coordinates.
+pub(crate) fn pixel_ndc((width, height): (u32, u32), x: u32, y: u32) -> Vec2 {
+  Vec2::new(
+    (x as f32 + 0.5) / width as f32 * 2.0 - 1.0,
+    1.0 - (y as f32 + 0.5) / height as f32 * 2.0,
+  )
+}
+
+fn pixel_texture(renderer: &Renderer, label: &str, format: TextureFormat) -> Texture {
+  renderer.device().create_texture(&TextureDescriptor {
+    label: Some(label),
+    size: Extent3d {
+      width: 1,
+      height: 1,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: TextureDimension::D2,
+    format,
+    usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+    view_formats: &[],
+  })
+}
+
+fn uniform_layout(renderer: &Renderer, label: &str, visibility: ShaderStages) -> BindGroupLayout {
+  renderer
+    .device()
+    .create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some(label),
+      entries: &[BindGroupLayoutEntry {
+        binding: 0,
+        visibility,
+        ty: BindingType::Buffer {
+          ty: BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
+      }],
+    })
+}
+
+impl Picker {
+  pub(crate) fn new(renderer: &Renderer) -> Self {
+    // the fog bound next to the camera of the scene is left out, nothing is shaded here
+    let camera_layout = uniform_layout(
+      renderer,
+      "picking camera layout",
+      ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+    );
+    // matches the transform layout of the scene, the lit materials read its flags
+    let transform_layout = uniform_layout(
+      renderer,
+      "picking transform layout",
+      ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+    );
+    let index_layout = uniform_layout(renderer, "picking index layout", ShaderStages::FRAGMENT);
+
+    let camera_buffer = renderer.device().create_buffer_init(&BufferInitDescriptor {
+      label: Some("picking camera buffer"),
+      contents: bytemuck::cast_slice(&[0.0f32; 4 * 5]),
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+    });
+
+    let camera_bind_group = renderer.device().create_bind_group(&BindGroupDescriptor {
+      label: Some("picking camera bind group"),
+      layout: &camera_layout,
+      entries: &[BindGroupEntry {
+        binding: 0,
+        resource: camera_buffer.as_entire_binding(),
+      }],
+    });
+
+    let pipeline_layout = renderer
+      .device()
+      .create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("picking pipeline layout"),
+        bind_group_layouts: &[&camera_layout, &transform_layout, &index_layout],
+        push_constant_ranges: &[],
+      });
+
+    let shader = renderer
+      .device()
+      .create_shader_module(include_wgsl!("shaders/picking.wgsl"));
+
+    let target = Some(ColorTargetState {
+      format: ID_FORMAT,
+      blend: None,
+      write_mask: ColorWrites::ALL,
+    });
+
+    let pipeline = renderer
+      .device()
+      .create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("picking pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[Geometry::vertex_desc()],
+          compilation_options: Default::default(),
+        },
+        primitive: PrimitiveState {
+          topology: PrimitiveTopology::TriangleList,
+          strip_index_format: None,
+          front_face: FrontFace::Ccw,
+          // double sided materials have to be pickable from behind, like in `Scene::raycast`
+          cull_mode: None,
+          polygon_mode: PolygonMode::Fill,
+          unclipped_depth: false,
+          conservative: false,
+        },
+        depth_stencil: Some(DepthStencilState {
+          format: TextureFormat::Depth32Float,
+          depth_write_enabled: true,
+          depth_compare: CompareFunction::Less,
+          stencil: StencilState::default(),
+          bias: DepthBiasState::default(),
+        }),
+        multisample: MultisampleState::default(),
+        fragment: Some(FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          compilation_options: Default::default(),
+          targets: &[target.clone(), target],
+        }),
+        multiview: None,
+        cache: None,
+      });
+
+    let ids = pixel_texture(renderer, "picking id texture", ID_FORMAT);
+    let depth_bits = pixel_texture(renderer, "picking depth bits texture", ID_FORMAT);
+    let depth = pixel_texture(
+      renderer,
+      "picking depth texture",
+      TextureFormat::Depth32Float,
+    );
+
+    Self {
+      pipeline,
+      camera: (camera_buffer, camera_bind_group),
+      index_layout,
+      ids_view: ids.create_view(&TextureViewDescriptor::default()),
+      ids,
+      depth_bits_view: depth_bits.create_view(&TextureViewDescriptor::default()),
+      depth_bits,
+      depth_view: depth.create_view(&TextureViewDescriptor::default()),
+      next_index: 1,
+    }
+  }
+
+  pub(crate) fn create_index(&mut self, renderer: &Renderer) -> PickIndex {
+    let index = self.next_index;
+    self.next_index += 1;
+
+    let buffer = renderer.device().create_buffer_init(&BufferInitDescriptor {
+      label: Some("picking index buffer"),
+      contents: bytemuck::cast_slice(&[UVec4::new(index, 0, 0, 0)]),
+      usage: BufferUsages::UNIFORM,
+    });
+
+    let bind_group = renderer.device().create_bind_group(&BindGroupDescriptor {
+      label: Some("picking index bind group"),
+      layout: &self.index_layout,
+      entries: &[BindGroupEntry {
+        binding: 0,
+        resource: buffer.as_entire_binding(),
+      }],
+    });
+
+    PickIndex {
+      index,
+      _buffer: buffer,
+      bind_group,
+    }
+  }
+
+  /// Points the picking camera at pixel `(x, y)` of a `size` target seen through
+  /// `view_projection`.
+  pub(crate) fn aim(
+    &self,
+    renderer: &Renderer,
+    view_projection: Mat4,
+    size: (u32, u32),
+    x: u32,
+    y: u32,
+  ) {
+    let ndc = pixel_ndc(size, x, y);
+
+    // scales the pixel up to the whole clip space, translations are multiplied by `w` to stay
+    // in clip space
+    let zoom = Mat4::from_scale(Vec3::new(size.0 as f32, size.1 as f32, 1.0))
+      * Mat4::from_translation(-ndc.extend(0.0));
+
+    renderer.queue().write_buffer(
+      &self.camera.0,
+      0,
+      bytemuck::cast_slice(&[zoom * view_projection]),
+    );
+  }
+
+  pub(crate) fn begin_pass<'a>(&'a self, encoder: &'a mut CommandEncoder) -> RenderPass<'a> {
+    let clear = Operations {
+      load: LoadOp::Clear(Color::TRANSPARENT),
+      store: StoreOp::Store,
+    };
+
+    let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+      label: Some("picking render pass"),
+      color_attachments: &[
+        Some(RenderPassColorAttachment {
+          view: &self.ids_view,
+          resolve_target: None,
+          ops: clear,
+        }),
+        Some(RenderPassColorAttachment {
+          view: &self.depth_bits_view,
+          resolve_target: None,
+          ops: clear,
+        }),
+      ],
+      depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+        view: &self.depth_view,
+        depth_ops: Some(Operations {
+          load: LoadOp::Clear(1.0),
+          store: StoreOp::Discard,
+        }),
+        stencil_ops: None,
+      }),
+      timestamp_writes: None,
+      occlusion_query_set: None,
+    });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, &self.camera.1, &[]);
+
+    render_pass
+  }
+
+  /// Copies the picked pixel into a mappable buffer, the id is at offset `0` and the depth at
+  /// `COPY_BYTES_PER_ROW_ALIGNMENT`.
+  pub(crate) fn encode_readback(
+    &self,
+    renderer: &Renderer,
+    encoder: &mut CommandEncoder,
+  ) -> PickReadback {
+    let buffer = renderer.device().create_buffer(&BufferDescriptor {
+      label: Some("picking readback buffer"),
+      size: 2 * COPY_BYTES_PER_ROW_ALIGNMENT as u64,
+      usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+      mapped_at_creation: false,
+    });
+
+    for (index, texture) in [&self.ids, &self.depth_bits].into_iter().enumerate() {
+      encoder.copy_texture_to_buffer(
+        ImageCopyTexture {
+          aspect: TextureAspect::All,
+          texture,
+          mip_level: 0,
+          origin: Origin3d::ZERO,
+        },
+        ImageCopyBuffer {
+          buffer: &buffer,
+          layout: ImageDataLayout {
+            offset: index as u64 * COPY_BYTES_PER_ROW_ALIGNMENT as u64,
+            bytes_per_row: Some(COPY_BYTES_PER_ROW_ALIGNMENT),
+            rows_per_image: Some(1),
+          },
+        },
+        Extent3d {
+          width: 1,
+          height: 1,
+          depth_or_array_layers: 1,
+        },
+      );
+    }
+
+    PickReadback { buffer }
+  }
+}
+
+pub(crate) struct PickReadback {
+  buffer: Buffer,
+}
+
+impl PickReadback {
+  /// Subject index and depth of the picked pixel, must be called after the command buffer
+  /// holding the copy has been submitted.
+  pub(crate) async fn read(self, renderer: &Renderer) -> Result<(u32, f32), PickError> {
+    let slice = self.buffer.slice(..);
+
+    let (sender, receiver) = futures_channel::oneshot::channel();
+    slice.map_async(MapMode::Read, move |result| {
+      _ = sender.send(result);
+    });
+
+    renderer.device().poll(Maintain::Wait);
+
+    receiver
+      .await
+      .map_err(|_| PickError::Cancelled)?
+      .map_err(PickError::BufferAsync)?;
+
+    let (index, depth) = {
+      let data = slice.get_mapped_range();
+      let offset = COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+
+      (
+        bytemuck::pod_read_unaligned::<u32>(&data[..4]),
+        bytemuck::pod_read_unaligned::<f32>(&data[offset..offset + 4]),
+      )
+    };
+
+    self.buffer.unmap();
+
+    Ok((index, depth))
+  }
+}
@@ -0,0 +1,113 @@
+use std::borrow::Cow;
+
+use derive_builder::Builder;
+use getset::Getters;
+
+use crate::resource::texture::TextureId;
+
+pub mod bloom;
+pub(crate) mod chain;
+pub mod color_grading;
+pub mod fxaa;
+pub mod sharpen;
+pub mod tone_mapping;
+pub mod vignette;
+
+/// WGSL put in front of the shader of every `PostPass`. It declares the fullscreen `vs_main`
+/// and its `PostVertexOutput`, along with:
+///
+/// - `previous`, the output of the pass before in the same effect, or `source` for the first one
+/// - `source`, the input of the effect
+/// - `post_sampler`, a linear sampler clamping to the edges
+/// - `post_frame`, the pixel size of the target and the texel size of `previous`
+/// - `lookup`, the texture of the pass at group 1 binding 1
+///
+/// The uniform of the pass is left to the shader to declare at group 1 binding 0.
+pub const PRELUDE: &str = include_str!("post_processing/shaders/prelude.wgsl");
+
+/// A fullscreen pass of a `PostEffect`.
+#[derive(Builder, Getters, Clone, Debug, PartialEq)]
+#[getset(get = "pub")]
+#[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
+pub struct PostPass {
+  /// WGSL with an `fs_main` fragment entry point, appended to `PRELUDE`.
+  #[builder(setter(into))]
+  shader: Cow<'static, str>,
+  /// Content of the uniform at group 1 binding 0, padded to 16 bytes.
+  #[builder(default)]
+  uniforms: Vec<u8>,
+  /// Bound as `lookup`, a blank texel when `None`.
+  #[builder(default)]
+  texture: Option<TextureId>,
+  /// Size of the target relative to the frame. The last pass of an effect always covers the
+  /// whole frame.
+  #[builder(default = "1.0")]
+  scale: f32,
+}
+
+impl PostPass {
+  pub fn builder() -> PostPassBuilder {
+    Default::default()
+  }
+}
+
+impl PostPassBuilder {
+  pub fn build(self) -> PostPass {
+    self.fallible_build().expect("could not build `PostPass`")
+  }
+}
+
+/// Whether an effect runs before or after the tone mapping.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PostStage {
+  /// Sees the linear HDR colors of the scene, like `Bloom`.
+  #[default]
+  Hdr,
+  /// Sees the tone mapped colors, still linear but within `[0, 1]`, like `Fxaa` or `Vignette`.
+  Ldr,
+}
+
+/// Stage of the post processing chain, applied by `Scene::set_post_processing` to the rendered
+/// frame. The frame is tone mapped between the last `PostStage::Hdr` effect and the first
+/// `PostStage::Ldr` one.
+pub trait PostEffect {
+  /// Passes drawn in order, the last one produces the output of the effect.
+  fn passes(&self) -> Vec<PostPass>;
+
+  /// `PostStage::Hdr` unless the effect expects displayable colors.
+  fn stage(&self) -> PostStage {
+    PostStage::Hdr
+  }
+}
+
+/// A single pass is an effect of its own.
+impl PostEffect for PostPass {
+  fn passes(&self) -> Vec<PostPass> {
+    vec![self.clone()]
+  }
+}
+
+/// Passes of a custom effect, drawn in order.
+impl PostEffect for Vec<PostPass> {
+  fn passes(&self) -> Vec<PostPass> {
+    self.clone()
+  }
+}
+
+/// Nothing is drawn for `None`, e.g. while waiting on a texture.
+impl<Effect: PostEffect> PostEffect for Option<Effect> {
+  fn passes(&self) -> Vec<PostPass> {
+    self.as_ref().map(Effect::passes).unwrap_or_default()
+  }
+
+  fn stage(&self) -> PostStage {
+    self.as_ref().map(Effect::stage).unwrap_or_default()
+  }
+}
+
+/// Why `Scene::set_post_processing` kept the previous effects.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostProcessingError {
+  /// The texture of a pass isn't in the `TextureLoader` yet.
+  MissingTexture(TextureId),
+}
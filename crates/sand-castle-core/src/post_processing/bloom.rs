@@ -0,0 +1,59 @@
+use derive_builder::Builder;
+use getset::{Getters, Setters};
+
+use super::{PostEffect, PostPass};
+
+/// Makes the brightest parts of the frame glow: they are extracted at half resolution, blurred
+/// and added back.
+#[derive(Builder, Getters, Setters, Clone, Copy, Debug, PartialEq)]
+#[getset(get = "pub", set = "pub")]
+#[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
+pub struct Bloom {
+  /// Brightness above which pixels glow.
+  #[builder(default = "0.8")]
+  threshold: f32,
+  /// Scale of the glow added to the frame.
+  #[builder(default = "1.0")]
+  intensity: f32,
+  /// Spread of the blur, in texels of the half resolution targets.
+  #[builder(default = "1.0")]
+  radius: f32,
+}
+
+impl Bloom {
+  pub fn builder() -> BloomBuilder {
+    Default::default()
+  }
+}
+
+impl BloomBuilder {
+  pub fn build(self) -> Bloom {
+    self.fallible_build().expect("could not build `Bloom`")
+  }
+}
+
+impl PostEffect for Bloom {
+  fn passes(&self) -> Vec<PostPass> {
+    let blur = |direction: [f32; 2]| {
+      PostPass::builder()
+        .shader(include_str!("shaders/blur.wgsl"))
+        .uniforms(bytemuck::cast_slice(&[direction[0], direction[1], self.radius]).to_vec())
+        .scale(0.5)
+        .build()
+    };
+
+    vec![
+      PostPass::builder()
+        .shader(include_str!("shaders/bright_pass.wgsl"))
+        .uniforms(bytemuck::cast_slice(&[self.threshold]).to_vec())
+        .scale(0.5)
+        .build(),
+      blur([1.0, 0.0]),
+      blur([0.0, 1.0]),
+      PostPass::builder()
+        .shader(include_str!("shaders/bloom.wgsl"))
+        .uniforms(bytemuck::cast_slice(&[self.intensity]).to_vec())
+        .build(),
+    ]
+  }
+}
@@ -0,0 +1,577 @@
+use std::{borrow::Cow, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use glam::Vec2;
+use indexmap::IndexMap;
+use wgpu::{
+  util::{BufferInitDescriptor, DeviceExt},
+  AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+  BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+  BufferBindingType, BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder, Extent3d,
+  FragmentState, ImageCopyTexture, ImageDataLayout, LoadOp, MultisampleState, Operations, Origin3d,
+  PipelineLayoutDescriptor, PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor,
+  RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerBindingType, ShaderModuleDescriptor,
+  ShaderSource, ShaderStages, StoreOp, Texture, TextureAspect, TextureDescriptor, TextureDimension,
+  TextureFormat, TextureSampleType, TextureUsages, TextureView, TextureViewDimension, VertexState,
+};
+
+use crate::{
+  renderer::Renderer,
+  resource::{
+    loader::textures::TextureLoader,
+    texture::{Texture as SourceTexture, TextureId},
+  },
+  skybox::linear_sampler,
+  stats::RenderStats,
+};
+
+use super::{tone_mapping::ToneMapping, PostPass, PostProcessingError, PostStage, PRELUDE};
+
+const TONE_MAPPING_SHADER: &str = include_str!("shaders/tone_mapping.wgsl");
+
+/// `PostFrame` in the prelude.
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+struct PostFrameBuffer {
+  size: Vec2,
+  texel: Vec2,
+}
+
+/// `ToneMapping` in `tone_mapping.wgsl`.
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+struct ToneMappingBuffer {
+  curve: u32,
+  exposure: f32,
+  encode_srgb: u32,
+  _padding: u32,
+}
+
+struct Target {
+  texture: Texture,
+  view: TextureView,
+}
+
+struct ChainPass {
+  pipeline: Arc<RenderPipeline>,
+  /// Uniform and lookup texture at group 1.
+  settings: BindGroup,
+  frame: Buffer,
+  scale: f32,
+  /// Indices into the targets of the chain, `0` is the rendered scene.
+  previous: usize,
+  source: usize,
+  /// Group 0, recreated with the targets.
+  inputs: Option<BindGroup>,
+}
+
+/// Effects of `Scene::set_post_processing` around the tone mapping: the `PostStage::Hdr` ones
+/// before it, the `PostStage::Ldr` ones after it followed by a pass encoding them into the frame.
+/// The scene is drawn into the first HDR target and every pass but the last one renders into a
+/// target of its own.
+pub(crate) struct PostChain {
+  frame_format: TextureFormat,
+  tone_mapping: Buffer,
+  /// Settings of the tone mapping shader when it only clamps and encodes the `PostStage::Ldr`
+  /// effects into the frame.
+  encoding: Buffer,
+  /// Whether the tone mapping renders into the frame, encoding it itself.
+  tone_mapping_last: bool,
+  inputs_layout: BindGroupLayout,
+  settings_layout: BindGroupLayout,
+  sampler: Sampler,
+  blank: TextureView,
+  /// Compiled once per shader and target format, kept while an effect uses them.
+  pipelines: IndexMap<(Cow<'static, str>, TextureFormat), Arc<RenderPipeline>>,
+  lookups: IndexMap<TextureId, Target>,
+  passes: Vec<ChainPass>,
+  targets: Vec<Target>,
+}
+
+fn texture_layout_entry(binding: u32) -> BindGroupLayoutEntry {
+  BindGroupLayoutEntry {
+    binding,
+    visibility: ShaderStages::FRAGMENT,
+    ty: BindingType::Texture {
+      multisampled: false,
+      view_dimension: TextureViewDimension::D2,
+      sample_type: TextureSampleType::Float { filterable: true },
+    },
+    count: None,
+  }
+}
+
+fn uniform_layout_entry(binding: u32) -> BindGroupLayoutEntry {
+  BindGroupLayoutEntry {
+    binding,
+    visibility: ShaderStages::FRAGMENT,
+    ty: BindingType::Buffer {
+      ty: BufferBindingType::Uniform,
+      has_dynamic_offset: false,
+      min_binding_size: None,
+    },
+    count: None,
+  }
+}
+
+fn create_target(renderer: &Renderer, label: &str, (width, height): (u32, u32)) -> Target {
+  let texture = renderer.device().create_texture(&TextureDescriptor {
+    label: Some(label),
+    size: Extent3d {
+      width: width.max(1),
+      height: height.max(1),
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: TextureDimension::D2,
+    format: *renderer.hdr_format(),
+    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+    view_formats: &[],
+  });
+
+  Target {
+    view: texture.create_view(&Default::default()),
+    texture,
+  }
+}
+
+impl PostChain {
+  pub(crate) fn new(renderer: &Renderer) -> Self {
+    let inputs_layout = renderer
+      .device()
+      .create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("post processing inputs layout"),
+        entries: &[
+          texture_layout_entry(0),
+          texture_layout_entry(1),
+          BindGroupLayoutEntry {
+            binding: 2,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Sampler(SamplerBindingType::Filtering),
+            count: None,
+          },
+          uniform_layout_entry(3),
+        ],
+      });
+
+    let settings_layout = renderer
+      .device()
+      .create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("post processing settings layout"),
+        entries: &[uniform_layout_entry(0), texture_layout_entry(1)],
+      });
+
+    let frame_format = renderer
+      .supported_format()
+      .unwrap_or(TextureFormat::Rgba8UnormSrgb);
+
+    let mut chain = Self {
+      frame_format,
+      tone_mapping: renderer.device().create_buffer_init(&BufferInitDescriptor {
+        label: Some("tone mapping buffer"),
+        contents: bytemuck::cast_slice(&[ToneMappingBuffer::zeroed()]),
+        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+      }),
+      encoding: renderer.device().create_buffer_init(&BufferInitDescriptor {
+        label: Some("post processing encoding buffer"),
+        contents: bytemuck::cast_slice(&[ToneMappingBuffer {
+          curve: ToneMapping::None.index(),
+          exposure: 1.0,
+          encode_srgb: (!frame_format.is_srgb()).into(),
+          _padding: 0,
+        }]),
+        usage: BufferUsages::UNIFORM,
+      }),
+      tone_mapping_last: true,
+      inputs_layout,
+      settings_layout,
+      sampler: linear_sampler(
+        renderer,
+        "post processing sampler",
+        AddressMode::ClampToEdge,
+      ),
+      blank: create_target(renderer, "post processing blank lookup", (1, 1)).view,
+      pipelines: Default::default(),
+      lookups: Default::default(),
+      passes: vec![],
+      targets: vec![],
+    };
+    chain.set_passes(renderer, vec![], vec![]);
+
+    chain
+  }
+
+  /// Replaces the passes with the ones of `effects`, keeping the previous ones on error.
+  pub(crate) fn set_effects(
+    &mut self,
+    renderer: &Renderer,
+    texture_loader: &TextureLoader,
+    effects: &[(PostStage, Vec<PostPass>)],
+  ) -> Result<(), PostProcessingError> {
+    // stable, the effects of a stage keep their order
+    let (hdr, ldr): (Vec<_>, Vec<_>) = effects
+      .iter()
+      .filter(|(_, passes)| !passes.is_empty())
+      .partition(|(stage, _)| *stage == PostStage::Hdr);
+    let hdr = hdr.into_iter().map(|(_, passes)| passes.clone()).collect();
+    let ldr = ldr
+      .into_iter()
+      .map(|(_, passes)| passes.clone())
+      .collect::<Vec<_>>();
+
+    for id in effects
+      .iter()
+      .flat_map(|(_, passes)| passes)
+      .filter_map(|pass| *pass.texture())
+    {
+      if !self.lookups.contains_key(&id) {
+        let texture = texture_loader
+          .get_from_id(id)
+          .ok_or(PostProcessingError::MissingTexture(id))?;
+        self.lookups.insert(id, self.upload(renderer, texture));
+      }
+    }
+
+    self.lookups.retain(|id, _| {
+      effects
+        .iter()
+        .flat_map(|(_, passes)| passes)
+        .any(|pass| pass.texture() == &Some(*id))
+    });
+    self.set_passes(renderer, hdr, ldr);
+
+    Ok(())
+  }
+
+  /// Chains the passes of the `hdr` effects, the tone mapping and the passes of the `ldr` effects,
+  /// whose lookup textures are uploaded.
+  fn set_passes(&mut self, renderer: &Renderer, hdr: Vec<Vec<PostPass>>, ldr: Vec<Vec<PostPass>>) {
+    let mut passes = vec![];
+    let mut used_pipelines = IndexMap::new();
+    let mut pipeline = |chain: &Self, shader: &Cow<'static, str>, format: TextureFormat| {
+      let key = (shader.clone(), format);
+      let pipeline = used_pipelines
+        .get(&key)
+        .or_else(|| chain.pipelines.get(&key))
+        .cloned()
+        .unwrap_or_else(|| Arc::new(chain.create_pipeline(renderer, shader, format)));
+      used_pipelines.insert(key, pipeline.clone());
+
+      pipeline
+    };
+    let frame_buffer = || {
+      renderer.device().create_buffer_init(&BufferInitDescriptor {
+        label: Some("post processing frame buffer"),
+        contents: bytemuck::cast_slice(&[PostFrameBuffer::zeroed()]),
+        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+      })
+    };
+    let tone_mapping = vec![PostPass::builder().shader(TONE_MAPPING_SHADER).build()];
+    let tone_mapped = hdr.len();
+    self.tone_mapping_last = ldr.is_empty();
+    // the tone mapping shader without a curve clamps and encodes the `ldr` effects into the frame
+    let encoding = (!ldr.is_empty()).then(|| tone_mapping.clone());
+    let effects = hdr
+      .into_iter()
+      .chain([tone_mapping])
+      .chain(ldr)
+      .chain(encoding)
+      .collect::<Vec<_>>();
+    let mut source = 0;
+
+    for (effect_index, effect) in effects.iter().enumerate() {
+      let final_effect = effect_index + 1 == effects.len();
+
+      for (index, pass) in effect.iter().enumerate() {
+        let last = index + 1 == effect.len();
+
+        let settings = if effect_index == tone_mapped {
+          self.settings_bind_group(renderer, &self.tone_mapping, None)
+        } else if final_effect {
+          self.settings_bind_group(renderer, &self.encoding, None)
+        } else {
+          let mut uniforms = pass.uniforms().clone();
+          uniforms.resize(uniforms.len().div_ceil(16).max(1) * 16, 0);
+
+          let buffer = renderer.device().create_buffer_init(&BufferInitDescriptor {
+            label: Some("post processing settings buffer"),
+            contents: &uniforms,
+            usage: BufferUsages::UNIFORM,
+          });
+
+          self.settings_bind_group(renderer, &buffer, *pass.texture())
+        };
+
+        let format = if final_effect && last {
+          self.frame_format
+        } else {
+          *renderer.hdr_format()
+        };
+
+        passes.push(ChainPass {
+          pipeline: pipeline(self, pass.shader(), format),
+          settings,
+          frame: frame_buffer(),
+          scale: if last { 1.0 } else { *pass.scale() },
+          previous: if index == 0 { source } else { passes.len() },
+          source,
+          inputs: None,
+        });
+      }
+
+      source = passes.len();
+    }
+
+    self.pipelines = used_pipelines;
+    self.passes = passes;
+    self.resize(renderer);
+  }
+
+  /// Recreates the targets at the size of the `Renderer`.
+  pub(crate) fn resize(&mut self, renderer: &Renderer) {
+    let (width, height) = renderer.size();
+    let scaled = |scale: f32| {
+      (
+        (width as f32 * scale).round() as u32,
+        (height as f32 * scale).round() as u32,
+      )
+    };
+
+    self.targets = [1.0]
+      .into_iter()
+      .chain(self.passes.iter().map(|pass| pass.scale))
+      .take(self.passes.len())
+      .map(|scale| create_target(renderer, "post processing target", scaled(scale)))
+      .collect();
+
+    for pass in &mut self.passes {
+      let (previous, source) = (&self.targets[pass.previous], &self.targets[pass.source]);
+      let (target_width, target_height) = scaled(pass.scale);
+
+      renderer.queue().write_buffer(
+        &pass.frame,
+        0,
+        bytemuck::cast_slice(&[PostFrameBuffer {
+          size: Vec2::new(target_width.max(1) as f32, target_height.max(1) as f32),
+          texel: Vec2::ONE
+            / Vec2::new(
+              previous.texture.width() as f32,
+              previous.texture.height() as f32,
+            ),
+        }]),
+      );
+
+      pass.inputs = Some(renderer.device().create_bind_group(&BindGroupDescriptor {
+        label: Some("post processing inputs bind group"),
+        layout: &self.inputs_layout,
+        entries: &[
+          BindGroupEntry {
+            binding: 0,
+            resource: BindingResource::TextureView(&previous.view),
+          },
+          BindGroupEntry {
+            binding: 1,
+            resource: BindingResource::TextureView(&source.view),
+          },
+          BindGroupEntry {
+            binding: 2,
+            resource: BindingResource::Sampler(&self.sampler),
+          },
+          BindGroupEntry {
+            binding: 3,
+            resource: pass.frame.as_entire_binding(),
+          },
+        ],
+      }));
+    }
+  }
+
+  /// Target the scene is drawn into.
+  pub(crate) fn input(&self) -> &TextureView {
+    &self.targets[0].view
+  }
+
+  /// Uploads the settings of the tone mapping, before the passes are drawn.
+  pub(crate) fn prepare(&self, renderer: &Renderer, tone_mapping: ToneMapping, exposure: f32) {
+    renderer.queue().write_buffer(
+      &self.tone_mapping,
+      0,
+      bytemuck::cast_slice(&[ToneMappingBuffer {
+        curve: tone_mapping.index(),
+        exposure,
+        encode_srgb: (self.tone_mapping_last && !self.frame_format.is_srgb()).into(),
+        _padding: 0,
+      }]),
+    );
+  }
+
+  /// Draws every pass, the last one into `frame`.
+  pub(crate) fn encode(
+    &self,
+    encoder: &mut CommandEncoder,
+    frame: &TextureView,
+    stats: &mut RenderStats,
+  ) {
+    for (index, pass) in self.passes.iter().enumerate() {
+      let Some(inputs) = &pass.inputs else {
+        continue;
+      };
+
+      let view = self
+        .targets
+        .get(index + 1)
+        .map(|target| &target.view)
+        .unwrap_or(frame);
+
+      let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some("post processing pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+          view,
+          resolve_target: None,
+          ops: Operations {
+            load: LoadOp::Clear(Color::BLACK),
+            store: StoreOp::Store,
+          },
+        })],
+        ..Default::default()
+      });
+
+      render_pass.set_pipeline(&pass.pipeline);
+      render_pass.set_bind_group(0, inputs, &[]);
+      render_pass.set_bind_group(1, &pass.settings, &[]);
+      render_pass.draw(0..3, 0..1);
+
+      stats.record_pipeline_switch();
+      stats.record_bind_group_switch();
+      stats.record_bind_group_switch();
+      stats.record_draw(3);
+    }
+  }
+
+  pub(crate) fn textures(&self) -> impl Iterator<Item = &Texture> {
+    self
+      .targets
+      .iter()
+      .chain(self.lookups.values())
+      .map(|target| &target.texture)
+  }
+
+  fn upload(&self, renderer: &Renderer, texture: &SourceTexture) -> Target {
+    let (width, height) = texture.dimensions;
+    let size = Extent3d {
+      width,
+      height,
+      depth_or_array_layers: 1,
+    };
+
+    let lookup = renderer.device().create_texture(&TextureDescriptor {
+      label: Some(&format!("TextureId({:?})", texture.id)),
+      size,
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: TextureDimension::D2,
+      format: texture.format.wgpu_format(),
+      usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+      view_formats: &[],
+    });
+
+    renderer.queue().write_texture(
+      ImageCopyTexture {
+        aspect: TextureAspect::All,
+        texture: &lookup,
+        mip_level: 0,
+        origin: Origin3d::ZERO,
+      },
+      &texture.content,
+      ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(texture.format.bytes_per_texel() * width),
+        rows_per_image: Some(height),
+      },
+      size,
+    );
+
+    Target {
+      view: lookup.create_view(&Default::default()),
+      texture: lookup,
+    }
+  }
+
+  fn settings_bind_group(
+    &self,
+    renderer: &Renderer,
+    buffer: &Buffer,
+    lookup: Option<TextureId>,
+  ) -> BindGroup {
+    let lookup = lookup
+      .and_then(|id| self.lookups.get(&id))
+      .map(|target| &target.view)
+      .unwrap_or(&self.blank);
+
+    renderer.device().create_bind_group(&BindGroupDescriptor {
+      label: Some("post processing settings bind group"),
+      layout: &self.settings_layout,
+      entries: &[
+        BindGroupEntry {
+          binding: 0,
+          resource: buffer.as_entire_binding(),
+        },
+        BindGroupEntry {
+          binding: 1,
+          resource: BindingResource::TextureView(lookup),
+        },
+      ],
+    })
+  }
+
+  fn create_pipeline(
+    &self,
+    renderer: &Renderer,
+    shader: &str,
+    format: TextureFormat,
+  ) -> RenderPipeline {
+    let shader = renderer
+      .device()
+      .create_shader_module(ShaderModuleDescriptor {
+        label: Some("post processing shader"),
+        source: ShaderSource::Wgsl(format!("{PRELUDE}\n{shader}").into()),
+      });
+
+    renderer
+      .device()
+      .create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("post processing pipeline"),
+        layout: Some(
+          &renderer
+            .device()
+            .create_pipeline_layout(&PipelineLayoutDescriptor {
+              label: Some("post processing pipeline layout"),
+              bind_group_layouts: &[&self.inputs_layout, &self.settings_layout],
+              push_constant_ranges: &[],
+            }),
+        ),
+        vertex: VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[],
+          compilation_options: Default::default(),
+        },
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: MultisampleState::default(),
+        fragment: Some(FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[Some(ColorTargetState {
+            format,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+          })],
+          compilation_options: Default::default(),
+        }),
+        multiview: None,
+        cache: None,
+      })
+  }
+}
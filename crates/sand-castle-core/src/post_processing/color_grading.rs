@@ -0,0 +1,48 @@
+use derive_builder::Builder;
+use getset::{Getters, Setters};
+
+use crate::resource::texture::TextureId;
+
+use super::{PostEffect, PostPass, PostStage};
+
+/// Remaps colors through a lookup table, usually exported from an image editor after grading a
+/// screenshot of the identity table.
+#[derive(Builder, Getters, Setters, Clone, Copy, Debug, PartialEq)]
+#[getset(get = "pub", set = "pub")]
+#[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
+pub struct ColorGrading {
+  /// Table of `n` slices of `n` by `n` texels laid out in a row, red grows to the right of each
+  /// slice, green downwards and blue from one slice to the next.
+  lut: TextureId,
+  /// Blend between the original colors at `0.0` and the graded ones at `1.0`.
+  #[builder(default = "1.0")]
+  intensity: f32,
+}
+
+impl ColorGrading {
+  pub fn builder() -> ColorGradingBuilder {
+    Default::default()
+  }
+}
+
+impl ColorGradingBuilder {
+  pub fn build(self) -> ColorGrading {
+    self
+      .fallible_build()
+      .expect("could not build `ColorGrading`")
+  }
+}
+
+impl PostEffect for ColorGrading {
+  fn passes(&self) -> Vec<PostPass> {
+    vec![PostPass::builder()
+      .shader(include_str!("shaders/color_grading.wgsl"))
+      .uniforms(bytemuck::cast_slice(&[self.intensity]).to_vec())
+      .texture(Some(self.lut))
+      .build()]
+  }
+
+  fn stage(&self) -> PostStage {
+    PostStage::Ldr
+  }
+}
@@ -0,0 +1,44 @@
+use derive_builder::Builder;
+use getset::{Getters, Setters};
+
+use super::{PostEffect, PostPass, PostStage};
+
+/// Fast approximate anti-aliasing, blurring along the edges found from the luma of the frame.
+/// Cheaper than MSAA and smooths the edges within textures and shaders as well, at the cost of
+/// some sharpness.
+#[derive(Builder, Getters, Setters, Clone, Copy, Debug, PartialEq)]
+#[getset(get = "pub", set = "pub")]
+#[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
+pub struct Fxaa {
+  /// Longest blur along an edge, in pixels.
+  #[builder(default = "8.0")]
+  span: f32,
+  /// Contrast below which an edge is left alone, relative to the brightness around it.
+  #[builder(default = "0.125")]
+  threshold: f32,
+}
+
+impl Fxaa {
+  pub fn builder() -> FxaaBuilder {
+    Default::default()
+  }
+}
+
+impl FxaaBuilder {
+  pub fn build(self) -> Fxaa {
+    self.fallible_build().expect("could not build `Fxaa`")
+  }
+}
+
+impl PostEffect for Fxaa {
+  fn passes(&self) -> Vec<PostPass> {
+    vec![PostPass::builder()
+      .shader(include_str!("shaders/fxaa.wgsl"))
+      .uniforms(bytemuck::cast_slice(&[self.span, self.threshold]).to_vec())
+      .build()]
+  }
+
+  fn stage(&self) -> PostStage {
+    PostStage::Ldr
+  }
+}
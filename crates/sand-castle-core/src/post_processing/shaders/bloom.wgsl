@@ -0,0 +1,16 @@
+// Adds the blurred bright parts back onto the input of the effect.
+
+struct Bloom {
+  intensity: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> bloom: Bloom;
+
+@fragment
+fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+  let color = textureSampleLevel(source, post_sampler, in.uv, 0.0);
+  let glow = textureSampleLevel(previous, post_sampler, in.uv, 0.0).rgb;
+
+  return vec4<f32>(color.rgb + glow * bloom.intensity, color.a);
+}
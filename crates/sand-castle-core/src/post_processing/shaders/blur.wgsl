@@ -0,0 +1,24 @@
+// Nine tap gaussian blur along `direction`.
+
+struct Blur {
+  direction: vec2<f32>,
+  radius: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> blur: Blur;
+
+@fragment
+fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+  var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+  let step = blur.direction * post_frame.texel * blur.radius;
+
+  var color = textureSampleLevel(previous, post_sampler, in.uv, 0.0).rgb * weights[0];
+  for (var index = 1; index < 5; index++) {
+    let offset = step * f32(index);
+    color += textureSampleLevel(previous, post_sampler, in.uv + offset, 0.0).rgb * weights[index];
+    color += textureSampleLevel(previous, post_sampler, in.uv - offset, 0.0).rgb * weights[index];
+  }
+
+  return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,17 @@
+// Keeps what is brighter than the threshold, fading in to avoid a hard cut.
+
+struct BrightPass {
+  threshold: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> bright_pass: BrightPass;
+
+@fragment
+fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+  let color = textureSampleLevel(previous, post_sampler, in.uv, 0.0).rgb;
+  let brightness = max(color.r, max(color.g, color.b));
+  let contribution = max(brightness - bright_pass.threshold, 0.0) / max(brightness, 0.0001);
+
+  return vec4<f32>(color * contribution, 1.0);
+}
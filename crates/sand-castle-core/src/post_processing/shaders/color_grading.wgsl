@@ -0,0 +1,41 @@
+// Looks colors up in a strip of slices, blending between the two slices around blue.
+
+struct ColorGrading {
+  intensity: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> color_grading: ColorGrading;
+
+fn srgb_encode(color: vec3<f32>) -> vec3<f32> {
+  let low = color * 12.92;
+  let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+
+  return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+// Lookup of a color in the slice `slice`, staying half a texel within it.
+fn slice_lookup(color: vec3<f32>, slice: f32, size: f32) -> vec3<f32> {
+  let texel = (color.rg * (size - 1.0) + 0.5) / size;
+  let uv = vec2<f32>((slice + texel.x) / size, texel.y);
+
+  return textureSampleLevel(lookup, post_sampler, uv, 0.0).rgb;
+}
+
+@fragment
+fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+  let color = textureSampleLevel(previous, post_sampler, in.uv, 0.0);
+  // tables are indexed by the encoded colors, as seen in an image editor
+  let encoded = srgb_encode(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0)));
+  let size = f32(textureDimensions(lookup).y);
+
+  let blue = encoded.b * (size - 1.0);
+  let lower = floor(blue);
+  let graded = mix(
+    slice_lookup(encoded, lower, size),
+    slice_lookup(encoded, min(lower + 1.0, size - 1.0), size),
+    blue - lower,
+  );
+
+  return vec4<f32>(mix(color.rgb, graded, color_grading.intensity), color.a);
+}
@@ -0,0 +1,58 @@
+// FXAA in the spirit of the console version by Timothy Lottes: the edge direction comes from the
+// luma of the four diagonal neighbours, and the blur along it is dropped when it overshoots.
+
+struct Fxaa {
+  span: f32,
+  threshold: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> fxaa: Fxaa;
+
+const REDUCE_MIN = 0.0078125;
+const REDUCE_MUL = 0.125;
+
+fn luma(color: vec3<f32>) -> f32 {
+  return dot(color, vec3<f32>(0.299, 0.587, 0.114));
+}
+
+fn tap(uv: vec2<f32>) -> vec3<f32> {
+  return textureSampleLevel(previous, post_sampler, uv, 0.0).rgb;
+}
+
+@fragment
+fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+  let texel = post_frame.texel;
+  let center = textureSampleLevel(previous, post_sampler, in.uv, 0.0);
+
+  let luma_nw = luma(tap(in.uv + vec2<f32>(-1.0, -1.0) * texel));
+  let luma_ne = luma(tap(in.uv + vec2<f32>(1.0, -1.0) * texel));
+  let luma_sw = luma(tap(in.uv + vec2<f32>(-1.0, 1.0) * texel));
+  let luma_se = luma(tap(in.uv + vec2<f32>(1.0, 1.0) * texel));
+  let luma_m = luma(center.rgb);
+
+  let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+  let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+  if luma_max - luma_min < max(REDUCE_MIN, luma_max * fxaa.threshold) {
+    return center;
+  }
+
+  var direction = vec2<f32>(
+    (luma_sw + luma_se) - (luma_nw + luma_ne),
+    (luma_nw + luma_sw) - (luma_ne + luma_se),
+  );
+  let reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+  let scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+  direction = clamp(direction * scale, vec2<f32>(-fxaa.span), vec2<f32>(fxaa.span)) * texel;
+
+  let inner = 0.5 * (tap(in.uv + direction * (1.0 / 3.0 - 0.5)) + tap(in.uv + direction * (2.0 / 3.0 - 0.5)));
+  let outer = inner * 0.5 + 0.25 * (tap(in.uv - direction * 0.5) + tap(in.uv + direction * 0.5));
+  let luma_outer = luma(outer);
+
+  if luma_outer < luma_min || luma_outer > luma_max {
+    return vec4<f32>(inner, center.a);
+  }
+
+  return vec4<f32>(outer, center.a);
+}
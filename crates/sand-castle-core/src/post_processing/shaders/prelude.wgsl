@@ -0,0 +1,37 @@
+// Shared by every post processing pass, see `PRELUDE`.
+
+struct PostFrame {
+  // pixels of the target
+  size: vec2<f32>,
+  // texel of `previous`
+  texel: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var previous: texture_2d<f32>;
+@group(0) @binding(1)
+var source: texture_2d<f32>;
+@group(0) @binding(2)
+var post_sampler: sampler;
+@group(0) @binding(3)
+var<uniform> post_frame: PostFrame;
+
+@group(1) @binding(1)
+var lookup: texture_2d<f32>;
+
+struct PostVertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+};
+
+// One triangle covering the target, `uv` points down like texture coordinates.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> PostVertexOutput {
+  let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+  var out: PostVertexOutput;
+  out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+  out.uv = uv;
+
+  return out;
+}
@@ -0,0 +1,20 @@
+struct Sharpen {
+  amount: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> sharpen: Sharpen;
+
+@fragment
+fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+  let texel = post_frame.texel;
+  let center = textureSampleLevel(previous, post_sampler, in.uv, 0.0);
+  let neighbours = textureSampleLevel(previous, post_sampler, in.uv + vec2<f32>(texel.x, 0.0), 0.0).rgb
+    + textureSampleLevel(previous, post_sampler, in.uv - vec2<f32>(texel.x, 0.0), 0.0).rgb
+    + textureSampleLevel(previous, post_sampler, in.uv + vec2<f32>(0.0, texel.y), 0.0).rgb
+    + textureSampleLevel(previous, post_sampler, in.uv - vec2<f32>(0.0, texel.y), 0.0).rgb;
+
+  let color = center.rgb * (1.0 + 4.0 * sharpen.amount) - neighbours * sharpen.amount;
+
+  return vec4<f32>(max(color, vec3<f32>(0.0)), center.a);
+}
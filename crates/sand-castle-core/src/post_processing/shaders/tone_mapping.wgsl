@@ -0,0 +1,119 @@
+// Exposure, tone mapping and, when it renders into a frame that isn't stored as sRGB, the sRGB
+// encoding. Without a curve it also clamps and encodes the `PostStage::Ldr` effects into the frame.
+
+struct ToneMapping {
+  curve: u32,
+  exposure: f32,
+  encode_srgb: u32,
+};
+
+@group(1) @binding(0)
+var<uniform> tone_mapping: ToneMapping;
+
+fn aces_fit(color: vec3<f32>) -> vec3<f32> {
+  let a = color * (color + 0.0245786) - 0.000090537;
+  let b = color * (0.983729 * color + 0.4329510) + 0.238081;
+
+  return a / b;
+}
+
+fn aces(color: vec3<f32>) -> vec3<f32> {
+  let input = mat3x3<f32>(
+    0.59719, 0.07600, 0.02840,
+    0.35458, 0.90834, 0.13383,
+    0.04823, 0.01566, 0.83777,
+  );
+  let output = mat3x3<f32>(
+    1.60475, -0.10208, -0.00327,
+    -0.53108, 1.10813, -0.07276,
+    -0.07367, -0.00605, 1.07602,
+  );
+
+  // the fit expects the exposure of the reference transform
+  return output * aces_fit(input * (color / 0.6));
+}
+
+fn agx_contrast(x: vec3<f32>) -> vec3<f32> {
+  let x2 = x * x;
+  let x4 = x2 * x2;
+
+  return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2
+    + 0.1191 * x - 0.00232;
+}
+
+// Approximation of the AgX base transform by Benjamin Wrensch, as found in Blender and three.js.
+fn agx(color: vec3<f32>) -> vec3<f32> {
+  let srgb_to_rec2020 = mat3x3<f32>(
+    0.6274, 0.0691, 0.0164,
+    0.3293, 0.9195, 0.0880,
+    0.0433, 0.0113, 0.8956,
+  );
+  let rec2020_to_srgb = mat3x3<f32>(
+    1.6605, -0.1246, -0.0182,
+    -0.5876, 1.1329, -0.1006,
+    -0.0728, -0.0083, 1.1187,
+  );
+  let inset = mat3x3<f32>(
+    0.856627153315983, 0.137318972929847, 0.11189821299995,
+    0.0951212405381588, 0.761241990602591, 0.0767994186031903,
+    0.0482516061458583, 0.101439036467562, 0.811302368396859,
+  );
+  let outset = mat3x3<f32>(
+    1.1271005818144368, -0.1413297634984383, -0.14132976349843826,
+    -0.11060664309660323, 1.157823702216272, -0.11060664309660294,
+    -0.016493938717834573, -0.016493938717834257, 1.2519364065950405,
+  );
+  let min_ev = -12.47393;
+  let max_ev = 4.026069;
+
+  var agx = inset * (srgb_to_rec2020 * color);
+  agx = (log2(max(agx, vec3<f32>(1e-10))) - min_ev) / (max_ev - min_ev);
+  agx = agx_contrast(clamp(agx, vec3<f32>(0.0), vec3<f32>(1.0)));
+  agx = pow(max(outset * agx, vec3<f32>(0.0)), vec3<f32>(2.2));
+
+  return rec2020_to_srgb * agx;
+}
+
+fn hable(x: vec3<f32>) -> vec3<f32> {
+  let a = 0.15;
+  let b = 0.50;
+  let c = 0.10;
+  let d = 0.20;
+  let e = 0.02;
+  let f = 0.30;
+
+  return (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f;
+}
+
+fn filmic(color: vec3<f32>) -> vec3<f32> {
+  return hable(color * 2.0) / hable(vec3<f32>(11.2));
+}
+
+fn srgb_encode(color: vec3<f32>) -> vec3<f32> {
+  let low = color * 12.92;
+  let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+
+  return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+@fragment
+fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+  let hdr = textureSampleLevel(previous, post_sampler, in.uv, 0.0);
+  let exposed = max(hdr.rgb * tone_mapping.exposure, vec3<f32>(0.0));
+
+  var mapped: vec3<f32>;
+  switch tone_mapping.curve {
+    case 1u: { mapped = exposed / (exposed + 1.0); }
+    case 2u: { mapped = aces(exposed); }
+    case 3u: { mapped = agx(exposed); }
+    case 4u: { mapped = filmic(exposed); }
+    default: { mapped = exposed; }
+  }
+  mapped = clamp(mapped, vec3<f32>(0.0), vec3<f32>(1.0));
+
+  if tone_mapping.encode_srgb != 0u {
+    mapped = srgb_encode(mapped);
+  }
+
+  return vec4<f32>(mapped, hdr.a);
+}
@@ -0,0 +1,17 @@
+struct Vignette {
+  intensity: f32,
+  radius: f32,
+  softness: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> vignette: Vignette;
+
+@fragment
+fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+  let color = textureSampleLevel(previous, post_sampler, in.uv, 0.0);
+  let distance = length(in.uv * 2.0 - 1.0);
+  let shade = smoothstep(vignette.radius, vignette.radius - vignette.softness, distance);
+
+  return vec4<f32>(color.rgb * mix(1.0 - vignette.intensity, 1.0, shade), color.a);
+}
@@ -0,0 +1,39 @@
+use derive_builder::Builder;
+use getset::{Getters, Setters};
+
+use super::{PostEffect, PostPass, PostStage};
+
+/// Accentuates edges by subtracting the neighbouring pixels.
+#[derive(Builder, Getters, Setters, Clone, Copy, Debug, PartialEq)]
+#[getset(get = "pub", set = "pub")]
+#[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
+pub struct Sharpen {
+  /// Weight of each of the four neighbours, `0.0` leaves the frame untouched.
+  #[builder(default = "0.25")]
+  amount: f32,
+}
+
+impl Sharpen {
+  pub fn builder() -> SharpenBuilder {
+    Default::default()
+  }
+}
+
+impl SharpenBuilder {
+  pub fn build(self) -> Sharpen {
+    self.fallible_build().expect("could not build `Sharpen`")
+  }
+}
+
+impl PostEffect for Sharpen {
+  fn passes(&self) -> Vec<PostPass> {
+    vec![PostPass::builder()
+      .shader(include_str!("shaders/sharpen.wgsl"))
+      .uniforms(bytemuck::cast_slice(&[self.amount]).to_vec())
+      .build()]
+  }
+
+  fn stage(&self) -> PostStage {
+    PostStage::Ldr
+  }
+}
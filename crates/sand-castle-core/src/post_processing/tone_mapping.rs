@@ -0,0 +1,29 @@
+/// Curve compressing the HDR frame into the displayable range, applied between the
+/// `PostStage::Hdr` and the `PostStage::Ldr` effects together with `Scene::exposure`.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ToneMapping {
+  /// Colors are clamped, what the materials output is what is displayed up to white.
+  #[default]
+  None,
+  /// `color / (1 + color)`, simple but desaturates highlights.
+  Reinhard,
+  /// Fit of the ACES reference rendering transform by Stephen Hill, contrasty and saturated.
+  Aces,
+  /// The default view transform of Blender since 4.0, keeps the hue of bright colors.
+  AgX,
+  /// The filmic curve of John Hable, with a white point of `11.2`.
+  Filmic,
+}
+
+impl ToneMapping {
+  /// Matches the `switch` of `tone_mapping.wgsl`.
+  pub(crate) fn index(&self) -> u32 {
+    match self {
+      Self::None => 0,
+      Self::Reinhard => 1,
+      Self::Aces => 2,
+      Self::AgX => 3,
+      Self::Filmic => 4,
+    }
+  }
+}
@@ -0,0 +1,45 @@
+use derive_builder::Builder;
+use getset::{Getters, Setters};
+
+use super::{PostEffect, PostPass, PostStage};
+
+/// Darkens the frame towards its corners.
+#[derive(Builder, Getters, Setters, Clone, Copy, Debug, PartialEq)]
+#[getset(get = "pub", set = "pub")]
+#[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
+pub struct Vignette {
+  /// How dark the corners get, from `0.0` to `1.0`.
+  #[builder(default = "0.5")]
+  intensity: f32,
+  /// Distance from the center where the darkening is complete, `1.0` at the middle of the edges.
+  #[builder(default = "1.2")]
+  radius: f32,
+  /// Width of the fade towards `radius`.
+  #[builder(default = "0.8")]
+  softness: f32,
+}
+
+impl Vignette {
+  pub fn builder() -> VignetteBuilder {
+    Default::default()
+  }
+}
+
+impl VignetteBuilder {
+  pub fn build(self) -> Vignette {
+    self.fallible_build().expect("could not build `Vignette`")
+  }
+}
+
+impl PostEffect for Vignette {
+  fn passes(&self) -> Vec<PostPass> {
+    vec![PostPass::builder()
+      .shader(include_str!("shaders/vignette.wgsl"))
+      .uniforms(bytemuck::cast_slice(&[self.intensity, self.radius, self.softness, 0.0]).to_vec())
+      .build()]
+  }
+
+  fn stage(&self) -> PostStage {
+    PostStage::Ldr
+  }
+}
@@ -0,0 +1,122 @@
+use getset::Getters;
+use glam::{Mat4, Vec2, Vec3};
+
+use crate::resource::{camera::Camera, geometry::Geometry, Id};
+
+/// Half-line cast into a scene, the direction is always normalized.
+#[derive(Getters, Clone, Copy, Debug, PartialEq)]
+#[getset(get = "pub")]
+pub struct Ray {
+  origin: Vec3,
+  direction: Vec3,
+}
+
+/// Intersection of a `Ray` with a triangle of a scene subject.
+#[derive(Getters, Clone, Copy, Debug, PartialEq)]
+#[getset(get = "pub")]
+pub struct RaycastHit {
+  id: Id,
+  distance: f32,
+  point: Vec3,
+  /// World space normal of the hit face, following its winding order.
+  normal: Vec3,
+  /// Texture coordinates interpolated at the hit point.
+  uv: Vec2,
+}
+
+impl Ray {
+  pub fn new(origin: Vec3, direction: Vec3) -> Self {
+    Self {
+      origin,
+      direction: direction.normalize_or_zero(),
+    }
+  }
+
+  /// Ray going from the camera through a point of the viewport, `screen` is `(0, 0)` at the top
+  /// left corner and `(1, 1)` at the bottom right one.
+  pub fn from_camera(camera: &impl Camera, screen: Vec2) -> Self {
+    Self::from_view_projection(camera.to_matrix(), screen)
+  }
+
+  pub fn from_view_projection(view_projection: Mat4, screen: Vec2) -> Self {
+    let inverse = view_projection.inverse();
+    let ndc = Vec2::new(screen.x * 2.0 - 1.0, 1.0 - screen.y * 2.0);
+
+    // depth goes from `0.0` on the near plane to `1.0` on the far one
+    let near = inverse.project_point3(ndc.extend(0.0));
+    let far = inverse.project_point3(ndc.extend(1.0));
+
+    Self::new(near, far - near)
+  }
+
+  pub fn at(&self, distance: f32) -> Vec3 {
+    self.origin + self.direction * distance
+  }
+
+  /// Möller–Trumbore, both faces are hit. Returns the distance and barycentric coordinates.
+  fn intersect_triangle(&self, [a, b, c]: [Vec3; 3]) -> Option<(f32, f32, f32)> {
+    let edge_ab = b - a;
+    let edge_ac = c - a;
+
+    let p = self.direction.cross(edge_ac);
+    let determinant = edge_ab.dot(p);
+
+    if determinant.abs() <= f32::EPSILON {
+      return None;
+    }
+
+    let inverse_determinant = 1.0 / determinant;
+    let to_origin = self.origin - a;
+
+    let u = to_origin.dot(p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+      return None;
+    }
+
+    let q = to_origin.cross(edge_ab);
+    let v = self.direction.dot(q) * inverse_determinant;
+    if v < 0.0 || u + v > 1.0 {
+      return None;
+    }
+
+    let distance = edge_ac.dot(q) * inverse_determinant;
+
+    (distance > f32::EPSILON).then_some((distance, u, v))
+  }
+
+  /// Every hit of the ray against a geometry placed in the world by `world`, in index order.
+  pub(crate) fn intersect_geometry(
+    &self,
+    id: Id,
+    geometry: &Geometry,
+    world: Mat4,
+  ) -> Vec<RaycastHit> {
+    // mirroring transforms flip the winding order of the triangles
+    let facing = world.determinant().signum();
+
+    geometry
+      .indices
+      .chunks_exact(3)
+      .filter_map(|triangle| {
+        let vertices = [
+          geometry.vertices.get(triangle[0] as usize)?,
+          geometry.vertices.get(triangle[1] as usize)?,
+          geometry.vertices.get(triangle[2] as usize)?,
+        ];
+
+        let [a, b, c] = vertices.map(|vertex| world.transform_point3(vertex.position));
+        let (distance, u, v) = self.intersect_triangle([a, b, c])?;
+
+        let w = 1.0 - u - v;
+
+        Some(RaycastHit {
+          id,
+          distance,
+          point: self.at(distance),
+          normal: ((b - a).cross(c - a) * facing).normalize_or_zero(),
+          uv: vertices[0].tex_coords * w + vertices[1].tex_coords * u + vertices[2].tex_coords * v,
+        })
+      })
+      .collect()
+  }
+}
@@ -1,18 +1,94 @@
+use std::sync::{
+  atomic::{AtomicBool, Ordering},
+  Arc,
+};
+
 use derive_builder::Builder;
 use getset::Getters;
 use web_sys::HtmlCanvasElement;
 use wgpu::{
-  Adapter, Backends, CompositeAlphaMode, CreateSurfaceError, Device, DeviceDescriptor, Features,
-  Instance, InstanceDescriptor, Limits, MemoryHints, PowerPreference, Queue, RequestAdapterOptions,
-  RequestDeviceError, Surface, SurfaceCapabilities, SurfaceConfiguration, SurfaceTarget,
-  TextureFormat, TextureUsages,
+  Adapter, Backends, CreateSurfaceError, Device, DeviceDescriptor, Extent3d, Features, Instance,
+  InstanceDescriptor, Limits, MemoryHints, PowerPreference, Queue, RequestAdapterOptions,
+  RequestDeviceError, Surface, SurfaceCapabilities, SurfaceConfiguration, SurfaceTexture, Texture,
+  TextureDescriptor, TextureDimension, TextureFormat, TextureFormatFeatureFlags, TextureUsages,
 };
 
-#[derive(Default, Clone, PartialEq)]
+pub use wgpu::{CompositeAlphaMode, DeviceLostReason, PresentMode, WindowHandle};
+
+/// Format the scene is drawn in, before the tone mapping brings it into the range of the frame.
+const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+/// Drawn in instead of `HDR_FORMAT` where the adapter can't render or blend into it, which clamps
+/// the scene to `[0, 1]` before the tone mapping.
+const LDR_FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+#[derive(Default, Clone, PartialEq, Debug)]
 pub enum Backend {
   #[default]
   WebGL,
   WebGPU,
+  Vulkan,
+  Metal,
+  DX12,
+  GL,
+  /// Tries `WebGPU` first and falls back to `WebGL`, see `Renderer::active_backend` for the
+  /// backend that was picked.
+  Auto,
+}
+
+impl Backend {
+  /// On native targets `WebGL` maps to the OpenGL/GLES backend and `WebGPU` to the primary
+  /// backends of the platform (Vulkan, Metal or DX12).
+  fn backends(&self) -> Backends {
+    match self {
+      Backend::WebGL | Backend::GL => Backends::GL,
+      #[cfg(target_arch = "wasm32")]
+      Backend::WebGPU => Backends::BROWSER_WEBGPU,
+      #[cfg(not(target_arch = "wasm32"))]
+      Backend::WebGPU => Backends::PRIMARY,
+      Backend::Vulkan => Backends::VULKAN,
+      Backend::Metal => Backends::METAL,
+      Backend::DX12 => Backends::DX12,
+      Backend::Auto => Backends::all(),
+    }
+  }
+
+  /// Backends tried in order when building a `Renderer`.
+  fn fallback_chain(&self) -> Vec<Backends> {
+    match self {
+      Backend::Auto => vec![Backend::WebGPU.backends(), Backend::WebGL.backends()],
+      backend => vec![backend.backends()],
+    }
+  }
+
+  fn from_adapter_backend(backend: wgpu::Backend, requested: &Backend) -> Self {
+    match backend {
+      wgpu::Backend::BrowserWebGpu => Backend::WebGPU,
+      wgpu::Backend::Gl if cfg!(target_arch = "wasm32") => Backend::WebGL,
+      wgpu::Backend::Gl => Backend::GL,
+      wgpu::Backend::Vulkan => Backend::Vulkan,
+      wgpu::Backend::Metal => Backend::Metal,
+      wgpu::Backend::Dx12 => Backend::DX12,
+      wgpu::Backend::Empty => requested.clone(),
+    }
+  }
+}
+
+/// Describes the texture a headless `Renderer` draws into instead of a canvas surface.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Offscreen {
+  pub width: u32,
+  pub height: u32,
+  pub format: TextureFormat,
+}
+
+impl Offscreen {
+  pub fn new(width: u32, height: u32) -> Self {
+    Self {
+      width,
+      height,
+      format: TextureFormat::Rgba8UnormSrgb,
+    }
+  }
 }
 
 #[derive(Builder, Getters)]
@@ -20,10 +96,66 @@ pub enum Backend {
 #[builder(pattern = "owned", build_fn(skip))]
 pub struct Renderer {
   backend: Backend,
-  canvas: HtmlCanvasElement,
+
+  /// The backend the adapter was found on, resolves `Backend::Auto` to a concrete backend.
+  #[builder(setter(skip))]
+  active_backend: Backend,
+
+  #[builder(setter(strip_option))]
+  canvas: Option<HtmlCanvasElement>,
+
+  /// Any `raw-window-handle` target such as a winit window, takes precedence over `canvas`.
+  #[builder(setter(custom))]
+  window: Option<Arc<dyn WindowHandle>>,
+
+  #[getset(skip)]
+  #[builder(setter(custom))]
+  window_size: (u32, u32),
+
+  /// Renders into an offscreen texture, takes precedence over `window` and `canvas`.
+  #[builder(setter(strip_option))]
+  offscreen: Option<Offscreen>,
+
+  /// Only consider fallback (software) adapters such as lavapipe or llvmpipe.
+  force_fallback_adapter: bool,
+
+  /// Falls back to the first mode supported by the surface when unset or unsupported.
+  #[builder(setter(strip_option))]
+  present_mode: Option<PresentMode>,
+
+  /// Use `PreMultiplied` for canvases that blend with the page behind them, falls back to the
+  /// first mode supported by the surface when unset or unsupported.
+  #[builder(setter(strip_option))]
+  alpha_mode: Option<CompositeAlphaMode>,
+
+  /// Multiplier applied to the CSS size of a `canvas`, usually `window.devicePixelRatio`.
+  pixel_ratio: f64,
+
+  /// MSAA sample count, falls back to `1` when the adapter can't multisample the surface format.
+  sample_count: u32,
+
+  /// Measures GPU frame times with timestamp queries when the adapter supports them, see
+  /// `RenderStats::gpu_time`.
+  gpu_timing: bool,
+
+  /// Binds lights as storage buffers when the adapter supports them, otherwise they are limited to
+  /// the uniform arrays WebGL provides, see `LightStorage`.
+  storage_buffers: bool,
+
+  /// Called when the device is lost, the `Renderer` and its scenes have to be rebuilt afterwards.
+  #[getset(skip)]
+  #[builder(setter(custom))]
+  on_device_lost: Option<Arc<dyn Fn(DeviceLostReason, String) + Send + Sync>>,
+
+  #[getset(skip)]
+  #[builder(setter(skip))]
+  lost: Arc<AtomicBool>,
 
   #[builder(setter(skip))]
-  surface: Surface<'static>,
+  surface: Option<Surface<'static>>,
+
+  #[builder(setter(skip))]
+  offscreen_texture: Option<Texture>,
 
   #[builder(setter(skip))]
   adapter: Adapter,
@@ -39,16 +171,42 @@ pub struct Renderer {
 
   #[builder(setter(skip))]
   supported_format: Option<TextureFormat>,
+
+  /// Format the scene is drawn in before the tone mapping, `Rgba16Float` unless the adapter can't
+  /// render into it.
+  #[builder(setter(skip))]
+  hdr_format: TextureFormat,
 }
 
 #[derive(Debug)]
 pub enum RendererBuildError {
   NoAdapter,
   Incomplete(&'static str),
+  Unsupported(&'static str),
   CreateSurfaceError(CreateSurfaceError),
   RequestDeviceError(RequestDeviceError),
 }
 
+pub(crate) enum Frame<'a> {
+  Surface(SurfaceTexture),
+  Offscreen(&'a Texture),
+}
+
+impl Frame<'_> {
+  pub(crate) fn texture(&self) -> &Texture {
+    match self {
+      Frame::Surface(output) => &output.texture,
+      Frame::Offscreen(texture) => texture,
+    }
+  }
+
+  pub(crate) fn present(self) {
+    if let Frame::Surface(output) = self {
+      output.present();
+    }
+  }
+}
+
 // use wasm_bindgen::prelude::*;
 
 // #[wasm_bindgen]
@@ -57,49 +215,186 @@ pub enum RendererBuildError {
 //   fn log(s: &str);
 // }
 
+fn create_surface(
+  instance: &Instance,
+  offscreen: &Option<Offscreen>,
+  window: &Option<Arc<dyn WindowHandle>>,
+  canvas: &Option<HtmlCanvasElement>,
+) -> Result<Option<Surface<'static>>, RendererBuildError> {
+  match (offscreen, window, canvas) {
+    (Some(_), _, _) => Ok(None),
+    (None, Some(window), _) => instance
+      .create_surface(window.clone())
+      .map(Some)
+      .map_err(RendererBuildError::CreateSurfaceError),
+    (None, None, Some(canvas)) => create_canvas_surface(instance, canvas).map(Some),
+    (None, None, None) => Ok(None),
+  }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn create_canvas_surface(
+  instance: &Instance,
+  canvas: &HtmlCanvasElement,
+) -> Result<Surface<'static>, RendererBuildError> {
+  instance
+    .create_surface(wgpu::SurfaceTarget::Canvas(canvas.clone()))
+    .map_err(RendererBuildError::CreateSurfaceError)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn create_canvas_surface(
+  _instance: &Instance,
+  _canvas: &HtmlCanvasElement,
+) -> Result<Surface<'static>, RendererBuildError> {
+  Err(RendererBuildError::Unsupported(
+    "`canvas` targets are only supported on wasm32, use `offscreen` instead",
+  ))
+}
+
+fn create_offscreen_texture(device: &Device, offscreen: &Offscreen) -> Texture {
+  device.create_texture(&TextureDescriptor {
+    label: Some("offscreen texture"),
+    size: Extent3d {
+      width: offscreen.width,
+      height: offscreen.height,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: TextureDimension::D2,
+    format: offscreen.format,
+    usage: TextureUsages::RENDER_ATTACHMENT
+      | TextureUsages::TEXTURE_BINDING
+      | TextureUsages::COPY_SRC,
+    view_formats: &[],
+  })
+}
+
 impl RendererBuilder {
+  /// Renders into a native window, `width` and `height` are the size of its client area in
+  /// physical pixels.
+  pub fn window(mut self, window: impl WindowHandle + 'static, width: u32, height: u32) -> Self {
+    self.window = Some(Some(Arc::new(window)));
+    self.window_size = Some((width, height));
+    self
+  }
+
+  pub fn on_device_lost(
+    mut self,
+    callback: impl Fn(DeviceLostReason, String) + Send + Sync + 'static,
+  ) -> Self {
+    self.on_device_lost = Some(Some(Arc::new(callback)));
+    self
+  }
+
   pub async fn build(self) -> Result<Renderer, RendererBuildError> {
-    let canvas = self.canvas.ok_or(RendererBuildError::Incomplete(
-      "`canvas` is a required field on the `Renderer` builder",
-    ))?;
+    let canvas = self.canvas.flatten();
+    let window = self.window.flatten();
+    let window_size = self.window_size.unwrap_or_default();
+    let offscreen = self.offscreen.flatten();
+
+    if canvas.is_none() && window.is_none() && offscreen.is_none() {
+      return Err(RendererBuildError::Incomplete(
+        "one of `canvas`, `window` or `offscreen` is a required field on the `Renderer` builder",
+      ));
+    }
 
     let backend = self.backend.unwrap_or_default();
+    let force_fallback_adapter = self.force_fallback_adapter.unwrap_or_default();
+    let present_mode = self.present_mode.flatten();
+    let alpha_mode = self.alpha_mode.flatten();
+    let pixel_ratio = self.pixel_ratio.unwrap_or(1.0);
+    let on_device_lost = self.on_device_lost.flatten();
+    let gpu_timing = self.gpu_timing.unwrap_or_default();
+    let storage_buffers = self.storage_buffers.unwrap_or(true);
 
-    let instance = Instance::new(InstanceDescriptor {
-      backends: if backend == Backend::WebGL {
-        Backends::GL
-      } else {
-        Backends::BROWSER_WEBGPU
-      },
-      ..Default::default()
-    });
+    let mut last_error = RendererBuildError::NoAdapter;
+    let mut selected = None;
 
-    let surface = instance
-      .create_surface(SurfaceTarget::Canvas(canvas.clone()))
-      .map_err(|err| RendererBuildError::CreateSurfaceError(err))?;
+    for backends in backend.fallback_chain() {
+      let instance = Instance::new(InstanceDescriptor {
+        backends,
+        ..Default::default()
+      });
 
-    let adapter = instance
-      .request_adapter(&RequestAdapterOptions {
-        power_preference: PowerPreference::default(),
-        compatible_surface: Some(&surface),
-        force_fallback_adapter: false,
-      })
-      .await
-      .ok_or(RendererBuildError::NoAdapter)?;
+      // a canvas only ever hands out one kind of context, so WebGPU is probed without a surface
+      // to keep the canvas usable for WebGL when no adapter is found
+      let surface_first = backends != Backends::BROWSER_WEBGPU;
 
-    let mut required_limits = if backend == Backend::WebGL {
+      let mut surface = None;
+      if surface_first {
+        match create_surface(&instance, &offscreen, &window, &canvas) {
+          Ok(created) => surface = created,
+          Err(err) => {
+            last_error = err;
+            continue;
+          }
+        }
+      }
+
+      let Some(adapter) = instance
+        .request_adapter(&RequestAdapterOptions {
+          power_preference: PowerPreference::default(),
+          compatible_surface: surface.as_ref(),
+          force_fallback_adapter,
+        })
+        .await
+      else {
+        last_error = RendererBuildError::NoAdapter;
+        continue;
+      };
+
+      if !surface_first {
+        match create_surface(&instance, &offscreen, &window, &canvas) {
+          Ok(created) => surface = created,
+          Err(err) => {
+            last_error = err;
+            continue;
+          }
+        }
+      }
+
+      selected = Some((surface, adapter));
+      break;
+    }
+
+    let Some((surface, adapter)) = selected else {
+      return Err(last_error);
+    };
+
+    let active_backend = Backend::from_adapter_backend(adapter.get_info().backend, &backend);
+
+    let mut required_limits = if adapter.get_info().backend == wgpu::Backend::Gl {
       Limits::downlevel_webgl2_defaults()
     } else {
       Limits::downlevel_defaults()
     };
     required_limits.max_bind_groups = 8;
     required_limits.max_texture_dimension_2d = 8192;
-    // required_limits.max_storage_buffers_per_shader_stage = 1;
+
+    if storage_buffers {
+      let adapter_limits = adapter.limits();
+      required_limits.max_storage_buffers_per_shader_stage = adapter_limits
+        .max_storage_buffers_per_shader_stage
+        .min(Limits::downlevel_defaults().max_storage_buffers_per_shader_stage);
+      required_limits.max_storage_buffer_binding_size = adapter_limits
+        .max_storage_buffer_binding_size
+        .min(Limits::downlevel_defaults().max_storage_buffer_binding_size);
+    } else {
+      required_limits.max_storage_buffers_per_shader_stage = 0;
+    }
+
+    let required_features = if gpu_timing {
+      adapter.features() & Features::TIMESTAMP_QUERY
+    } else {
+      Features::empty()
+    };
 
     let (device, queue) = adapter
       .request_device(
         &DeviceDescriptor {
-          required_features: Features::empty(),
+          required_features,
           required_limits,
           memory_hints: MemoryHints::default(),
           label: None,
@@ -107,79 +402,246 @@ impl RendererBuilder {
         None,
       )
       .await
-      .map_err(|err| RendererBuildError::RequestDeviceError(err))?;
-
-    let capabilities = surface.get_capabilities(&adapter);
-    let supported_format = capabilities
-      .formats
-      .iter()
-      .find(|format| format.is_srgb())
-      .or_else(|| capabilities.formats.first())
-      .copied();
-
-    if let (Some(present_mode), Some(alpha_mode), Some(format)) = (
-      capabilities.present_modes.first().cloned(),
-      capabilities.alpha_modes.first().cloned(),
-      supported_format.clone(),
-    ) {
-      let width = canvas.client_width() as u32;
-      let height = canvas.client_height() as u32;
-
-      if width != 0 && height != 0 {
-        surface.configure(
-          &device,
-          &SurfaceConfiguration {
-            usage: TextureUsages::RENDER_ATTACHMENT,
-            format,
-            present_mode,
-            alpha_mode,
-            width,
-            height,
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-          },
-        );
-      }
+      .map_err(RendererBuildError::RequestDeviceError)?;
+
+    let lost = Arc::new(AtomicBool::new(false));
+
+    {
+      let lost = lost.clone();
+      let on_device_lost = on_device_lost.clone();
+
+      device.set_device_lost_callback(move |reason, message| {
+        // wgpu also calls back when the device is dropped or the callback replaced
+        if matches!(
+          reason,
+          DeviceLostReason::Dropped | DeviceLostReason::ReplacedCallback
+        ) {
+          return;
+        }
+
+        lost.store(true, Ordering::Release);
+
+        if let Some(on_device_lost) = &on_device_lost {
+          on_device_lost(reason, message);
+        }
+      });
+    }
+
+    let (capabilities, supported_format) = if let Some(surface) = &surface {
+      let capabilities = surface.get_capabilities(&adapter);
+      let supported_format = capabilities
+        .formats
+        .iter()
+        .find(|format| format.is_srgb())
+        .or_else(|| capabilities.formats.first())
+        .copied();
+
+      (capabilities, supported_format)
+    } else {
+      (
+        SurfaceCapabilities::default(),
+        offscreen.as_ref().map(|offscreen| offscreen.format),
+      )
+    };
+
+    let hdr_format = if renderable(&adapter, HDR_FORMAT) {
+      HDR_FORMAT
+    } else {
+      LDR_FORMAT
+    };
+
+    let sample_count = self.sample_count.unwrap_or(1).max(1);
+    // only the HDR and depth targets the scene is drawn in are multisampled
+    let sample_count = if [hdr_format, TextureFormat::Depth32Float]
+      .into_iter()
+      .all(|format| {
+        adapter
+          .get_texture_format_features(format)
+          .flags
+          .sample_count_supported(sample_count)
+      }) {
+      sample_count
+    } else {
+      1
     };
 
-    Ok(Renderer {
+    let offscreen_texture = offscreen
+      .as_ref()
+      .map(|offscreen| create_offscreen_texture(&device, offscreen));
+
+    let renderer = Renderer {
       backend,
+      active_backend,
       canvas,
+      window,
+      window_size,
+      offscreen,
+      force_fallback_adapter,
+      present_mode,
+      alpha_mode,
+      pixel_ratio,
+      sample_count,
+      gpu_timing,
+      storage_buffers,
+      on_device_lost,
+      lost,
       surface,
+      offscreen_texture,
       adapter,
       device,
       queue,
       surface_capabilities: capabilities,
       supported_format,
-    })
+      hdr_format,
+    };
+
+    renderer.resize();
+
+    Ok(renderer)
   }
 }
 
+/// Whether the scene can be drawn into `format`, blending the transparent subjects.
+fn renderable(adapter: &Adapter, format: TextureFormat) -> bool {
+  let features = adapter.get_texture_format_features(format);
+
+  features
+    .allowed_usages
+    .contains(TextureUsages::RENDER_ATTACHMENT)
+    && features
+      .flags
+      .contains(TextureFormatFeatureFlags::BLENDABLE)
+}
+
 impl Renderer {
   pub fn builder() -> RendererBuilder {
     RendererBuilder::default()
   }
 
+  /// Whether the adapter can render and blend into `format`.
+  pub(crate) fn renderable(&self, format: TextureFormat) -> bool {
+    renderable(&self.adapter, format)
+  }
+
+  /// Size in pixels of the texture the `Renderer` currently draws into.
+  pub fn size(&self) -> (u32, u32) {
+    if let Some(offscreen) = &self.offscreen {
+      return (offscreen.width, offscreen.height);
+    }
+
+    if self.window.is_some() {
+      return self.window_size;
+    }
+
+    self
+      .canvas
+      .as_ref()
+      .map(|canvas| {
+        (
+          (canvas.client_width() as f64 * self.pixel_ratio).round() as u32,
+          (canvas.client_height() as f64 * self.pixel_ratio).round() as u32,
+        )
+      })
+      .unwrap_or_default()
+  }
+
+  /// Updates the size of a native window target and reconfigures its surface.
+  pub fn set_window_size(&mut self, width: u32, height: u32) {
+    self.window_size = (width, height);
+    self.resize();
+  }
+
+  /// Recreates the offscreen texture of a headless `Renderer` with a new size.
+  pub fn set_offscreen_size(&mut self, width: u32, height: u32) {
+    let Some(offscreen) = &mut self.offscreen else {
+      return;
+    };
+
+    offscreen.width = width;
+    offscreen.height = height;
+    self.offscreen_texture = Some(create_offscreen_texture(&self.device, offscreen));
+  }
+
+  /// Whether the device was lost, see `Renderer::rebuild`.
+  pub fn is_lost(&self) -> bool {
+    self.lost.load(Ordering::Acquire)
+  }
+
+  /// Builds a new `Renderer` with the same options and target, used to recover from a lost
+  /// device. Scenes have to be rebuilt with `Scene::rebuild` against the new `Renderer`.
+  pub async fn rebuild(&self) -> Result<Renderer, RendererBuildError> {
+    RendererBuilder {
+      backend: Some(self.backend.clone()),
+      canvas: Some(self.canvas.clone()),
+      window: Some(self.window.clone()),
+      window_size: Some(self.window_size),
+      offscreen: Some(self.offscreen.clone()),
+      force_fallback_adapter: Some(self.force_fallback_adapter),
+      present_mode: Some(self.present_mode),
+      alpha_mode: Some(self.alpha_mode),
+      pixel_ratio: Some(self.pixel_ratio),
+      sample_count: Some(self.sample_count),
+      gpu_timing: Some(self.gpu_timing),
+      storage_buffers: Some(self.storage_buffers),
+      on_device_lost: Some(self.on_device_lost.clone()),
+      ..Default::default()
+    }
+    .build()
+    .await
+  }
+
+  pub fn is_headless(&self) -> bool {
+    self.offscreen_texture.is_some()
+  }
+
+  pub(crate) fn current_frame(&self) -> Option<Frame<'_>> {
+    if let Some(texture) = &self.offscreen_texture {
+      return Some(Frame::Offscreen(texture));
+    }
+
+    self
+      .surface
+      .as_ref()
+      .and_then(|surface| surface.get_current_texture().ok())
+      .map(Frame::Surface)
+  }
+
   pub fn resize(&self) {
-    let (Some(present_mode), Some(alpha_mode), Some(format)) = (
-      self.surface_capabilities().present_modes.first().cloned(),
-      self.surface_capabilities().alpha_modes.first().cloned(),
-      self.supported_format().clone(),
-    ) else {
+    let Some(surface) = self.surface() else {
+      return;
+    };
+
+    let capabilities = self.surface_capabilities();
+
+    let present_mode = self
+      .present_mode
+      .filter(|mode| capabilities.present_modes.contains(mode))
+      .or_else(|| capabilities.present_modes.first().cloned());
+
+    let alpha_mode = self
+      .alpha_mode
+      .filter(|mode| capabilities.alpha_modes.contains(mode))
+      .or_else(|| capabilities.alpha_modes.first().cloned());
+
+    let (Some(present_mode), Some(alpha_mode), Some(format)) =
+      (present_mode, alpha_mode, *self.supported_format())
+    else {
       return;
     };
 
-    let width = self.canvas.client_width() as u32;
-    let height = self.canvas.client_height() as u32;
+    let (width, height) = self.size();
 
     if width == 0 || height == 0 {
       return;
     }
 
-    self.surface().configure(
+    // allow reading frames back when the surface supports it
+    let usage = TextureUsages::RENDER_ATTACHMENT | (capabilities.usages & TextureUsages::COPY_SRC);
+
+    surface.configure(
       self.device(),
       &SurfaceConfiguration {
-        usage: TextureUsages::RENDER_ATTACHMENT,
+        usage,
         format,
         present_mode,
         alpha_mode,
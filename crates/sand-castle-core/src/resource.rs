@@ -1,4 +1,5 @@
 pub mod camera;
+pub mod environment;
 pub mod geometry;
 pub mod lighting;
 pub mod object_3d;
@@ -42,6 +42,9 @@ impl PerspectiveCamera {
   }
 }
 
+/// Remapped OpenGL clip space depth into wgpu's `0.0..=1.0` range. `Mat4::perspective_rh` already
+/// outputs wgpu depth, so applying it squeezed the scene into the far half of the depth buffer.
+#[deprecated(note = "glam projections already output wgpu clip space, drop the multiplication")]
 pub const OPENGL_TO_WGPU_MATRIX: Mat4 = Mat4::from_cols(
   Vec4::new(1.0, 0.0, 0.0, 0.0),
   Vec4::new(0.0, 1.0, 0.0, 0.0),
@@ -54,18 +57,16 @@ impl Camera for PerspectiveCamera {
     let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
     let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
 
-    OPENGL_TO_WGPU_MATRIX
-      * Mat4::perspective_rh(
-        self.fov,
-        self.aspect_ratio,
-        self.view_frustum.near,
-        self.view_frustum.far,
-      )
-      * Mat4::look_to_rh(
-        self.position,
-        Vec3::new(cos_pitch * cos_yaw, sin_pitch, cos_pitch * sin_yaw).normalize(),
-        Vec3::Y,
-      )
+    Mat4::perspective_rh(
+      self.fov,
+      self.aspect_ratio,
+      self.view_frustum.near,
+      self.view_frustum.far,
+    ) * Mat4::look_to_rh(
+      self.position,
+      Vec3::new(cos_pitch * cos_yaw, sin_pitch, cos_pitch * sin_yaw).normalize(),
+      Vec3::Y,
+    )
   }
 }
 
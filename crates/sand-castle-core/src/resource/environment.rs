@@ -0,0 +1,71 @@
+use derive_builder::Builder;
+use getset::{Getters, Setters};
+
+use crate::resource::{
+  texture::{TexelFormat, TextureId},
+  Id, Resource,
+};
+
+/// Images an `Environment` is made of, looked up in the `TextureLoader`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnvironmentSource {
+  /// Six square faces of the same size in the order +X, -X, +Y, -Y, +Z, -Z.
+  Cubemap([TextureId; 6]),
+  /// A single panorama in the equirectangular projection, usually twice as wide as it is high.
+  Equirectangular(TextureId),
+}
+
+/// Why the images of an `Environment` couldn't be turned into a cubemap.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnvironmentError {
+  /// The texture isn't in the `TextureLoader` yet.
+  MissingTexture(TextureId),
+  /// A face of a `EnvironmentSource::Cubemap` isn't square or differs from the first one.
+  FaceSize {
+    expected: (u32, u32),
+    actual: (u32, u32),
+  },
+  /// A face of a `EnvironmentSource::Cubemap` is stored differently from the first one.
+  FaceFormat {
+    expected: TexelFormat,
+    actual: TexelFormat,
+  },
+  /// The adapter can't render into the format of the images to build the cubemap, such as
+  /// `Rgba16Float` on WebGL without float color buffers.
+  UnsupportedFormat(TexelFormat),
+}
+
+/// Surroundings of a scene, seen from infinitely far away.
+#[derive(Builder, Getters, Setters, Clone, Debug)]
+#[getset(get = "pub", set = "pub")]
+#[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
+pub struct Environment {
+  #[getset(skip)]
+  #[builder(default)]
+  id: Id,
+  source: EnvironmentSource,
+  /// Draws the environment as a skybox behind all subjects instead of clearing to the scene
+  /// color.
+  #[builder(default = "true")]
+  background: bool,
+}
+
+impl Environment {
+  pub fn builder() -> EnvironmentBuilder {
+    Default::default()
+  }
+}
+
+impl Resource for Environment {
+  fn id(&self) -> Id {
+    self.id
+  }
+}
+
+impl EnvironmentBuilder {
+  pub fn build(self) -> Environment {
+    self
+      .fallible_build()
+      .expect("could not build `Environment`")
+  }
+}
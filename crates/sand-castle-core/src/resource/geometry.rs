@@ -6,6 +6,8 @@ use getset::Getters;
 use glam::{Vec2, Vec3};
 use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
 
+use crate::bounds::{Aabb, BoundingSphere};
+
 use super::Id;
 
 pub mod cuboid;
@@ -24,6 +26,9 @@ pub struct Geometry {
   pub(crate) id: Id,
   pub(crate) vertices: Vec<Vertex>,
   pub(crate) indices: Vec<u32>,
+  /// Bounds of the vertex positions, computed at creation.
+  pub(crate) aabb: Aabb,
+  pub(crate) bounding_sphere: BoundingSphere,
 }
 
 pub trait ToGeometry {
@@ -42,8 +47,12 @@ impl Vertex {
 
 impl Geometry {
   pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
+    let positions = vertices.iter().map(|vertex| vertex.position);
+
     Self {
       id: Id::new(),
+      aabb: Aabb::from_points(positions.clone()).unwrap_or_default(),
+      bounding_sphere: BoundingSphere::from_points(positions).unwrap_or_default(),
       vertices,
       indices,
     }
@@ -2,8 +2,6 @@ use derive_builder::Builder;
 use getset::Getters;
 use glam::Vec3;
 
-use crate::resource::Id;
-
 use super::{Geometry, ToGeometry, Vertex};
 
 #[derive(Getters, Builder, Debug, Clone)]
@@ -67,9 +65,8 @@ impl ToGeometry for Cuboid {
 
     let normals = Geometry::calculate_normals(&vertices, &indices);
 
-    Geometry {
-      id: Id::new(),
-      vertices: vertices
+    Geometry::new(
+      vertices
         .into_iter()
         .zip(normals.into_iter())
         .map(|(position, normal)| Vertex {
@@ -79,6 +76,6 @@ impl ToGeometry for Cuboid {
         })
         .collect::<Vec<_>>(),
       indices,
-    }
+    )
   }
 }
@@ -1,4 +1,7 @@
 pub mod ambient_light;
 pub mod directional_light;
+pub mod hemisphere_light;
 pub mod point_light;
+pub mod rect_area_light;
+pub mod shadow;
 pub mod spot_light;
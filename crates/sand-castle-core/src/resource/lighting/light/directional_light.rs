@@ -2,6 +2,7 @@ use derive_builder::Builder;
 use getset::{Getters, Setters};
 use glam::Vec3;
 
+use super::shadow::Shadow;
 use crate::resource::{Id, Resource};
 
 #[derive(Getters, Setters, Builder, Debug, Clone)]
@@ -15,6 +16,13 @@ pub struct DirectionalLight {
   color: Vec3,
   #[builder(default)]
   direction: Vec3,
+  /// Illuminance in lux.
+  #[builder(default = "1.0")]
+  intensity: f32,
+  #[builder(default)]
+  cast_shadow: bool,
+  #[builder(default)]
+  shadow: Shadow,
 }
 
 impl DirectionalLight {
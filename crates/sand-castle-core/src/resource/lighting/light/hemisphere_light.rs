@@ -0,0 +1,33 @@
+use derive_builder::Builder;
+use getset::{Getters, Setters};
+use glam::Vec3;
+
+/// Ambient light fading from `ground_color` to `sky_color` along `up`, a cheap outdoor fill.
+/// `Default` emits no light.
+#[derive(Getters, Setters, Builder, Default, Debug, Clone)]
+#[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub", set = "pub")]
+pub struct HemisphereLight {
+  #[builder(default = "Vec3::new(1.0, 1.0, 1.0)")]
+  sky_color: Vec3,
+  #[builder(default)]
+  ground_color: Vec3,
+  #[builder(default = "Vec3::Y")]
+  up: Vec3,
+  #[builder(default = "1.0")]
+  intensity: f32,
+}
+
+impl HemisphereLight {
+  pub fn builder() -> HemisphereLightBuilder {
+    Default::default()
+  }
+}
+
+impl HemisphereLightBuilder {
+  pub fn build(self) -> HemisphereLight {
+    self
+      .fallible_build()
+      .expect("could not build `HemisphereLight`")
+  }
+}
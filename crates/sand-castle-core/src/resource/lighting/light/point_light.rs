@@ -15,6 +15,15 @@ pub struct PointLight {
   color: Vec3,
   #[builder(default)]
   position: Vec3,
+  /// Luminous intensity in candela.
+  #[builder(default = "1.0")]
+  intensity: f32,
+  /// Distance at which the light fades out completely, `0.0` for no cutoff.
+  #[builder(default)]
+  range: f32,
+  /// Exponent of the distance falloff, `2.0` is physically correct inverse-square.
+  #[builder(default = "2.0")]
+  decay: f32,
 }
 
 impl PointLight {
@@ -0,0 +1,49 @@
+use derive_builder::Builder;
+use getset::{Getters, Setters};
+use glam::{Quat, Vec3};
+
+use crate::resource::{Id, Resource};
+
+/// Rectangle emitting light from its front face, spanning the local X and Y axes and facing local
+/// -Z. `PhongMaterial` only receives its diffuse light.
+#[derive(Builder, Getters, Setters, Clone, Debug)]
+#[getset(get = "pub", set = "pub")]
+#[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
+pub struct RectAreaLight {
+  #[getset(skip)]
+  #[builder(default)]
+  id: Id,
+  #[builder(default = "Vec3::new(1.0, 1.0, 1.0)")]
+  color: Vec3,
+  /// Luminance in nits.
+  #[builder(default = "1.0")]
+  intensity: f32,
+  #[builder(default)]
+  position: Vec3,
+  #[builder(default)]
+  rotation: Quat,
+  #[builder(default = "1.0")]
+  width: f32,
+  #[builder(default = "1.0")]
+  height: f32,
+}
+
+impl RectAreaLight {
+  pub fn builder() -> RectAreaLightBuilder {
+    Default::default()
+  }
+}
+
+impl Resource for RectAreaLight {
+  fn id(&self) -> Id {
+    self.id
+  }
+}
+
+impl RectAreaLightBuilder {
+  pub fn build(self) -> RectAreaLight {
+    self
+      .fallible_build()
+      .expect("could not build `RectAreaLight`")
+  }
+}
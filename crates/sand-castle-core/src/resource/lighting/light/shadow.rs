@@ -0,0 +1,36 @@
+use derive_builder::Builder;
+use getset::{Getters, Setters};
+
+/// Shadow map settings of a light casting shadows.
+#[derive(Builder, Getters, Setters, Clone, Copy, Debug, PartialEq)]
+#[getset(get = "pub", set = "pub")]
+#[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
+pub struct Shadow {
+  /// Depth offset against shadow acne, in shadow map depth.
+  #[builder(default = "0.001")]
+  bias: f32,
+  /// Offset of the shaded point along its normal, in world units.
+  #[builder(default = "0.02")]
+  normal_bias: f32,
+  /// Radius of the PCF kernel in shadow map texels, `0.0` gives hard edges.
+  #[builder(default = "1.0")]
+  radius: f32,
+}
+
+impl Shadow {
+  pub fn builder() -> ShadowBuilder {
+    Default::default()
+  }
+}
+
+impl Default for Shadow {
+  fn default() -> Self {
+    Self::builder().build()
+  }
+}
+
+impl ShadowBuilder {
+  pub fn build(self) -> Shadow {
+    self.fallible_build().expect("could not build `Shadow`")
+  }
+}
@@ -2,6 +2,7 @@ use derive_builder::Builder;
 use getset::{Getters, Setters};
 use glam::Vec3;
 
+use super::shadow::Shadow;
 use crate::resource::{Id, Resource};
 
 #[derive(Builder, Getters, Setters, Clone, Debug)]
@@ -17,8 +18,25 @@ pub struct SpotLight {
   position: Vec3,
   #[builder(default)]
   direction: Vec3,
+  /// Luminous intensity in candela.
+  #[builder(default = "1.0")]
+  intensity: f32,
+  /// Distance at which the light fades out completely, `0.0` for no cutoff.
   #[builder(default)]
-  cutoff_angle: f32,
+  range: f32,
+  /// Exponent of the distance falloff, `2.0` is physically correct inverse-square.
+  #[builder(default = "2.0")]
+  decay: f32,
+  /// Angle from `direction` in radians where the light starts to fade out.
+  #[builder(default)]
+  inner_cone_angle: f32,
+  /// Angle from `direction` in radians past which nothing is lit.
+  #[builder(default = "std::f32::consts::FRAC_PI_4")]
+  outer_cone_angle: f32,
+  #[builder(default)]
+  cast_shadow: bool,
+  #[builder(default)]
+  shadow: Shadow,
 }
 
 impl SpotLight {
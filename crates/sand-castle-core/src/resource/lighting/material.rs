@@ -1,15 +1,79 @@
+use std::borrow::Cow;
+
 use basic::BasicMaterial;
 use getset::Getters;
+use glam::Vec4;
 use shader::ShaderMaterial;
-use wgpu::{BindGroupLayoutDescriptor, ShaderModuleDescriptor};
+use wgpu::{
+  BindGroupLayoutDescriptor, BlendComponent, BlendFactor, BlendOperation, BlendState, Face,
+  ShaderModuleDescriptor, ShaderSource,
+};
+
+use crate::{
+  fog::FOG_SHADER,
+  light_storage::LIGHTS_SHADER,
+  resource::{texture::TextureId, Id},
+};
+
+/// Like `include_wgsl!`, for the fragment shaders of the built-in materials calling `apply_fog`.
+///
+/// `FOG_SHADER` (`shaders/fog.wgsl`) is prepended, so the shaders use `fog` and `apply_fog`
+/// without declaring them.
+macro_rules! include_fragment_wgsl {
+  ($path:literal) => {
+    $crate::resource::lighting::material::fragment_shader($path, include_str!($path))
+  };
+}
 
-use crate::resource::{texture::TextureId, Id};
+/// `include_fragment_wgsl!` for the lit shaders, which also get `LIGHTS_SHADER` prepended.
+macro_rules! include_lit_fragment_wgsl {
+  ($path:literal) => {
+    $crate::resource::lighting::material::lit_fragment_shader($path, include_str!($path))
+  };
+}
 
 pub mod basic;
 pub mod pbr;
 pub mod phong;
 pub mod shader;
 
+/// How the alpha of a material is interpreted.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+  /// Alpha is ignored.
+  #[default]
+  Opaque,
+  /// Fragments with an alpha below the cutoff are discarded.
+  Mask(f32),
+  /// Blended over what is behind, drawn after the opaque subjects from back to front.
+  Blend,
+}
+
+impl AlphaMode {
+  pub fn is_transparent(&self) -> bool {
+    matches!(self, AlphaMode::Blend)
+  }
+
+  /// Alpha below which the built-in shaders discard a fragment.
+  pub fn cutoff(&self) -> f32 {
+    match self {
+      AlphaMode::Mask(cutoff) => *cutoff,
+      _ => 0.0,
+    }
+  }
+
+  pub(crate) fn blend_state(&self) -> Option<BlendState> {
+    self.is_transparent().then_some(BlendState {
+      color: BlendComponent {
+        src_factor: BlendFactor::SrcAlpha,
+        dst_factor: BlendFactor::OneMinusSrcAlpha,
+        operation: BlendOperation::Add,
+      },
+      alpha: BlendComponent::OVER,
+    })
+  }
+}
+
 #[derive(Getters, Debug, Clone)]
 #[getset(get = "pub")]
 pub struct Material {
@@ -24,6 +88,36 @@ pub struct Material {
   pub(crate) fragment_shader: ShaderModuleDescriptor<'static>,
   pub(crate) fragment_data: Vec<u8>,
   pub(crate) fragment_data_layout: BindGroupLayoutDescriptor<'static>,
+
+  pub(crate) alpha_mode: AlphaMode,
+  /// Multiplied with the diffuse map when the shadow pass tests the alpha of a masked subject.
+  pub(crate) mask_color: Vec4,
+  pub(crate) double_sided: bool,
+}
+
+impl Material {
+  pub(crate) fn cull_mode(&self) -> Option<Face> {
+    (!self.double_sided).then_some(Face::Back)
+  }
+}
+
+/// `source` preceded by `FOG_SHADER`, which every built-in fragment shader shares.
+pub(crate) fn fragment_shader(
+  label: &'static str,
+  source: &str,
+) -> ShaderModuleDescriptor<'static> {
+  ShaderModuleDescriptor {
+    label: Some(label),
+    source: ShaderSource::Wgsl(Cow::Owned(format!("{FOG_SHADER}\n{source}"))),
+  }
+}
+
+/// `fragment_shader` with `LIGHTS_SHADER` between `FOG_SHADER` and `source`.
+pub(crate) fn lit_fragment_shader(
+  label: &'static str,
+  source: &str,
+) -> ShaderModuleDescriptor<'static> {
+  fragment_shader(label, &format!("{LIGHTS_SHADER}\n{source}"))
 }
 
 pub trait ToMaterial {
@@ -8,7 +8,7 @@ use wgpu::{
 
 use crate::resource::{texture::TextureId, Id};
 
-use super::{Material, ToMaterial};
+use super::{AlphaMode, Material, ToMaterial};
 
 #[derive(Getters, Builder, Debug, Default, Clone)]
 #[getset(get = "pub")]
@@ -30,6 +30,12 @@ pub struct PbrMaterial {
 
   #[builder(default = "1.0")]
   roughness: f32,
+
+  #[builder(default)]
+  alpha_mode: AlphaMode,
+
+  #[builder(default)]
+  double_sided: bool,
 }
 
 impl PbrMaterialBuilder {
@@ -49,9 +55,9 @@ impl PbrMaterial {
 impl ToMaterial for PbrMaterial {
   fn to_material(&self) -> Material {
     let fragment_shader = if self.diffuse_map_texture_id.is_some() {
-      include_wgsl!("shaders/pbr/fs_pbr_tex.wgsl")
+      include_lit_fragment_wgsl!("shaders/pbr/fs_pbr_tex.wgsl")
     } else {
-      include_wgsl!("shaders/pbr/fs_pbr.wgsl")
+      include_lit_fragment_wgsl!("shaders/pbr/fs_pbr.wgsl")
     };
 
     Material {
@@ -75,8 +81,16 @@ impl ToMaterial for PbrMaterial {
       },
       fragment_data: Vec::from(bytemuck::cast_slice(&[
         self.color,
-        Vec4::new(self.roughness, self.metalness, 0.0, 0.0),
+        Vec4::new(
+          self.roughness,
+          self.metalness,
+          self.alpha_mode.cutoff(),
+          0.0,
+        ),
       ])),
+      alpha_mode: self.alpha_mode,
+      mask_color: self.color,
+      double_sided: self.double_sided,
     }
   }
 }
@@ -8,7 +8,7 @@ use wgpu::{
 
 use crate::resource::{texture::TextureId, Id};
 
-use super::{Material, ToMaterial};
+use super::{AlphaMode, Material, ToMaterial};
 
 #[derive(Getters, Builder, Debug, Default, Clone)]
 #[getset(get = "pub")]
@@ -24,6 +24,12 @@ pub struct PhongMaterial {
 
   #[builder(default)]
   color: Vec4,
+
+  #[builder(default)]
+  alpha_mode: AlphaMode,
+
+  #[builder(default)]
+  double_sided: bool,
 }
 
 impl PhongMaterialBuilder {
@@ -50,9 +56,9 @@ impl PhongMaterial {
 impl ToMaterial for PhongMaterial {
   fn to_material(&self) -> Material {
     let fragment_shader = if self.diffuse_map_texture_id.is_some() {
-      include_wgsl!("shaders/phong/fs_phong_tex.wgsl")
+      include_lit_fragment_wgsl!("shaders/phong/fs_phong_tex.wgsl")
     } else {
-      include_wgsl!("shaders/phong/fs_phong.wgsl")
+      include_lit_fragment_wgsl!("shaders/phong/fs_phong.wgsl")
     };
 
     Material {
@@ -74,7 +80,18 @@ impl ToMaterial for PhongMaterial {
           count: None,
         }],
       },
-      fragment_data: Vec::from(bytemuck::cast_slice(&[self.color])),
+      fragment_data: Vec::from(bytemuck::cast_slice(&[
+        self.color,
+        Vec4::new(self.alpha_mode.cutoff(), 0.0, 0.0, 0.0),
+      ])),
+      alpha_mode: self.alpha_mode,
+      // the textured shader only reads the alpha of the diffuse map
+      mask_color: if self.diffuse_map_texture_id.is_some() {
+        Vec4::ONE
+      } else {
+        self.color
+      },
+      double_sided: self.double_sided,
     }
   }
 }
@@ -2,11 +2,12 @@ use std::borrow::Cow;
 
 use derive_builder::Builder;
 use getset::Getters;
+use glam::Vec4;
 use wgpu::{BindGroupLayoutDescriptor, ShaderModuleDescriptor, ShaderSource};
 
 use crate::resource::{texture::TextureId, Id};
 
-use super::{Material, ToMaterial};
+use super::{AlphaMode, Material, ToMaterial};
 
 #[derive(Getters, Builder, Debug, Clone)]
 #[getset(get = "pub")]
@@ -24,6 +25,13 @@ pub struct ShaderMaterial {
   vertex_shader: Cow<'static, str>,
   #[builder(setter(into))]
   fragment_shader: Cow<'static, str>,
+
+  /// The shadow pass can't run the fragment shader, a `Mask` still casts a solid shadow.
+  #[builder(default)]
+  alpha_mode: AlphaMode,
+
+  #[builder(default)]
+  double_sided: bool,
 }
 
 impl ShaderMaterialBuilder {
@@ -59,6 +67,9 @@ impl ToMaterial for ShaderMaterial {
         entries: &[],
       },
       fragment_data: vec![],
+      alpha_mode: self.alpha_mode,
+      mask_color: Vec4::ONE,
+      double_sided: self.double_sided,
     }
   }
 }
@@ -5,9 +5,21 @@ struct VertexOutput {
 };
 
 @group(2) @binding(0)
-var<uniform> material_color: vec4<f32>;
+var<uniform> material: Material;
+
+struct Material {
+  color: vec4<f32>,
+  alpha_cutoff: f32,
+  pad0: f32,
+  pad1: f32,
+  pad2: f32,
+}
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-  return material_color;
+  if material.color.a < material.alpha_cutoff {
+    discard;
+  }
+
+  return apply_fog(material.color, in.world_position);
 }
@@ -5,6 +5,17 @@ struct VertexOutput {
   @location(2) tex_coords: vec2<f32>,
 };
 
+@group(2) @binding(0)
+var<uniform> material: Material;
+
+struct Material {
+  color: vec4<f32>,
+  alpha_cutoff: f32,
+  pad0: f32,
+  pad1: f32,
+  pad2: f32,
+}
+
 @group(6) @binding(0)
 var diffuse_map: texture_2d<f32>;
 @group(6) @binding(1)
@@ -12,5 +23,11 @@ var diffuse_sampler: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-  return textureSample(diffuse_map, diffuse_sampler, in.tex_coords);
+  let color = textureSample(diffuse_map, diffuse_sampler, in.tex_coords);
+
+  if color.a < material.alpha_cutoff {
+    discard;
+  }
+
+  return apply_fog(color, in.world_position);
 }
@@ -17,101 +17,97 @@ struct Camera {
 @group(2) @binding(0)
 var<uniform> material: Material;
 
+// color of the material tinted by the diffuse map, sampled once per fragment
+var<private> base_color: vec4<f32>;
+
 struct Material {
   color: vec4<f32>,
   roughness: f32,
   metalness: f32,
+  alpha_cutoff: f32,
   pad0: f32,
-  pad1: f32,
 }
 
-@group(4) @binding(0)
-var<uniform> ambient_light: vec4<f32>;
-
-@group(5) @binding(0)
-var<uniform> directional_lights: array<DirectionalLight, 16>;
-@group(5) @binding(1)
-var<uniform> point_lights: array<PointLight, 16>;
-@group(5) @binding(2)
-var<uniform> spot_lights: array<SpotLight, 16>;
-@group(5) @binding(3)
-var<uniform> directional_light_count: LightCount;
-@group(5) @binding(4)
-var<uniform> point_light_count: LightCount;
-@group(5) @binding(5)
-var<uniform> spot_light_count: LightCount;
+// the environment lighting, black without an environment
+@group(4) @binding(1)
+var irradiance_map: texture_cube<f32>;
+@group(4) @binding(2)
+var prefiltered_map: texture_cube<f32>;
+@group(4) @binding(3)
+var brdf_lut: texture_2d<f32>;
+@group(4) @binding(4)
+var environment_sampler: sampler;
+
+@group(5) @binding(8)
+var ltc_tables: texture_2d_array<f32>;
+@group(5) @binding(9)
+var ltc_sampler: sampler;
 
 @group(6) @binding(0)
 var diffuse_map: texture_2d<f32>;
 @group(6) @binding(1)
 var diffuse_sampler: sampler;
 
-struct SpotLight {
-  point_light: PointLight,
-  direction: vec3<f32>,
-  cutoff_angle: f32,
-}
-
-struct PointLight {
-  pos: vec3<f32>,
-  color: vec3<f32>,
-}
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+  base_color = material.color * textureSample(diffuse_map, diffuse_sampler, vertex.tex_coords);
 
-struct DirectionalLight {
-  direction: vec3<f32>,
-  color: vec3<f32>,
-}
+  if base_color.a < material.alpha_cutoff {
+    discard;
+  }
 
-struct LightCount {
-  value: u32,
-  padding0: u32,
-  padding1: u32,
-  padding2: u32,
-}
+  let albedo = vec4<f32>(base_color.rgb * (1.0 - material.metalness), 1.0);
+  var light_influence = (ambient_light.color + hemisphere_light(normalize(vertex.normal))) * albedo
+    + environment_light(vertex);
 
-@fragment
-fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
-  var light_influence = ambient_light;
+  for (var index: u32 = 0; index < light_counts.directional; index++) {
+    light_influence += emission_from_directional_light(directional_lights[index], vertex);
+  }
 
-  for (var index: u32 = 0; index < point_light_count.value; index++) {
+  for (var index: u32 = 0; index < light_counts.point; index++) {
     light_influence += emission_from_point_light(point_lights[index], vertex);
   }
 
-  for (var index: u32 = 0; index < spot_light_count.value; index++) {
+  for (var index: u32 = 0; index < light_counts.spot; index++) {
     light_influence += emission_from_spot_light(spot_lights[index], vertex);
   }
 
-  light_influence = light_influence / (light_influence + vec4<f32>(1.0, 1.0, 1.0, 0.0));
-
-  let gamma_corrected = vec4<f32>(pow(light_influence.xyz, vec3<f32>(1.0 / 2.2, 1.0 / 2.2, 1.0 / 2.2)), 1.0);
+  for (var index: u32 = 0; index < light_counts.rect_area; index++) {
+    light_influence += emission_from_rect_area_light(rect_area_lights[index], vertex);
+  }
 
-  return gamma_corrected * textureSample(diffuse_map, diffuse_sampler, vertex.tex_coords);
+  // linear radiance, tone mapped and encoded once the whole frame is drawn
+  return apply_fog(vec4<f32>(light_influence.rgb, base_color.a), vertex.world_position);
 }
 
 const PI = radians(180.0);
 
-const attenuation_const: f32 = 1.0;
-const attenuation_linear: f32 = 0.045;
-const attenuation_quad: f32 = 0.0075;
-
 fn emission_from_point_light(
   light: PointLight,
   vertex: VertexOutput,
 ) -> vec4<f32> {
-  let vertex_normal = normalize(vertex.normal);
+  let to_light = light.pos - vertex.world_position;
+  let light_distance = length(to_light);
+
+  let light_intensity = light.color * light.intensity * distance_attenuation(light, light_distance);
 
-  var light_to_pixel = normalize(light.pos - vertex.world_position);
-  let light_distance = length(light_to_pixel);
+  return emission(to_light / light_distance, light_intensity, vertex);
+}
 
-  var light_intensity = light.color;
-  light_intensity = light_intensity / (light_distance * light_distance);
+// Cook-Torrance reflectance of light arriving from `light_direction`, pointing towards the light.
+fn emission(
+  light_direction: vec3<f32>,
+  light_intensity: vec3<f32>,
+  vertex: VertexOutput,
+) -> vec4<f32> {
+  let vertex_normal = normalize(vertex.normal);
 
   let view_normal = normalize(camera.position - vertex.world_position);
-  let half_vector = normalize(view_normal + light_to_pixel);
+  let half_vector = normalize(view_normal + light_direction);
 
   let f = schlick(max(dot(view_normal, half_vector), 0.0));
 
-  let light_dot = max(dot(vertex_normal, light_to_pixel), 0.0000001);
+  let light_dot = max(dot(vertex_normal, light_direction), 0.0000001);
   let view_dot = max(dot(vertex_normal, view_normal), 0.0000001);
 
   var specular = ggx(max(dot(vertex_normal, half_vector), 0.0))
@@ -120,7 +116,7 @@ fn emission_from_point_light(
     * geom_smith(view_dot);
   specular = specular / max(4.0 * view_dot * light_dot, 0.0000001);
 
-  let albedo = material.color.xyz;
+  let albedo = base_color.rgb;
 
   var kd = 1.0 - f;
   kd = kd * (1.0 - material.metalness);
@@ -148,7 +144,7 @@ fn geom_smith(dp: f32) -> f32 {
 fn schlick(v_dot_h: f32) -> vec3<f32> {
   let dielectric_f0 = vec3<f32>(0.04, 0.04, 0.04);
 
-  let f0 = mix(dielectric_f0, material.color.xyz, material.metalness);
+  let f0 = mix(dielectric_f0, base_color.rgb, material.metalness);
 
   return f0 + (1 - f0) * pow(clamp(1.0 - v_dot_h, 0.0, 1.0), 5.0);
 }
@@ -157,22 +153,89 @@ fn emission_from_spot_light(
   light: SpotLight,
   vertex: VertexOutput,
 ) -> vec4<f32> {
-  let light_to_pixel = normalize(light.point_light.pos - vertex.world_position);
-  let spot_factor = dot(light_to_pixel, light.direction);
+  let cone = spot_cone(light, normalize(light.point_light.pos - vertex.world_position));
 
-  if spot_factor > light.cutoff_angle {
+  if cone <= 0.0 {
     return vec4<f32>(0.0, 0.0, 0.0, 0.0);
   }
 
   let color = emission_from_point_light(light.point_light, vertex);
-  let intensity = (1.0 - (1.0 - spot_factor) / (1.0 - light.cutoff_angle));
+  let shadow = shadow_factor(light.shadow_index, vertex.world_position, vertex.normal);
 
-  return color * intensity;
+  return vec4<f32>(color.rgb * cone * shadow, color.a);
 }
 
 fn emission_from_directional_light(
   light: DirectionalLight,
   vertex: VertexOutput,
 ) -> vec4<f32> {
-  return vec4<f32>(light.color, 1.0);
+  let radiance = emission(-normalize(light.direction), light.color * light.intensity, vertex);
+
+  let shadow = shadow_factor(light.shadow_index, vertex.world_position, vertex.normal);
+
+  return vec4<f32>(radiance.rgb * shadow, 1.0);
+}
+
+// Cook-Torrance reflectance of a rect area light, integrated over its surface with linearly
+// transformed cosines.
+fn emission_from_rect_area_light(
+  light: RectAreaLight,
+  vertex: VertexOutput,
+) -> vec4<f32> {
+  let vertex_normal = normalize(vertex.normal);
+  let view_normal = normalize(camera.position - vertex.world_position);
+  let frame = shading_frame(vertex_normal, view_normal);
+
+  let uv = vec2<f32>(material.roughness, sqrt(1.0 - clamp(dot(vertex_normal, view_normal), 0.0, 1.0)))
+    * LTC_SCALE + LTC_BIAS;
+  let t1 = textureSampleLevel(ltc_tables, ltc_sampler, uv, 0, 0.0);
+  let t2 = textureSampleLevel(ltc_tables, ltc_sampler, uv, 1, 0.0);
+  let m_inv = mat3x3<f32>(
+    vec3<f32>(t1.x, 0.0, t1.y),
+    vec3<f32>(0.0, 1.0, 0.0),
+    vec3<f32>(t1.z, 0.0, t1.w),
+  );
+
+  let f0 = mix(vec3<f32>(0.04, 0.04, 0.04), base_color.rgb, material.metalness);
+  let fresnel = f0 * t2.x + (1.0 - f0) * t2.y;
+
+  let specular = fresnel * rect_form_factor(light, vertex.world_position, m_inv * frame);
+  let diffuse = base_color.rgb * (1.0 - material.metalness)
+    * rect_form_factor(light, vertex.world_position, frame);
+
+  return vec4<f32>((diffuse + specular) * light.color * light.intensity, 1.0);
+}
+
+// The LTC tables are sampled at texel centers.
+const LTC_SCALE = 63.0 / 64.0;
+const LTC_BIAS = 0.5 / 64.0;
+
+// Split sum approximation of the environment reflected by the Cook-Torrance BRDF.
+fn environment_light(vertex: VertexOutput) -> vec4<f32> {
+  let vertex_normal = normalize(vertex.normal);
+  let view_normal = normalize(camera.position - vertex.world_position);
+  let n_dot_v = max(dot(vertex_normal, view_normal), 0.0);
+
+  // cubemap faces are laid out for a left handed frame, the world is right handed
+  let flip = vec3<f32>(-1.0, 1.0, 1.0);
+  let reflection = reflect(-view_normal, vertex_normal) * flip;
+
+  let albedo = base_color.rgb;
+  let f0 = mix(vec3<f32>(0.04, 0.04, 0.04), albedo, material.metalness);
+  // rough surfaces reflect less at grazing angles
+  let fresnel = f0 + (max(vec3<f32>(1.0 - material.roughness), f0) - f0) * pow(1.0 - n_dot_v, 5.0);
+  let kd = (1.0 - fresnel) * (1.0 - material.metalness);
+
+  let irradiance = textureSampleLevel(irradiance_map, environment_sampler, vertex_normal * flip, 0.0).rgb;
+  let prefiltered = textureSampleLevel(
+    prefiltered_map,
+    environment_sampler,
+    reflection,
+    material.roughness * ambient_light.environment.y,
+  ).rgb;
+  let brdf = textureSampleLevel(brdf_lut, environment_sampler, vec2<f32>(n_dot_v, material.roughness), 0.0).xy;
+
+  let radiance = kd * albedo * irradiance + prefiltered * (f0 * brdf.x + brdf.y);
+
+  return vec4<f32>(radiance * ambient_light.environment.x, 1.0);
 }
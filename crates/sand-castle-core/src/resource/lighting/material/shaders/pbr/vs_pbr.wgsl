@@ -32,7 +32,7 @@ fn vs_main(
   let world_position: vec4<f32> = transformation * vec4<f32>(position, 1.0);
 
   out.world_position = world_position.xyz;
-  out.normal = view_matrix_normal * normal;
+  out.normal = normalize(view_matrix_normal * normal);
   out.clip_position = camera.view_matrix * world_position;
   out.tex_coords = tex_coords;
 
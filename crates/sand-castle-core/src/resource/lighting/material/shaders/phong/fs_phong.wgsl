@@ -5,76 +5,55 @@ struct VertexOutput {
 };
 
 @group(2) @binding(0)
-var<uniform> material_color: vec4<f32>;
-
-@group(4) @binding(0)
-var<uniform> ambient_light: vec4<f32>;
-
-@group(5) @binding(0)
-var<uniform> directional_lights: array<DirectionalLight, 16>;
-@group(5) @binding(1)
-var<uniform> point_lights: array<PointLight, 16>;
-@group(5) @binding(2)
-var<uniform> spot_lights: array<SpotLight, 16>;
-@group(5) @binding(3)
-var<uniform> directional_light_count: LightCount;
-@group(5) @binding(4)
-var<uniform> point_light_count: LightCount;
-@group(5) @binding(5)
-var<uniform> spot_light_count: LightCount;
-
-struct SpotLight {
-  point_light: PointLight,
-  direction: vec3<f32>,
-  cutoff_angle: f32,
-}
-
-struct PointLight {
-  pos: vec3<f32>,
-  color: vec3<f32>,
-}
-
-struct DirectionalLight {
-  direction: vec3<f32>,
-  color: vec3<f32>,
-}
-
-struct LightCount {
-  value: u32,
-  padding0: u32,
-  padding1: u32,
-  padding2: u32,
+var<uniform> material: Material;
+
+struct Material {
+  color: vec4<f32>,
+  alpha_cutoff: f32,
+  pad0: f32,
+  pad1: f32,
+  pad2: f32,
 }
 
 @fragment
 fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
-  var light_influence = ambient_light;
+  var light_influence = ambient_light.color + hemisphere_light(normalize(vertex.normal));
 
-  for (var index: u32 = 0; index < point_light_count.value; index++) {
+  for (var index: u32 = 0; index < light_counts.directional; index++) {
+    light_influence += emission_from_directional_light(directional_lights[index], vertex);
+  }
+
+  for (var index: u32 = 0; index < light_counts.point; index++) {
     light_influence += emission_from_point_light(point_lights[index], vertex);
   }
 
-  for (var index: u32 = 0; index < spot_light_count.value; index++) {
+  for (var index: u32 = 0; index < light_counts.spot; index++) {
     light_influence += emission_from_spot_light(spot_lights[index], vertex);
   }
 
-  return light_influence * material_color;
-}
+  for (var index: u32 = 0; index < light_counts.rect_area; index++) {
+    light_influence += emission_from_rect_area_light(rect_area_lights[index], vertex);
+  }
 
-const attenuation_const: f32 = 1.0;
-const attenuation_linear: f32 = 0.045;
-const attenuation_quad: f32 = 0.0075;
+  let color = material.color;
+
+  if color.a < material.alpha_cutoff {
+    discard;
+  }
+
+  return apply_fog(vec4<f32>(light_influence.rgb * color.rgb, color.a), vertex.world_position);
+}
 
 fn emission_from_point_light(
   light: PointLight,
   vertex: VertexOutput,
 ) -> vec4<f32> {
-  let light_to_pixel = normalize(light.pos - vertex.world_position);
-  let light_distance = length(light_to_pixel);
-  let light_direction = light_to_pixel / light_distance;
+  let to_light = light.pos - vertex.world_position;
+  let light_distance = length(to_light);
+  let light_direction = to_light / light_distance;
 
-  let attenuation = 1.0 / (attenuation_const + attenuation_linear * light_distance + attenuation_quad * (light_distance * light_distance));
-  let diffuse = light.color * attenuation * max(dot(vertex.normal, light_direction), 0.0);
+  let radiance = light.color * light.intensity * distance_attenuation(light, light_distance);
+  let diffuse = radiance * max(dot(normalize(vertex.normal), light_direction), 0.0);
 
   return vec4<f32>(diffuse, 1.0);
 }
@@ -83,22 +62,38 @@ fn emission_from_spot_light(
   light: SpotLight,
   vertex: VertexOutput,
 ) -> vec4<f32> {
-  let light_to_pixel = normalize(light.point_light.pos - vertex.world_position);
-  let spot_factor = dot(light_to_pixel, light.direction);
+  let cone = spot_cone(light, normalize(light.point_light.pos - vertex.world_position));
 
-  if spot_factor > light.cutoff_angle {
+  if cone <= 0.0 {
     return vec4<f32>(0.0, 0.0, 0.0, 0.0);
   }
 
   let color = emission_from_point_light(light.point_light, vertex);
-  let intensity = (1.0 - (1.0 - spot_factor) / (1.0 - light.cutoff_angle));
+  let shadow = shadow_factor(light.shadow_index, vertex.world_position, vertex.normal);
 
-  return color * intensity;
+  return vec4<f32>(color.rgb * cone * shadow, color.a);
 }
 
 fn emission_from_directional_light(
   light: DirectionalLight,
   vertex: VertexOutput,
 ) -> vec4<f32> {
-  return vec4<f32>(light.color, 1.0);
+  let radiance = light.color * light.intensity;
+  let diffuse = radiance * max(dot(normalize(vertex.normal), -normalize(light.direction)), 0.0);
+
+  let shadow = shadow_factor(light.shadow_index, vertex.world_position, vertex.normal);
+
+  return vec4<f32>(diffuse * shadow, 1.0);
+}
+
+// Lambertian reflectance of a rect area light, integrated over its surface.
+fn emission_from_rect_area_light(
+  light: RectAreaLight,
+  vertex: VertexOutput,
+) -> vec4<f32> {
+  let vertex_normal = normalize(vertex.normal);
+  let frame = shading_frame(vertex_normal, light.position - vertex.world_position);
+  let irradiance = rect_form_factor(light, vertex.world_position, frame);
+
+  return vec4<f32>(light.color * light.intensity * irradiance, 1.0);
 }
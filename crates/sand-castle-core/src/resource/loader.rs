@@ -1,6 +1,6 @@
-use super::geometry::Geometry;
+use super::{geometry::Geometry, lighting::material::AlphaMode, texture::TextureId};
 use getset::Getters;
-use glam::{Quat, Vec3};
+use glam::{Quat, Vec3, Vec4};
 
 #[cfg(feature = "to_url")]
 use js_sys::{Array, Uint8Array};
@@ -25,6 +25,16 @@ pub struct LoadedTransform {
   pub scale: Vec3,
 }
 
+/// Material properties of a loaded primitive.
+#[derive(Getters, Clone, Copy)]
+#[getset(get = "pub")]
+pub struct LoadedMaterial {
+  pub color: Vec4,
+  pub diffuse_map_texture_id: Option<TextureId>,
+  pub alpha_mode: AlphaMode,
+  pub double_sided: bool,
+}
+
 #[derive(Getters, Clone)]
 #[getset(get = "pub")]
 pub struct Textures {
@@ -1,6 +1,9 @@
 #[cfg(feature = "loader_gltf")]
 pub use gltf::{buffer::Source as BufferSource, image::Source, import_buffers, Document, Gltf};
 
+#[cfg(feature = "loader_gltf")]
+use crate::resource::lighting::material::AlphaMode;
+
 #[derive(Debug, Clone)]
 pub struct LoadGltfError;
 
@@ -31,3 +34,13 @@ impl LoadGltf for Gltf {
     Self::from_slice(&binary).map_err(|_| LoadGltfError)
   }
 }
+
+#[cfg(feature = "loader_gltf")]
+pub fn alpha_mode(material: &gltf::Material) -> AlphaMode {
+  match material.alpha_mode() {
+    gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+    // the cutoff defaults to 0.5 in the spec
+    gltf::material::AlphaMode::Mask => AlphaMode::Mask(material.alpha_cutoff().unwrap_or(0.5)),
+    gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+  }
+}
@@ -1,10 +1,11 @@
 use getset::Getters;
-use glam::{Quat, Vec3};
+use glam::{Mat4, Quat, Vec3};
 
 use crate::{renderer::Renderer, scene::Scene};
 
 use super::{Id, Resource};
 
+pub mod group;
 pub mod mesh;
 
 #[derive(Getters, Clone, Copy, Debug, PartialEq)]
@@ -25,12 +26,42 @@ impl Default for Scale {
   }
 }
 
+impl From<Vec3> for Scale {
+  fn from(scale: Vec3) -> Self {
+    Self {
+      width: scale.x,
+      height: scale.y,
+      depth: scale.z,
+    }
+  }
+}
+
+impl From<Scale> for Vec3 {
+  fn from(scale: Scale) -> Self {
+    Vec3::new(scale.width, scale.height, scale.depth)
+  }
+}
+
 pub trait Object3D: Transform {
   fn geometry_id(&self) -> Option<Id>;
   fn material_id(&self) -> Option<Id>;
 
   fn set_geometry_id(&mut self, geometry_id: Id);
   fn set_material_id(&mut self, material_id: Id);
+
+  /// Whether the object is drawn into the shadow maps of the lights.
+  fn cast_shadow(&self) -> bool {
+    false
+  }
+  /// Whether shadows of other objects darken the object.
+  fn receive_shadow(&self) -> bool {
+    false
+  }
+
+  /// Ignored by objects that can't cast shadows.
+  fn set_cast_shadow(&mut self, _cast_shadow: bool) {}
+  /// Ignored by objects that can't receive shadows.
+  fn set_receive_shadow(&mut self, _receive_shadow: bool) {}
 }
 
 pub trait Transform {
@@ -41,10 +72,27 @@ pub trait Transform {
   fn set_rot(&mut self, rot: Quat);
   fn set_pos(&mut self, pos: Vec3);
   fn set_scale(&mut self, scale: Scale);
+
+  /// Transform relative to the parent node, or to the world for nodes without a parent.
+  fn matrix(&self) -> Mat4 {
+    Mat4::from_scale_rotation_translation((*self.scale()).into(), *self.rot(), *self.pos())
+  }
 }
 
-pub trait SceneTransform: Transform + Resource {
-  fn update_rot(&mut self, scene: &Scene, renderer: &Renderer, rot: Quat);
-  fn update_pos(&mut self, scene: &Scene, renderer: &Renderer, pos: Vec3);
-  fn update_scale(&mut self, scene: &Scene, renderer: &Renderer, scale: Scale);
+/// Updates the transform of a node and of all its descendants in a `Scene`.
+pub trait SceneTransform: Transform + Resource + Sized {
+  fn update_rot(&mut self, scene: &mut Scene, renderer: &Renderer, rot: Quat) {
+    self.set_rot(rot);
+    scene.update_transform(renderer, self);
+  }
+
+  fn update_pos(&mut self, scene: &mut Scene, renderer: &Renderer, pos: Vec3) {
+    self.set_pos(pos);
+    scene.update_transform(renderer, self);
+  }
+
+  fn update_scale(&mut self, scene: &mut Scene, renderer: &Renderer, scale: Scale) {
+    self.set_scale(scale);
+    scene.update_transform(renderer, self);
+  }
 }
@@ -0,0 +1,67 @@
+use derive_builder::Builder;
+use glam::{Quat, Vec3};
+
+use super::{Scale, SceneTransform, Transform};
+use crate::resource::{Id, Resource};
+
+/// A node without geometry whose transform is applied to the nodes parented to it with
+/// `Scene::set_parent`.
+#[derive(Debug, Builder)]
+#[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
+pub struct Group {
+  #[builder(setter(skip))]
+  id: Id,
+
+  #[builder(default)]
+  scale: Scale,
+  #[builder(default)]
+  position: Vec3,
+  #[builder(default)]
+  rotation: Quat,
+}
+
+impl GroupBuilder {
+  pub fn build(self) -> Group {
+    self.fallible_build().expect("failed to build `Group`")
+  }
+}
+
+impl Group {
+  pub fn builder() -> GroupBuilder {
+    GroupBuilder::default()
+  }
+}
+
+impl Resource for Group {
+  fn id(&self) -> Id {
+    self.id
+  }
+}
+
+impl Transform for Group {
+  fn pos(&self) -> &Vec3 {
+    &self.position
+  }
+
+  fn rot(&self) -> &Quat {
+    &self.rotation
+  }
+
+  fn set_pos(&mut self, pos: Vec3) {
+    self.position = pos;
+  }
+
+  fn set_rot(&mut self, rot: Quat) {
+    self.rotation = rot;
+  }
+
+  fn scale(&self) -> &Scale {
+    &self.scale
+  }
+
+  fn set_scale(&mut self, dim: Scale) {
+    self.scale = dim;
+  }
+}
+
+impl SceneTransform for Group {}
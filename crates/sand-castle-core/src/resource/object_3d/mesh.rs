@@ -2,14 +2,10 @@ pub mod instanced;
 
 use derive_builder::Builder;
 use getset::Getters;
-use glam::{Mat4, Quat, Vec3};
+use glam::{Quat, Vec3};
 
 use super::{Object3D, Scale, SceneTransform, Transform};
-use crate::{
-  renderer::Renderer,
-  resource::{Id, Resource},
-  scene::{Scene, Subject},
-};
+use crate::resource::{Id, Resource};
 
 #[derive(Debug, Getters, Builder)]
 #[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
@@ -23,6 +19,11 @@ pub struct Mesh {
   #[builder(default, setter(strip_option))]
   material_id: Option<Id>,
 
+  #[builder(default)]
+  cast_shadow: bool,
+  #[builder(default)]
+  receive_shadow: bool,
+
   #[getset(skip)]
   scale: Scale,
   #[getset(skip)]
@@ -65,6 +66,22 @@ impl Object3D for Mesh {
   fn set_material_id(&mut self, material_id: Id) {
     self.material_id = Some(material_id);
   }
+
+  fn cast_shadow(&self) -> bool {
+    self.cast_shadow
+  }
+
+  fn receive_shadow(&self) -> bool {
+    self.receive_shadow
+  }
+
+  fn set_cast_shadow(&mut self, cast_shadow: bool) {
+    self.cast_shadow = cast_shadow;
+  }
+
+  fn set_receive_shadow(&mut self, receive_shadow: bool) {
+    self.receive_shadow = receive_shadow;
+  }
 }
 
 impl Transform for Mesh {
@@ -93,78 +110,4 @@ impl Transform for Mesh {
   }
 }
 
-impl SceneTransform for Mesh {
-  fn update_pos(&mut self, scene: &Scene, renderer: &Renderer, pos: Vec3) {
-    self.set_pos(pos);
-
-    let Some(Subject {
-      transform: (transform, _),
-      normal: (normal, _),
-      ..
-    }) = &scene.subjects().get(&self.id())
-    else {
-      return;
-    };
-
-    renderer.queue().write_buffer(
-      transform,
-      0,
-      bytemuck::cast_slice(&[Mat4::from_translation(pos) * Mat4::from_quat(self.rot().clone())]),
-    );
-    renderer.queue().write_buffer(
-      normal,
-      0,
-      bytemuck::cast_slice(&[Mat4::from_quat(self.rot().clone())]),
-    );
-  }
-
-  fn update_rot(&mut self, scene: &Scene, renderer: &Renderer, rot: Quat) {
-    self.set_rot(rot);
-
-    let Some(Subject {
-      transform: (transform, _),
-      normal: (normal, _),
-      ..
-    }) = &scene.subjects().get(&self.id())
-    else {
-      return;
-    };
-
-    renderer.queue().write_buffer(
-      transform,
-      0,
-      bytemuck::cast_slice(&[Mat4::from_translation(self.pos().clone()) * Mat4::from_quat(rot)]),
-    );
-    renderer.queue().write_buffer(
-      normal,
-      0,
-      bytemuck::cast_slice(&[Mat4::from_quat(self.rot().clone())]),
-    );
-  }
-
-  fn update_scale(&mut self, scene: &Scene, renderer: &Renderer, scale: Scale) {
-    self.set_scale(scale);
-
-    let Some(Subject {
-      transform: (transform, _),
-      normal: (normal, _),
-      ..
-    }) = &scene.subjects().get(&self.id())
-    else {
-      return;
-    };
-
-    renderer.queue().write_buffer(
-      transform,
-      0,
-      bytemuck::cast_slice(&[
-        Mat4::from_translation(self.pos().clone()) * Mat4::from_quat(self.rot().clone())
-      ]),
-    );
-    renderer.queue().write_buffer(
-      normal,
-      0,
-      bytemuck::cast_slice(&[Mat4::from_quat(self.rot().clone())]),
-    );
-  }
-}
+impl SceneTransform for Mesh {}
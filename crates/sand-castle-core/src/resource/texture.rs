@@ -12,9 +12,10 @@ use web_sys::{
 };
 
 use derive_more::{Deref, From};
+use half::f16;
 
 #[cfg(all(feature = "loader_textures", not(feature = "to_url")))]
-use image::{ImageError, ImageFormat};
+use image::{DynamicImage, ImageError, ImageFormat};
 
 use uuid::Uuid;
 
@@ -36,11 +37,38 @@ impl Default for TextureId {
   }
 }
 
+/// How the pixels of a `Texture` are stored.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum TexelFormat {
+  /// 8 bit RGBA in sRGB, as decoded from PNG or JPEG images.
+  #[default]
+  Rgba8Srgb,
+  /// Linear RGBA half floats, as decoded from HDR images such as Radiance or OpenEXR files.
+  Rgba16Float,
+}
+
+impl TexelFormat {
+  pub(crate) fn wgpu_format(&self) -> wgpu::TextureFormat {
+    match self {
+      Self::Rgba8Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+      Self::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
+    }
+  }
+
+  pub(crate) fn bytes_per_texel(&self) -> u32 {
+    match self {
+      Self::Rgba8Srgb => 4,
+      Self::Rgba16Float => 8,
+    }
+  }
+}
+
 #[derive(Getters)]
 #[getset(get = "pub")]
 pub struct Texture {
   pub(crate) id: TextureId,
   pub(crate) dimensions: (u32, u32),
+  pub(crate) format: TexelFormat,
   pub(crate) content: Vec<u8>,
 }
 
@@ -70,6 +98,30 @@ pub enum LoadTextureError {
 }
 
 impl Texture {
+  /// Texture of `dimensions` from tightly packed RGBA8 pixels in sRGB, row by row from the top.
+  pub fn from_rgba(dimensions: (u32, u32), content: Vec<u8>) -> Self {
+    Self {
+      id: TextureId::new(),
+      dimensions,
+      format: TexelFormat::Rgba8Srgb,
+      content,
+    }
+  }
+
+  /// HDR texture of `dimensions` from linear RGBA floats, row by row from the top. They are
+  /// stored as half floats.
+  pub fn from_rgba_f32(dimensions: (u32, u32), pixels: &[f32]) -> Self {
+    Self {
+      id: TextureId::new(),
+      dimensions,
+      format: TexelFormat::Rgba16Float,
+      content: pixels
+        .iter()
+        .flat_map(|value| f16::from_f32(*value).to_le_bytes())
+        .collect(),
+    }
+  }
+
   #[cfg(feature = "loader_textures")]
   pub async fn from_url(url: &str) -> Result<Self, FromUrlError> {
     let (send_rgba, recv_rgba) = async_channel::bounded(1);
@@ -129,6 +181,7 @@ impl Texture {
           .send(Self {
             id: Default::default(),
             dimensions,
+            format: TexelFormat::Rgba8Srgb,
             content: data.data().0,
           })
           .await;
@@ -159,10 +212,14 @@ impl Texture {
     )
     .map_err(LoadTextureError::Image)?;
 
-    Ok(Texture {
-      id: TextureId::new(),
-      dimensions: (image.width(), image.height()),
-      content: image.to_rgba8().into_vec(),
+    let dimensions = (image.width(), image.height());
+
+    // float images such as Radiance or OpenEXR files keep their high dynamic range
+    Ok(match image {
+      DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+        Texture::from_rgba_f32(dimensions, &image.to_rgba32f())
+      }
+      image => Texture::from_rgba(dimensions, image.to_rgba8().into_vec()),
     })
   }
 }
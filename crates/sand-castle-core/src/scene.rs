@@ -3,17 +3,17 @@ use std::sync::Arc;
 use bytemuck::{Pod, Zeroable};
 use derive_builder::Builder;
 use getset::Getters;
-use glam::{Mat4, UVec3, UVec4, Vec3, Vec4};
+use glam::{Mat3, Mat4, UVec4, Vec2, Vec3, Vec4};
 use indexmap::IndexMap;
 use wgpu::{
   util::{BufferInitDescriptor, DeviceExt},
   AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
-  BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, BlendComponent,
-  BlendFactor, BlendOperation, BlendState, Buffer, BufferBindingType, BufferUsages, Color,
-  ColorTargetState, ColorWrites, CompareFunction, DepthBiasState, DepthStencilState, Extent3d,
-  Face, FilterMode, FragmentState, FrontFace, ImageCopyTexture, ImageDataLayout, IndexFormat,
-  LoadOp, MultisampleState, Operations, Origin3d, PipelineLayoutDescriptor, PolygonMode,
-  PrimitiveState, PrimitiveTopology, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
+  BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+  BufferBindingType, BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder,
+  CompareFunction, DepthBiasState, DepthStencilState, Extent3d, Features, FilterMode,
+  FragmentState, FrontFace, ImageCopyTexture, ImageDataLayout, IndexFormat, LoadOp, Maintain,
+  MultisampleState, Operations, Origin3d, PipelineLayoutDescriptor, PolygonMode, PrimitiveState,
+  PrimitiveTopology, RenderPass, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
   RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerBindingType,
   SamplerDescriptor, ShaderStages, StencilState, StoreOp, Texture, TextureAspect,
   TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
@@ -30,22 +30,45 @@ extern "C" {
 }
 
 use crate::{
+  bounds::{Aabb, Frustum},
+  capture::{CaptureError, FrameCapture, Readback},
+  fog::{Fog, FogBuffer},
+  ibl::EnvironmentLighting,
+  light_storage::{
+    self, LightArrayError, LightKind, LightStorage, TooManyLights, MAX_UNIFORM_LIGHTS,
+  },
+  ltc::LtcTables,
+  picking::{pixel_ndc, PickError, PickIndex, PickResult, Picker},
+  post_processing::{
+    chain::PostChain, tone_mapping::ToneMapping, PostEffect, PostPass, PostProcessingError,
+    PostStage,
+  },
+  raycast::{Ray, RaycastHit},
   renderer::Renderer,
   resource::{
     camera::Camera,
+    environment::{Environment, EnvironmentError},
     geometry::Geometry,
-    lighting::light::{
-      ambient_light::AmbientLight, directional_light::DirectionalLight, point_light::PointLight,
-      spot_light::SpotLight,
+    lighting::{
+      light::{
+        ambient_light::AmbientLight, directional_light::DirectionalLight,
+        hemisphere_light::HemisphereLight, point_light::PointLight, rect_area_light::RectAreaLight,
+        shadow::Shadow, spot_light::SpotLight,
+      },
+      material::{AlphaMode, Material},
     },
     loader::{geometry::GeometryLoader, material::MaterialLoader, textures::TextureLoader},
-    object_3d::Object3D,
+    object_3d::{group::Group, Object3D, Transform},
     texture::TextureId,
     Id, Resource,
   },
+  shadow::{directional_layer, spot_layer, ShadowMaps, ShadowSource},
+  skybox::Skybox,
+  stats::{GpuTimer, RenderStats},
 };
 
 pub(crate) struct Subject {
+  pub(crate) geometry_id: Option<Id>,
   pub(crate) diffuse_texture_id: Option<TextureId>,
   pub(crate) material_data: Option<(Buffer, BindGroup)>,
   pub(crate) transform: (Buffer, BindGroup),
@@ -53,24 +76,270 @@ pub(crate) struct Subject {
   pub(crate) vertices: (Buffer, usize),
   pub(crate) indices: (Buffer, usize),
   pub(crate) pipeline: Option<Arc<RenderPipeline>>,
+  pub(crate) pick_index: Option<PickIndex>,
+  pub(crate) alpha_mode: AlphaMode,
+  pub(crate) cast_shadow: bool,
+  /// Alpha test of the shadow pass, only set for `AlphaMode::Mask` materials.
+  pub(crate) shadow_mask: Option<(Buffer, BindGroup)>,
+  /// Bounds of the geometry in object space and in world space.
+  pub(crate) bounds: Option<(Aabb, Aabb)>,
+}
+
+/// Place of a mesh or group in the scene hierarchy.
+struct Node {
+  parent: Option<Id>,
+  children: Vec<Id>,
+  local: Mat4,
+  world: Mat4,
 }
 
 pub(crate) struct LightsBinding {
   pub(crate) directional_lights: Buffer,
   pub(crate) point_lights: Buffer,
   pub(crate) spot_lights: Buffer,
-  pub(crate) directional_light_count: Buffer,
-  pub(crate) point_light_count: Buffer,
-  pub(crate) spot_light_count: Buffer,
+  pub(crate) rect_area_lights: Buffer,
+  /// Number of directional, point, spot and rect area lights.
+  pub(crate) counts: Buffer,
+  pub(crate) ltc: LtcTables,
   pub(crate) bind_group: BindGroup,
 }
 
+impl LightsBinding {
+  /// Light buffer of `kind` and its label.
+  fn buffer(&mut self, kind: LightKind) -> (&mut Buffer, &'static str) {
+    match kind {
+      LightKind::Directional => (&mut self.directional_lights, "directional lights"),
+      LightKind::Point => (&mut self.point_lights, "point lights"),
+      LightKind::Spot => (&mut self.spot_lights, "spot lights"),
+      LightKind::RectArea => (&mut self.rect_area_lights, "rect area lights"),
+    }
+  }
+
+  /// Recreates the bind group after a light buffer or the shadow maps were reallocated.
+  fn rebind(&mut self, renderer: &Renderer, layout: &BindGroupLayout, shadow_maps: &ShadowMaps) {
+    self.bind_group = lights_bind_group(
+      renderer,
+      layout,
+      [
+        &self.directional_lights,
+        &self.point_lights,
+        &self.spot_lights,
+        &self.rect_area_lights,
+        &self.counts,
+      ],
+      shadow_maps,
+      &self.ltc,
+    );
+  }
+}
+
+/// Binds the light buffers in order followed by the shadow casters, maps and sampler and the
+/// LTC tables and sampler.
+fn lights_bind_group(
+  renderer: &Renderer,
+  layout: &BindGroupLayout,
+  buffers: [&Buffer; 5],
+  shadow_maps: &ShadowMaps,
+  ltc: &LtcTables,
+) -> BindGroup {
+  let entries = buffers
+    .iter()
+    .map(|buffer| buffer.as_entire_binding())
+    .chain([
+      shadow_maps.casters.as_entire_binding(),
+      BindingResource::TextureView(&shadow_maps.view),
+      BindingResource::Sampler(&shadow_maps.sampler),
+      BindingResource::TextureView(&ltc.view),
+      BindingResource::Sampler(&ltc.sampler),
+    ])
+    .enumerate()
+    .map(|(binding, resource)| BindGroupEntry {
+      binding: binding as u32,
+      resource,
+    })
+    .collect::<Vec<_>>();
+
+  renderer.device().create_bind_group(&BindGroupDescriptor {
+    label: Some("dynamic lights bind group"),
+    layout,
+    entries: &entries,
+  })
+}
+
 struct Depth {
   texture: Texture,
   view: TextureView,
   sampler: Sampler,
 }
 
+impl Depth {
+  fn new(renderer: &Renderer) -> Self {
+    let (width, height) = renderer.size();
+
+    // GL can't mix a multisampled depth texture with the multisampled color renderbuffer in one
+    // framebuffer, so the depth attachment is only bindable without MSAA
+    let usage = if *renderer.sample_count() > 1 {
+      TextureUsages::RENDER_ATTACHMENT
+    } else {
+      TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING
+    };
+
+    let texture = renderer.device().create_texture(&TextureDescriptor {
+      label: Some("depth texture"),
+      size: Extent3d {
+        width: width.max(1),
+        height: height.max(1),
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: *renderer.sample_count(),
+      dimension: TextureDimension::D2,
+      format: TextureFormat::Depth32Float,
+      usage,
+      view_formats: &[],
+    });
+
+    Depth {
+      view: texture.create_view(&TextureViewDescriptor::default()),
+      sampler: renderer.device().create_sampler(&SamplerDescriptor {
+        address_mode_u: AddressMode::ClampToEdge,
+        address_mode_v: AddressMode::ClampToEdge,
+        address_mode_w: AddressMode::ClampToEdge,
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        mipmap_filter: FilterMode::Nearest,
+        compare: Some(CompareFunction::LessEqual),
+        lod_min_clamp: 0.0,
+        lod_max_clamp: 100.0,
+        ..Default::default()
+      }),
+      texture,
+    }
+  }
+}
+
+/// Pipeline and bind groups last set on a render pass, state that is already bound is skipped so
+/// the stats only count actual switches.
+#[derive(Default)]
+struct BoundState<'a> {
+  pipeline: Option<&'a Arc<RenderPipeline>>,
+  bind_groups: [Option<&'a BindGroup>; 7],
+}
+
+impl<'a> BoundState<'a> {
+  fn set_pipeline(
+    &mut self,
+    render_pass: &mut RenderPass<'_>,
+    stats: &mut RenderStats,
+    pipeline: &'a Arc<RenderPipeline>,
+  ) {
+    if self
+      .pipeline
+      .is_some_and(|bound| Arc::ptr_eq(bound, pipeline))
+    {
+      return;
+    }
+
+    render_pass.set_pipeline(pipeline);
+    stats.record_pipeline_switch();
+
+    // a new pipeline may use a different layout, so every group is bound again
+    *self = BoundState {
+      pipeline: Some(pipeline),
+      ..Default::default()
+    };
+  }
+
+  fn set_bind_group(
+    &mut self,
+    render_pass: &mut RenderPass<'_>,
+    stats: &mut RenderStats,
+    index: usize,
+    bind_group: &'a BindGroup,
+  ) {
+    if self.bind_groups[index].is_some_and(|bound| std::ptr::eq(bound, bind_group)) {
+      return;
+    }
+
+    render_pass.set_bind_group(index as u32, bind_group, &[]);
+    stats.record_bind_group_switch();
+    self.bind_groups[index] = Some(bind_group);
+  }
+}
+
+/// Inverse-transpose of the upper 3x3, keeps normals perpendicular under non-uniform scaling.
+fn normal_matrix(world: Mat4) -> Mat4 {
+  let linear = Mat3::from_mat4(world);
+
+  if linear.determinant().abs() <= f32::EPSILON {
+    return Mat4::from_mat3(linear);
+  }
+
+  Mat4::from_mat3(linear.inverse().transpose())
+}
+
+/// Contents of the transform uniform, the world matrix is read by the vertex shaders and the flags
+/// by the lit fragment shaders.
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+struct TransformBuffer {
+  world: Mat4,
+  /// `x` is non zero when the subject receives shadows.
+  flags: UVec4,
+}
+
+fn transform_flags(object: &impl Object3D) -> UVec4 {
+  UVec4::new(object.receive_shadow() as u32, 0, 0, 0)
+}
+
+fn texture_size(texture: &Texture) -> u64 {
+  let size = texture.size();
+
+  size.width as u64
+    * size.height as u64
+    * size.depth_or_array_layers as u64
+    * texture.sample_count() as u64
+    * texture.format().block_copy_size(None).unwrap_or(4) as u64
+}
+
+fn create_multisample_view(renderer: &Renderer) -> Option<TextureView> {
+  let (width, height) = renderer.size();
+  let sample_count = *renderer.sample_count();
+
+  (sample_count > 1).then(|| {
+    renderer
+      .device()
+      .create_texture(&TextureDescriptor {
+        label: Some("multisample texture"),
+        size: Extent3d {
+          width: width.max(1),
+          height: height.max(1),
+          depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: TextureDimension::D2,
+        format: *renderer.hdr_format(),
+        usage: TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+      })
+      .create_view(&TextureViewDescriptor::default())
+  })
+}
+
+/// What was last set on the scene besides its subjects and camera, replayed by `Scene::rebuild`.
+#[derive(Default, Clone)]
+struct Bound {
+  ambient_light: Option<AmbientLight>,
+  hemisphere_light: Option<HemisphereLight>,
+  directional_lights: Vec<DirectionalLight>,
+  point_lights: Vec<PointLight>,
+  spot_lights: Vec<SpotLight>,
+  rect_area_lights: Vec<RectAreaLight>,
+  environment: Option<Environment>,
+  post_processing: Vec<(PostStage, Vec<PostPass>)>,
+}
+
 #[derive(Getters, Builder)]
 #[getset(get = "pub")]
 #[builder(pattern = "owned", build_fn(private, name = "fallible_build"))]
@@ -93,10 +362,26 @@ pub struct Scene {
   #[builder(setter(custom))]
   ambient_light_layout: BindGroupLayout,
 
+  #[getset(skip)]
+  #[builder(setter(custom))]
+  environment_lighting: EnvironmentLighting,
+
+  /// Scales the lighting of PBR materials by the environment.
+  #[builder(default = "1.0", setter(skip))]
+  environment_intensity: f32,
+
   #[getset(get = "pub(crate)")]
   #[builder(setter(custom))]
   lights: LightsBinding,
 
+  /// Whether the lights are bound as storage buffers or as fixed size uniform arrays.
+  #[builder(setter(custom))]
+  light_storage: LightStorage,
+
+  #[getset(skip)]
+  #[builder(default = "Default::default()", setter(skip))]
+  nodes: IndexMap<Id, Node>,
+
   #[getset(skip)]
   #[builder(default = "Default::default()", setter(skip))]
   material_pipelines: IndexMap<Id, Arc<RenderPipeline>>,
@@ -111,7 +396,7 @@ pub struct Scene {
 
   #[getset(skip)]
   #[builder(default = "Default::default()", setter(skip))]
-  texture_map_views: IndexMap<TextureId, (TextureView, BindGroup)>,
+  texture_map_views: IndexMap<TextureId, (Texture, TextureView, BindGroup)>,
 
   #[getset(skip)]
   #[builder(setter(custom))]
@@ -121,6 +406,25 @@ pub struct Scene {
   #[builder(setter(custom))]
   camera: (Buffer, BindGroup),
 
+  /// View-projection matrix of the last camera set on the scene.
+  #[builder(default = "Mat4::IDENTITY", setter(skip))]
+  camera_matrix: Mat4,
+
+  /// Contents of the camera uniform last written, written again by `rebuild`.
+  #[getset(skip)]
+  #[builder(default = "[0.0; 4 * 5]", setter(skip))]
+  stored_camera: [f32; 4 * 5],
+
+  /// Position of the last camera set on the scene, the fog thickens away from it.
+  #[getset(skip)]
+  #[builder(default = "Vec3::ZERO", setter(skip))]
+  camera_position: Vec3,
+
+  /// Uniform of `FOG_SHADER`, bound next to the camera.
+  #[getset(skip)]
+  #[builder(setter(custom))]
+  fog_buffer: Buffer,
+
   #[getset(skip)]
   #[builder(setter(custom))]
   camera_layout: BindGroupLayout,
@@ -129,9 +433,61 @@ pub struct Scene {
   #[builder(setter(custom))]
   dynamic_lights_layout: BindGroupLayout,
 
+  #[getset(skip)]
+  #[builder(setter(custom))]
+  shadow_maps: ShadowMaps,
+
   #[getset(skip)]
   #[builder(setter(custom))]
   depth: Depth,
+
+  /// Multisampled color target resolved into the frame, only present when MSAA is enabled.
+  #[getset(skip)]
+  #[builder(setter(custom))]
+  multisample: Option<TextureView>,
+
+  #[getset(skip)]
+  #[builder(setter(custom))]
+  gpu_timer: Option<GpuTimer>,
+
+  /// Only present once `enable_picking` was called.
+  #[getset(skip)]
+  #[builder(default = "None", setter(skip))]
+  picker: Option<Picker>,
+
+  #[getset(skip)]
+  #[builder(default = "None", setter(skip))]
+  environment: Option<Skybox>,
+
+  /// The scene is drawn into its first target, then tone mapped into the frame.
+  #[getset(skip)]
+  #[builder(setter(custom))]
+  post_processing: PostChain,
+
+  /// Curve bringing the HDR frame into the displayable range.
+  #[builder(default)]
+  tone_mapping: ToneMapping,
+
+  /// Scale applied to the HDR frame before tone mapping, `1.0` by default.
+  #[builder(default = "1.0")]
+  exposure: f32,
+
+  /// `None` by default. The background isn't fogged, set `color` to the fog color to hide the far
+  /// plane.
+  #[builder(default)]
+  fog: Option<Fog>,
+
+  /// Linear color the fog fades into.
+  #[builder(default = "Vec3::ONE")]
+  fog_color: Vec3,
+
+  /// How fast the fog thins out above `y = 0`, `0.0` keeps it as thick at every height.
+  #[builder(default)]
+  fog_height_falloff: f32,
+
+  #[getset(skip)]
+  #[builder(default, setter(skip))]
+  bound: Bound,
 }
 
 impl PartialEq for Scene {
@@ -187,16 +543,28 @@ impl SceneBuilder {
       .device()
       .create_bind_group_layout(&BindGroupLayoutDescriptor {
         label: Some("camera bind group layout"),
-        entries: &[BindGroupLayoutEntry {
-          binding: 0,
-          visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
-          ty: BindingType::Buffer {
-            ty: BufferBindingType::Uniform,
-            has_dynamic_offset: false,
-            min_binding_size: None,
+        entries: &[
+          BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+              ty: BufferBindingType::Uniform,
+              has_dynamic_offset: false,
+              min_binding_size: None,
+            },
+            count: None,
           },
-          count: None,
-        }],
+          BindGroupLayoutEntry {
+            binding: 1,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+              ty: BufferBindingType::Uniform,
+              has_dynamic_offset: false,
+              min_binding_size: None,
+            },
+            count: None,
+          },
+        ],
       });
 
     let camera_buffer_contents = [0.0f32; 4 * 5];
@@ -206,17 +574,18 @@ impl SceneBuilder {
       usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
     });
 
-    let camera_bind_group = renderer.device().create_bind_group(&BindGroupDescriptor {
-      label: Some("camera bind group"),
-      layout: &camera_layout,
-      entries: &[BindGroupEntry {
-        binding: 0,
-        resource: camera_buffer.as_entire_binding(),
-      }],
+    let fog_buffer = renderer.device().create_buffer_init(&BufferInitDescriptor {
+      label: Some("fog buffer"),
+      contents: bytemuck::cast_slice(&[FogBuffer::zeroed()]),
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
     });
 
+    let camera_bind_group =
+      camera_bind_group(renderer, &camera_layout, &camera_buffer, &fog_buffer);
+
     self.camera = Some((camera_buffer, camera_bind_group));
     self.camera_layout = Some(camera_layout);
+    self.fog_buffer = Some(fog_buffer);
 
     self
   }
@@ -227,40 +596,82 @@ impl SceneBuilder {
         .device()
         .create_bind_group_layout(&BindGroupLayoutDescriptor {
           label: Some("ambient light layout"),
-          entries: &[BindGroupLayoutEntry {
-            binding: 0,
-            visibility: ShaderStages::FRAGMENT,
-            ty: BindingType::Buffer {
-              ty: BufferBindingType::Uniform,
-              has_dynamic_offset: false,
-              min_binding_size: None,
+          entries: &[
+            BindGroupLayoutEntry {
+              binding: 0,
+              visibility: ShaderStages::FRAGMENT,
+              ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+              },
+              count: None,
             },
-            count: None,
-          }],
+            // the environment lighting, only declared by the PBR shaders
+            BindGroupLayoutEntry {
+              binding: 1,
+              visibility: ShaderStages::FRAGMENT,
+              ty: BindingType::Texture {
+                multisampled: false,
+                view_dimension: TextureViewDimension::Cube,
+                sample_type: TextureSampleType::Float { filterable: true },
+              },
+              count: None,
+            },
+            BindGroupLayoutEntry {
+              binding: 2,
+              visibility: ShaderStages::FRAGMENT,
+              ty: BindingType::Texture {
+                multisampled: false,
+                view_dimension: TextureViewDimension::Cube,
+                sample_type: TextureSampleType::Float { filterable: true },
+              },
+              count: None,
+            },
+            BindGroupLayoutEntry {
+              binding: 3,
+              visibility: ShaderStages::FRAGMENT,
+              ty: BindingType::Texture {
+                multisampled: false,
+                view_dimension: TextureViewDimension::D2,
+                sample_type: TextureSampleType::Float { filterable: true },
+              },
+              count: None,
+            },
+            BindGroupLayoutEntry {
+              binding: 4,
+              visibility: ShaderStages::FRAGMENT,
+              ty: BindingType::Sampler(SamplerBindingType::Filtering),
+              count: None,
+            },
+          ],
         });
 
     let ambient_light_buffer = renderer.device().create_buffer_init(&BufferInitDescriptor {
       label: Some("ambient light buffer"),
-      contents: bytemuck::cast_slice(&[Vec4::new(0.0, 0.0, 0.0, 1.0)]),
+      contents: bytemuck::cast_slice(&[AmbientLightBuffer::default()]),
       usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
     });
 
-    let ambient_light_bind_group = renderer.device().create_bind_group(&BindGroupDescriptor {
-      label: Some("ambient_light bind group"),
-      layout: &ambient_light_layout,
-      entries: &[BindGroupEntry {
-        binding: 0,
-        resource: ambient_light_buffer.as_entire_binding(),
-      }],
-    });
+    let environment_lighting = EnvironmentLighting::new(renderer);
+
+    let ambient_light_bind_group = ambient_light_bind_group(
+      renderer,
+      &ambient_light_layout,
+      &ambient_light_buffer,
+      &environment_lighting,
+    );
 
     self.ambient_light = Some((ambient_light_buffer, ambient_light_bind_group));
     self.ambient_light_layout = Some(ambient_light_layout);
+    self.environment_lighting = Some(environment_lighting);
 
     self
   }
 
   fn init_dynamic_lights(mut self, renderer: &Renderer) -> Self {
+    let light_storage = LightStorage::for_renderer(renderer);
+
     let dynamic_lights_layout =
       renderer
         .device()
@@ -271,7 +682,7 @@ impl SceneBuilder {
               binding: 0,
               visibility: ShaderStages::FRAGMENT,
               ty: BindingType::Buffer {
-                ty: BufferBindingType::Uniform,
+                ty: light_storage.binding_type(),
                 has_dynamic_offset: false,
                 min_binding_size: None,
               },
@@ -281,7 +692,7 @@ impl SceneBuilder {
               binding: 1,
               visibility: ShaderStages::FRAGMENT,
               ty: BindingType::Buffer {
-                ty: BufferBindingType::Uniform,
+                ty: light_storage.binding_type(),
                 has_dynamic_offset: false,
                 min_binding_size: None,
               },
@@ -291,7 +702,7 @@ impl SceneBuilder {
               binding: 2,
               visibility: ShaderStages::FRAGMENT,
               ty: BindingType::Buffer {
-                ty: BufferBindingType::Uniform,
+                ty: light_storage.binding_type(),
                 has_dynamic_offset: false,
                 min_binding_size: None,
               },
@@ -301,7 +712,7 @@ impl SceneBuilder {
               binding: 3,
               visibility: ShaderStages::FRAGMENT,
               ty: BindingType::Buffer {
-                ty: BufferBindingType::Uniform,
+                ty: light_storage.binding_type(),
                 has_dynamic_offset: false,
                 min_binding_size: None,
               },
@@ -327,127 +738,121 @@ impl SceneBuilder {
               },
               count: None,
             },
+            BindGroupLayoutEntry {
+              binding: 6,
+              visibility: ShaderStages::FRAGMENT,
+              ty: BindingType::Texture {
+                multisampled: false,
+                view_dimension: TextureViewDimension::D2Array,
+                sample_type: TextureSampleType::Depth,
+              },
+              count: None,
+            },
+            BindGroupLayoutEntry {
+              binding: 7,
+              visibility: ShaderStages::FRAGMENT,
+              ty: BindingType::Sampler(SamplerBindingType::Comparison),
+              count: None,
+            },
+            BindGroupLayoutEntry {
+              binding: 8,
+              visibility: ShaderStages::FRAGMENT,
+              ty: BindingType::Texture {
+                multisampled: false,
+                view_dimension: TextureViewDimension::D2Array,
+                sample_type: TextureSampleType::Float { filterable: true },
+              },
+              count: None,
+            },
+            BindGroupLayoutEntry {
+              binding: 9,
+              visibility: ShaderStages::FRAGMENT,
+              ty: BindingType::Sampler(SamplerBindingType::Filtering),
+              count: None,
+            },
           ],
         });
 
     {
-      let directional_light_buffer = [DirectionalLightBuffer::default(); 16];
-      let point_light_buffer = [PointLightBuffer::default(); 16];
-      let spot_light_buffer = [SpotLightBuffer::default(); 16];
-
-      let directional_lights = renderer.device().create_buffer_init(&BufferInitDescriptor {
-        label: Some("initial directional lights"),
-        contents: bytemuck::cast_slice(&directional_light_buffer),
-        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-      });
-
-      let point_lights = renderer.device().create_buffer_init(&BufferInitDescriptor {
-        label: Some("initial point lights"),
-        contents: bytemuck::cast_slice(&point_light_buffer),
-        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-      });
-
-      let spot_lights = renderer.device().create_buffer_init(&BufferInitDescriptor {
-        label: Some("initial spot lights"),
-        contents: bytemuck::cast_slice(&spot_light_buffer),
-        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-      });
-
-      let directional_light_count = renderer.device().create_buffer_init(&BufferInitDescriptor {
-        label: Some("initial directional light count"),
-        contents: bytemuck::cast_slice(&[UVec4::from((0, UVec3::default()))]),
-        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-      });
-
-      let point_light_count = renderer.device().create_buffer_init(&BufferInitDescriptor {
-        label: Some("initial point light count"),
-        contents: bytemuck::cast_slice(&[UVec4::from((0, UVec3::default()))]),
+      let directional_lights = light_storage.create_buffer::<DirectionalLightBuffer>(
+        renderer,
+        "directional lights",
+        MAX_UNIFORM_LIGHTS,
+      );
+      let point_lights = light_storage.create_buffer::<PointLightBuffer>(
+        renderer,
+        "point lights",
+        MAX_UNIFORM_LIGHTS,
+      );
+      let spot_lights =
+        light_storage.create_buffer::<SpotLightBuffer>(renderer, "spot lights", MAX_UNIFORM_LIGHTS);
+      let rect_area_lights = light_storage.create_buffer::<RectAreaLightBuffer>(
+        renderer,
+        "rect area lights",
+        MAX_UNIFORM_LIGHTS,
+      );
+
+      let counts = renderer.device().create_buffer_init(&BufferInitDescriptor {
+        label: Some("initial light counts"),
+        contents: bytemuck::cast_slice(&[UVec4::ZERO]),
         usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
       });
 
-      let spot_light_count = renderer.device().create_buffer_init(&BufferInitDescriptor {
-        label: Some("initial spot light count"),
-        contents: bytemuck::cast_slice(&[UVec4::from((0, UVec3::default()))]),
-        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-      });
+      let shadow_maps = ShadowMaps::new(renderer);
+      let ltc = LtcTables::new(renderer);
 
       self.lights = Some(LightsBinding {
-        bind_group: renderer.device().create_bind_group(&BindGroupDescriptor {
-          label: Some("initial directional light bind group"),
-          layout: &dynamic_lights_layout,
-          entries: &[
-            BindGroupEntry {
-              binding: 0,
-              resource: directional_lights.as_entire_binding(),
-            },
-            BindGroupEntry {
-              binding: 1,
-              resource: point_lights.as_entire_binding(),
-            },
-            BindGroupEntry {
-              binding: 2,
-              resource: spot_lights.as_entire_binding(),
-            },
-            BindGroupEntry {
-              binding: 3,
-              resource: directional_light_count.as_entire_binding(),
-            },
-            BindGroupEntry {
-              binding: 4,
-              resource: point_light_count.as_entire_binding(),
-            },
-            BindGroupEntry {
-              binding: 5,
-              resource: spot_light_count.as_entire_binding(),
-            },
+        bind_group: lights_bind_group(
+          renderer,
+          &dynamic_lights_layout,
+          [
+            &directional_lights,
+            &point_lights,
+            &spot_lights,
+            &rect_area_lights,
+            &counts,
           ],
-        }),
+          &shadow_maps,
+          &ltc,
+        ),
         directional_lights,
         point_lights,
         spot_lights,
-        directional_light_count,
-        point_light_count,
-        spot_light_count,
+        rect_area_lights,
+        counts,
+        ltc,
       });
+      self.shadow_maps = Some(shadow_maps);
     }
 
-    self.dynamic_lights_layout = Some(dynamic_lights_layout);
-    self
-  }
-
-  fn init_depth(mut self, renderer: &Renderer) -> Self {
-    let texture = renderer.device().create_texture(&TextureDescriptor {
-      label: Some("depth texture"),
-      size: Extent3d {
-        width: renderer.canvas().client_width() as u32,
-        height: renderer.canvas().client_height() as u32,
-        depth_or_array_layers: 1,
-      },
-      mip_level_count: 1,
-      sample_count: 1,
-      dimension: TextureDimension::D2,
-      format: TextureFormat::Depth32Float,
-      usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
-      view_formats: &[],
-    });
-
-    self.depth = Some(Depth {
-      view: texture.create_view(&TextureViewDescriptor::default()),
-      sampler: renderer.device().create_sampler(&SamplerDescriptor {
-        address_mode_u: AddressMode::ClampToEdge,
-        address_mode_v: AddressMode::ClampToEdge,
-        address_mode_w: AddressMode::ClampToEdge,
-        mag_filter: FilterMode::Linear,
-        min_filter: FilterMode::Linear,
-        mipmap_filter: FilterMode::Nearest,
-        compare: Some(CompareFunction::LessEqual),
-        lod_min_clamp: 0.0,
-        lod_max_clamp: 100.0,
-        ..Default::default()
-      }),
-      texture,
-    });
+    self.dynamic_lights_layout = Some(dynamic_lights_layout);
+    self.light_storage = Some(light_storage);
+    self
+  }
+
+  fn init_depth(mut self, renderer: &Renderer) -> Self {
+    self.depth = Some(Depth::new(renderer));
+    self
+  }
+
+  fn init_multisample(mut self, renderer: &Renderer) -> Self {
+    self.multisample = Some(create_multisample_view(renderer));
+    self
+  }
+
+  fn init_post_processing(mut self, renderer: &Renderer) -> Self {
+    self.post_processing = Some(PostChain::new(renderer));
+    self
+  }
 
+  fn init_gpu_timer(mut self, renderer: &Renderer) -> Self {
+    self.gpu_timer = Some(
+      renderer
+        .device()
+        .features()
+        .contains(Features::TIMESTAMP_QUERY)
+        .then(|| GpuTimer::new(renderer)),
+    );
     self
   }
 
@@ -459,25 +864,216 @@ impl SceneBuilder {
 #[repr(C)]
 #[derive(Pod, Zeroable, Default, Clone, Copy)]
 struct PointLightBuffer {
-  position: Vec4,
-  color: Vec4,
+  position: Vec3,
+  /// `0.0` for no cutoff.
+  range: f32,
+  color: Vec3,
+  intensity: f32,
+  decay: f32,
+  _padding: [f32; 3],
+}
+
+impl PointLightBuffer {
+  fn new(light: &PointLight) -> Self {
+    Self::falloff(
+      *light.position(),
+      *light.color(),
+      *light.intensity(),
+      *light.range(),
+      *light.decay(),
+    )
+  }
+
+  fn falloff(position: Vec3, color: Vec3, intensity: f32, range: f32, decay: f32) -> Self {
+    Self {
+      position,
+      range: range.max(0.0),
+      color,
+      intensity,
+      decay,
+      _padding: [0.0; 3],
+    }
+  }
 }
 
 #[repr(C)]
 #[derive(Pod, Zeroable, Default, Clone, Copy)]
 struct DirectionalLightBuffer {
-  color: Vec4,
-  direction: Vec4,
+  color: Vec3,
+  intensity: f32,
+  direction: Vec3,
+  /// Layer of the shadow maps, `-1` without shadows.
+  shadow_index: i32,
 }
 
 #[repr(C)]
 #[derive(Pod, Zeroable, Default, Clone, Copy)]
 struct SpotLightBuffer {
-  position: Vec4,
+  point_light: PointLightBuffer,
+  direction: Vec3,
+  /// Cosine of the outer cone angle.
+  outer_cone_cos: f32,
+  /// Cosine of the inner cone angle.
+  inner_cone_cos: f32,
+  /// Layer of the shadow maps, `-1` without shadows.
+  shadow_index: i32,
+  _padding: [i32; 2],
+}
+
+impl DirectionalLightBuffer {
+  fn new(light: &DirectionalLight, shadow_index: i32) -> Self {
+    Self {
+      color: *light.color(),
+      intensity: *light.intensity(),
+      direction: *light.direction(),
+      shadow_index,
+    }
+  }
+}
+
+impl SpotLightBuffer {
+  fn new(light: &SpotLight, shadow_index: i32) -> Self {
+    let outer_cone_angle = light
+      .outer_cone_angle()
+      .clamp(0.001, std::f32::consts::FRAC_PI_2);
+    // an inner cone as wide as the outer one would divide by zero in the smoothstep
+    let inner_cone_angle = light
+      .inner_cone_angle()
+      .clamp(0.0, outer_cone_angle * 0.999);
+
+    Self {
+      point_light: PointLightBuffer::falloff(
+        *light.position(),
+        *light.color(),
+        *light.intensity(),
+        *light.range(),
+        *light.decay(),
+      ),
+      direction: light.direction().normalize_or_zero(),
+      outer_cone_cos: outer_cone_angle.cos(),
+      inner_cone_cos: inner_cone_angle.cos(),
+      shadow_index,
+      _padding: [0; 2],
+    }
+  }
+}
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+struct RectAreaLightBuffer {
+  position: Vec3,
+  _padding: f32,
+  /// Half of the width along the rotated X axis.
+  half_width: Vec3,
+  _padding_2: f32,
+  /// Half of the height along the rotated Y axis.
+  half_height: Vec3,
+  _padding_3: f32,
+  color: Vec3,
+  intensity: f32,
+}
+
+impl Default for RectAreaLightBuffer {
+  fn default() -> Self {
+    Self::zeroed()
+  }
+}
+
+impl RectAreaLightBuffer {
+  fn new(light: &RectAreaLight) -> Self {
+    Self {
+      position: *light.position(),
+      _padding: 0.0,
+      half_width: *light.rotation() * Vec3::X * light.width().max(0.0) * 0.5,
+      _padding_2: 0.0,
+      half_height: *light.rotation() * Vec3::Y * light.height().max(0.0) * 0.5,
+      _padding_3: 0.0,
+      color: *light.color(),
+      intensity: *light.intensity(),
+    }
+  }
+}
+
+/// Ambient light followed by the hemisphere light and the intensity of the environment lighting
+/// with the last level of its prefiltered map.
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+struct AmbientLightBuffer {
   color: Vec4,
-  direction: Vec4,
-  // actually an f32, but to satisfy bytemuck we need an extra 12 bytes of padding so we use a Vec4
-  cutoff_angle: Vec4,
+  sky_color: Vec4,
+  ground_color: Vec4,
+  up: Vec4,
+  environment: Vec4,
+}
+
+impl Default for AmbientLightBuffer {
+  fn default() -> Self {
+    Self {
+      color: Vec4::new(0.0, 0.0, 0.0, 1.0),
+      sky_color: Vec4::ZERO,
+      ground_color: Vec4::ZERO,
+      up: Vec4::Y,
+      environment: Vec4::X,
+    }
+  }
+}
+
+/// Binds the camera buffer followed by the fog one.
+fn camera_bind_group(
+  renderer: &Renderer,
+  layout: &BindGroupLayout,
+  camera_buffer: &Buffer,
+  fog_buffer: &Buffer,
+) -> BindGroup {
+  renderer.device().create_bind_group(&BindGroupDescriptor {
+    label: Some("camera bind group"),
+    layout,
+    entries: &[
+      BindGroupEntry {
+        binding: 0,
+        resource: camera_buffer.as_entire_binding(),
+      },
+      BindGroupEntry {
+        binding: 1,
+        resource: fog_buffer.as_entire_binding(),
+      },
+    ],
+  })
+}
+
+/// Binds the ambient light buffer followed by the environment lighting maps and sampler.
+fn ambient_light_bind_group(
+  renderer: &Renderer,
+  layout: &BindGroupLayout,
+  buffer: &Buffer,
+  environment_lighting: &EnvironmentLighting,
+) -> BindGroup {
+  renderer.device().create_bind_group(&BindGroupDescriptor {
+    label: Some("ambient_light bind group"),
+    layout,
+    entries: &[
+      BindGroupEntry {
+        binding: 0,
+        resource: buffer.as_entire_binding(),
+      },
+      BindGroupEntry {
+        binding: 1,
+        resource: BindingResource::TextureView(&environment_lighting.irradiance_view),
+      },
+      BindGroupEntry {
+        binding: 2,
+        resource: BindingResource::TextureView(&environment_lighting.prefiltered_view),
+      },
+      BindGroupEntry {
+        binding: 3,
+        resource: BindingResource::TextureView(&environment_lighting.brdf_lut_view),
+      },
+      BindGroupEntry {
+        binding: 4,
+        resource: BindingResource::Sampler(&environment_lighting.sampler),
+      },
+    ],
+  })
 }
 
 impl Scene {
@@ -485,6 +1081,9 @@ impl Scene {
     SceneBuilder::default()
       .init_camera(&renderer)
       .init_depth(&renderer)
+      .init_multisample(&renderer)
+      .init_post_processing(renderer)
+      .init_gpu_timer(&renderer)
       .init_ambient_light(&renderer)
       .init_dynamic_lights(&renderer)
       .init_textures(&renderer)
@@ -498,10 +1097,32 @@ impl Scene {
     self.color = color;
   }
 
+  pub fn set_tone_mapping(&mut self, tone_mapping: ToneMapping) {
+    self.tone_mapping = tone_mapping;
+  }
+
+  pub fn set_exposure(&mut self, exposure: f32) {
+    self.exposure = exposure;
+  }
+
+  pub fn set_fog(&mut self, fog: Option<Fog>) {
+    self.fog = fog;
+  }
+
+  pub fn set_fog_color(&mut self, fog_color: Vec3) {
+    self.fog_color = fog_color;
+  }
+
+  pub fn set_fog_height_falloff(&mut self, fog_height_falloff: f32) {
+    self.fog_height_falloff = fog_height_falloff;
+  }
+
   pub fn has_subject(&self, object: &(impl Resource + Object3D)) -> bool {
     self.subjects.contains_key(&object.id())
   }
 
+  /// Fails without inserting `object` when its fragment shader declares the light arrays in a
+  /// form that can't be bound.
   pub fn insert<Object: Resource + Object3D>(
     &mut self,
     renderer: &Renderer,
@@ -509,7 +1130,7 @@ impl Scene {
     material_loader: &MaterialLoader,
     texture_loader: &TextureLoader,
     object: &Object,
-  ) {
+  ) -> Result<(), LightArrayError> {
     let geometry = object
       .geometry_id()
       .and_then(|id| geometry_loader.get_from_id(id));
@@ -518,6 +1139,10 @@ impl Scene {
       .material_id()
       .and_then(|id| material_loader.get_from_id(id));
 
+    let fragment_shader = material
+      .map(|material| self.light_storage.shader(material.fragment_shader()))
+      .transpose()?;
+
     let vertices = (
       renderer.device().create_buffer_init(&BufferInitDescriptor {
         label: Some("vertex buffer"),
@@ -548,24 +1173,33 @@ impl Scene {
         .unwrap_or_default(),
     );
 
+    let world = self.insert_node(object.id(), object.matrix());
+    let bounds = geometry.map(|geometry| (*geometry.aabb(), geometry.aabb().transform(world)));
+
     let transform = renderer.device().create_buffer_init(&BufferInitDescriptor {
       label: Some("transform buffer"),
-      contents: bytemuck::cast_slice(&[
-        Mat4::from_translation(object.pos().clone()) * Mat4::from_quat(object.rot().clone())
-      ]),
+      contents: bytemuck::cast_slice(&[TransformBuffer {
+        world,
+        flags: transform_flags(object),
+      }]),
       usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
     });
 
     let normal = renderer.device().create_buffer_init(&BufferInitDescriptor {
       label: Some("normal buffer"),
-      contents: bytemuck::cast_slice(&[Mat4::from_quat(object.rot().clone())]),
+      contents: bytemuck::cast_slice(&[normal_matrix(world)]),
       usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
     });
 
     if let Some(id) = material.and_then(|material| material.diffuse_map_texture_id().clone()) {
-      if let Some((content, dimensions)) = texture_loader
-        .get_from_id(id)
-        .map(|texture| (texture.content.as_slice(), texture.dimensions))
+      if let Some((content, dimensions, texel_format)) =
+        texture_loader.get_from_id(id).map(|texture| {
+          (
+            texture.content.as_slice(),
+            texture.dimensions,
+            texture.format,
+          )
+        })
       {
         let size = Extent3d {
           width: dimensions.0,
@@ -578,7 +1212,7 @@ impl Scene {
           mip_level_count: 1,
           sample_count: 1,
           dimension: TextureDimension::D2,
-          format: TextureFormat::Rgba8UnormSrgb,
+          format: texel_format.wgpu_format(),
           usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
           label: Some(&format!(r#"TextureId({:?})"#, id)),
           view_formats: &[],
@@ -594,7 +1228,7 @@ impl Scene {
           content,
           ImageDataLayout {
             offset: 0,
-            bytes_per_row: Some(4 * dimensions.0),
+            bytes_per_row: Some(texel_format.bytes_per_texel() * dimensions.0),
             rows_per_image: Some(dimensions.1),
           },
           size,
@@ -619,7 +1253,7 @@ impl Scene {
 
         self
           .texture_map_views
-          .insert(id, (texture_view, texture_bind_group));
+          .insert(id, (texture, texture_view, texture_bind_group));
       }
     }
 
@@ -629,7 +1263,7 @@ impl Scene {
         label: Some("transform layout"),
         entries: &[BindGroupLayoutEntry {
           binding: 0,
-          visibility: ShaderStages::VERTEX,
+          visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
           ty: BindingType::Buffer {
             ty: BufferBindingType::Uniform,
             has_dynamic_offset: false,
@@ -673,10 +1307,9 @@ impl Scene {
       }],
     });
 
-    let (pipeline, material_data) = object
-      .material_id()
-      .and_then(|id| material_loader.get_from_id(id))
-      .map(|material| {
+    let (pipeline, material_data) = material
+      .zip(fragment_shader)
+      .map(|(material, fragment_shader)| {
         let fragment_data_layout =
           self
             .material_layouts
@@ -693,13 +1326,20 @@ impl Scene {
           usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
         });
 
+        let fragment_data_entries = [BindGroupEntry {
+          binding: 0,
+          resource: fragment_data_buffer.as_entire_binding(),
+        }];
+
+        // a `ShaderMaterial` declares no fragment data
         let fragment_data_bind_group = renderer.device().create_bind_group(&BindGroupDescriptor {
           label: Some("fragment data bind group"),
           layout: fragment_data_layout,
-          entries: &[BindGroupEntry {
-            binding: 0,
-            resource: fragment_data_buffer.as_entire_binding(),
-          }],
+          entries: if material.fragment_data_layout().entries.is_empty() {
+            &[]
+          } else {
+            &fragment_data_entries
+          },
         });
 
         (
@@ -730,25 +1370,16 @@ impl Scene {
                     push_constant_ranges: &[],
                   });
 
-              let target = [renderer.supported_format().map(|format| ColorTargetState {
-                format,
-                blend: Some(BlendState {
-                  color: BlendComponent {
-                    src_factor: BlendFactor::SrcAlpha,
-                    dst_factor: BlendFactor::OneMinusSrcAlpha,
-                    operation: BlendOperation::Add,
-                  },
-                  alpha: BlendComponent::OVER,
-                }),
+              let target = [Some(ColorTargetState {
+                format: *renderer.hdr_format(),
+                blend: material.alpha_mode().blend_state(),
                 write_mask: ColorWrites::ALL,
               })];
 
               let vertex_shader = renderer
                 .device()
                 .create_shader_module(material.vertex_shader().clone());
-              let fragment_shader = renderer
-                .device()
-                .create_shader_module(material.fragment_shader().clone());
+              let fragment_shader = renderer.device().create_shader_module(fragment_shader);
 
               let pipeline = Arc::new(renderer.device().create_render_pipeline(
                 &RenderPipelineDescriptor {
@@ -764,20 +1395,20 @@ impl Scene {
                     topology: PrimitiveTopology::TriangleList,
                     strip_index_format: None,
                     front_face: FrontFace::Ccw,
-                    cull_mode: Some(Face::Back),
+                    cull_mode: material.cull_mode(),
                     polygon_mode: PolygonMode::Fill,
                     unclipped_depth: false,
                     conservative: false,
                   },
                   depth_stencil: Some(DepthStencilState {
                     format: TextureFormat::Depth32Float,
-                    depth_write_enabled: true,
+                    depth_write_enabled: !material.alpha_mode().is_transparent(),
                     depth_compare: CompareFunction::Less,
                     stencil: StencilState::default(),
                     bias: DepthBiasState::default(),
                   }),
                   multisample: MultisampleState {
-                    count: 1,
+                    count: *renderer.sample_count(),
                     mask: !0,
                     alpha_to_coverage_enabled: false,
                   },
@@ -792,53 +1423,517 @@ impl Scene {
                 },
               ));
 
-              self
-                .material_pipelines
-                .insert(*material.id(), pipeline.clone());
+              self
+                .material_pipelines
+                .insert(*material.id(), pipeline.clone());
+
+              pipeline
+            }),
+          (fragment_data_buffer, fragment_data_bind_group),
+        )
+      })
+      .map(|(material_data, pipeline)| (Some(material_data), Some(pipeline)))
+      .unwrap_or((None, None));
+
+    self.subjects.insert(
+      object.id(),
+      Subject {
+        geometry_id: object.geometry_id(),
+        diffuse_texture_id: material.and_then(|material| material.diffuse_map_texture_id().clone()),
+        alpha_mode: material
+          .map(|material| *material.alpha_mode())
+          .unwrap_or_default(),
+        cast_shadow: object.cast_shadow(),
+        shadow_mask: material.and_then(|material| self.shadow_maps.create_mask(renderer, material)),
+        material_data,
+        normal: (normal, normal_bind_group),
+        transform: (transform, transform_bind_group),
+        vertices,
+        indices,
+        pipeline,
+        pick_index: self
+          .picker
+          .as_mut()
+          .map(|picker| picker.create_index(renderer)),
+        bounds,
+      },
+    );
+
+    Ok(())
+  }
+
+  /// Removes a mesh or group, its children are detached and become root nodes.
+  pub fn remove(&mut self, renderer: &Renderer, object: &impl Resource) {
+    let id = object.id();
+
+    self.subjects.shift_remove(&id);
+
+    let Some(node) = self.nodes.shift_remove(&id) else {
+      return;
+    };
+
+    if let Some(parent) = node.parent.and_then(|parent| self.nodes.get_mut(&parent)) {
+      parent.children.retain(|child| *child != id);
+    }
+
+    for child in node.children {
+      if let Some(child_node) = self.nodes.get_mut(&child) {
+        child_node.parent = None;
+      }
+
+      self.propagate(renderer, child);
+    }
+  }
+
+  pub fn insert_group(&mut self, group: &Group) {
+    self.insert_node(group.id(), group.matrix());
+  }
+
+  /// Parents `child` to `parent` so its transform becomes relative to it, `None` makes `child` a
+  /// root node again. Ignored when it would create a cycle or either node isn't in the scene.
+  pub fn set_parent(&mut self, renderer: &Renderer, child: Id, parent: Option<Id>) {
+    let Some(node) = self.nodes.get(&child) else {
+      return;
+    };
+
+    if node.parent == parent {
+      return;
+    }
+
+    if let Some(parent) = parent {
+      let mut ancestor = Some(parent);
+
+      while let Some(id) = ancestor {
+        if id == child {
+          return;
+        }
+
+        ancestor = self.nodes.get(&id).and_then(|node| node.parent);
+      }
+
+      let Some(parent_node) = self.nodes.get_mut(&parent) else {
+        return;
+      };
+
+      parent_node.children.push(child);
+    }
+
+    let previous = self.nodes.get_mut(&child).and_then(|node| {
+      let previous = node.parent;
+      node.parent = parent;
+      previous
+    });
+
+    if let Some(previous) = previous.and_then(|previous| self.nodes.get_mut(&previous)) {
+      previous.children.retain(|id| *id != child);
+    }
+
+    self.propagate(renderer, child);
+  }
+
+  pub fn parent(&self, id: Id) -> Option<Id> {
+    self.nodes.get(&id).and_then(|node| node.parent)
+  }
+
+  /// World space bounds enclosing every subject with a geometry, `None` for an empty scene.
+  pub fn bounds(&self) -> Option<Aabb> {
+    self
+      .subjects
+      .values()
+      .filter_map(|subject| subject.bounds.map(|(_, world)| world))
+      .reduce(|a, b| a.union(&b))
+  }
+
+  /// Transform of a node composed with the transforms of all its ancestors.
+  pub fn world_matrix(&self, id: Id) -> Option<Mat4> {
+    self.nodes.get(&id).map(|node| node.world)
+  }
+
+  /// Casts a ray against the triangles of every subject, hits are sorted from nearest to farthest.
+  /// The geometries are read from `geometry_loader`, the same one subjects were inserted with.
+  pub fn raycast(
+    &self,
+    geometry_loader: &GeometryLoader,
+    origin: Vec3,
+    direction: Vec3,
+  ) -> Vec<RaycastHit> {
+    let ray = Ray::new(origin, direction);
+
+    let mut hits = self
+      .subjects
+      .iter()
+      .filter_map(|(id, subject)| {
+        let geometry = subject
+          .geometry_id
+          .and_then(|geometry_id| geometry_loader.get_from_id(geometry_id))?;
+        let world = self.world_matrix(*id).unwrap_or_default();
+
+        Some(ray.intersect_geometry(*id, geometry, world))
+      })
+      .flatten()
+      .collect::<Vec<_>>();
+
+    hits.sort_by(|a, b| a.distance().total_cmp(b.distance()));
+
+    hits
+  }
+
+  /// Writes the current transform of `object` and recomputes the world matrices of its subtree.
+  pub fn update_transform(&mut self, renderer: &Renderer, object: &(impl Resource + Transform)) {
+    let Some(node) = self.nodes.get_mut(&object.id()) else {
+      return;
+    };
+
+    node.local = object.matrix();
+    self.propagate(renderer, object.id());
+  }
+
+  /// Registers a node or updates the local transform of an existing one, keeping its parent.
+  fn insert_node(&mut self, id: Id, local: Mat4) -> Mat4 {
+    let parent_world = self
+      .parent(id)
+      .and_then(|parent| self.world_matrix(parent))
+      .unwrap_or(Mat4::IDENTITY);
+
+    let node = self.nodes.entry(id).or_insert_with(|| Node {
+      parent: None,
+      children: vec![],
+      local,
+      world: local,
+    });
+
+    node.local = local;
+    node.world = parent_world * local;
+    node.world
+  }
+
+  fn propagate(&mut self, renderer: &Renderer, id: Id) {
+    let parent_world = self
+      .parent(id)
+      .and_then(|parent| self.world_matrix(parent))
+      .unwrap_or(Mat4::IDENTITY);
+
+    let Some(node) = self.nodes.get_mut(&id) else {
+      return;
+    };
+
+    node.world = parent_world * node.local;
+    let (world, children) = (node.world, node.children.clone());
+
+    if let Some(Subject {
+      transform: (transform, _),
+      normal: (normal, _),
+      bounds,
+      ..
+    }) = self.subjects.get_mut(&id)
+    {
+      if let Some((local, world_bounds)) = bounds {
+        *world_bounds = local.transform(world);
+      }
+
+      renderer
+        .queue()
+        .write_buffer(transform, 0, bytemuck::cast_slice(&[world]));
+      renderer
+        .queue()
+        .write_buffer(normal, 0, bytemuck::cast_slice(&[normal_matrix(world)]));
+    }
+
+    for child in children {
+      self.propagate(renderer, child);
+    }
+  }
+
+  /// Recreates the GPU state of the scene on the device of `renderer`, after the previous device
+  /// was lost. `objects` are inserted again from the loaders and keep their place in the
+  /// hierarchy, the camera, lights, environment and post processing last set are restored.
+  pub fn rebuild<'a, Object: Resource + Object3D + 'a>(
+    &mut self,
+    renderer: &Renderer,
+    geometry_loader: &GeometryLoader,
+    material_loader: &MaterialLoader,
+    texture_loader: &TextureLoader,
+    objects: impl IntoIterator<Item = &'a Object>,
+  ) {
+    let id = self.id;
+    let nodes = std::mem::take(&mut self.nodes);
+    let picking = self.picker.is_some();
+    let environment_intensity = self.environment_intensity;
+    let bound = std::mem::take(&mut self.bound);
+    let (stored_camera, camera_matrix, camera_position) =
+      (self.stored_camera, self.camera_matrix, self.camera_position);
+
+    *self = Scene::builder(renderer)
+      .color(self.color)
+      .tone_mapping(self.tone_mapping)
+      .exposure(self.exposure)
+      .fog(self.fog)
+      .fog_color(self.fog_color)
+      .fog_height_falloff(self.fog_height_falloff)
+      .build();
+    self.id = id;
+    self.nodes = nodes;
+    self.stored_camera = stored_camera;
+    self.camera_matrix = camera_matrix;
+    self.camera_position = camera_position;
+    renderer
+      .queue()
+      .write_buffer(&self.camera.0, 0, bytemuck::cast_slice(&stored_camera));
+    self.set_environment_intensity(renderer, environment_intensity);
+    self.replay(renderer, texture_loader, &bound);
+
+    if picking {
+      self.enable_picking(renderer);
+    }
+
+    for object in objects {
+      let _ = self.insert(
+        renderer,
+        geometry_loader,
+        material_loader,
+        texture_loader,
+        object,
+      );
+    }
+  }
+
+  /// Sets everything in `bound` again, errors were already reported when it was first set.
+  fn replay(&mut self, renderer: &Renderer, texture_loader: &TextureLoader, bound: &Bound) {
+    if let Some(ambient_light) = &bound.ambient_light {
+      self.update_ambient_light(renderer, ambient_light);
+    }
+
+    if let Some(hemisphere_light) = &bound.hemisphere_light {
+      self.update_hemisphere_light(renderer, hemisphere_light);
+    }
+
+    let _ = self.bind_directional_lights(renderer, &bound.directional_lights);
+    let _ = self.bind_point_lights(renderer, &bound.point_lights);
+    let _ = self.bind_spot_lights(renderer, &bound.spot_lights);
+    let _ = self.bind_rect_area_lights(renderer, &bound.rect_area_lights);
+
+    if bound.environment.is_some() {
+      let _ = self.set_environment(renderer, texture_loader, bound.environment.as_ref());
+    }
+
+    let _ = self
+      .post_processing
+      .set_effects(renderer, texture_loader, &bound.post_processing);
+  }
+
+  /// Recreates the depth, multisample and post processing targets when the size of the `Renderer`
+  /// has changed. Rendering does it too, calling it right after `Renderer::resize` only avoids the
+  /// work on the next frame.
+  pub fn resize(&mut self, renderer: &Renderer) {
+    let (width, height) = renderer.size();
+
+    if width == 0 || height == 0 || self.target_size() == (width, height) {
+      return;
+    }
+
+    self.depth = Depth::new(renderer);
+    self.multisample = create_multisample_view(renderer);
+
+    self.post_processing.resize(renderer);
+  }
+
+  fn target_size(&self) -> (u32, u32) {
+    (self.depth.texture.width(), self.depth.texture.height())
+  }
+
+  pub fn render(&mut self, renderer: &Renderer) -> RenderStats {
+    let Some(frame) = renderer.current_frame() else {
+      return RenderStats::default();
+    };
+
+    self.resize(renderer);
+
+    let timer = self.gpu_timer.as_ref().filter(|timer| {
+      // drives the readback of previous measurements on native targets
+      renderer.device().poll(Maintain::Poll);
+      timer.begin_frame()
+    });
+
+    let mut encoder = renderer
+      .device()
+      .create_command_encoder(&Default::default());
+
+    let stats = self.draw(renderer, &mut encoder, frame.texture(), timer);
+
+    renderer.queue().submit([encoder.finish()]);
+    frame.present();
+
+    if let Some(timer) = timer {
+      timer.map();
+    }
+
+    stats
+  }
+
+  /// Renders the scene and reads the resulting frame back as RGBA8 pixels.
+  pub async fn render_to_image(
+    &mut self,
+    renderer: &Renderer,
+  ) -> Result<FrameCapture, CaptureError> {
+    let frame = renderer.current_frame().ok_or(CaptureError::NoFrame)?;
+
+    self.resize(renderer);
+
+    let mut encoder = renderer
+      .device()
+      .create_command_encoder(&Default::default());
+
+    self.draw(renderer, &mut encoder, frame.texture(), None);
+
+    let readback = Readback::encode(renderer, &mut encoder, frame.texture());
+
+    renderer.queue().submit([encoder.finish()]);
+    frame.present();
+
+    readback?.read(renderer).await
+  }
+
+  /// Creates the resources of the picking pass used by `pick`, subjects inserted before and after
+  /// are all pickable.
+  pub fn enable_picking(&mut self, renderer: &Renderer) {
+    let picker = self.picker.get_or_insert_with(|| Picker::new(renderer));
+
+    for subject in self.subjects.values_mut() {
+      if subject.pick_index.is_none() {
+        subject.pick_index = Some(picker.create_index(renderer));
+      }
+    }
+  }
+
+  /// Reads back the object drawn at pixel `(x, y)` of the render target, counted from the top
+  /// left corner, by rendering object indices into an `R32Uint` target. Unlike `raycast`, the
+  /// cost doesn't depend on the number of triangles on the CPU.
+  pub async fn pick(
+    &self,
+    renderer: &Renderer,
+    x: u32,
+    y: u32,
+  ) -> Result<Option<PickResult>, PickError> {
+    let picker = self.picker.as_ref().ok_or(PickError::NotEnabled)?;
+
+    let size = self.target_size();
+    if x >= size.0 || y >= size.1 {
+      return Ok(None);
+    }
+
+    picker.aim(renderer, self.camera_matrix, size, x, y);
 
-              pipeline
-            }),
-          (fragment_data_buffer, fragment_data_bind_group),
-        )
-      })
-      .map(|(material_data, pipeline)| (Some(material_data), Some(pipeline)))
-      .unwrap_or((None, None));
+    let mut encoder = renderer
+      .device()
+      .create_command_encoder(&Default::default());
 
-    self.subjects.insert(
-      object.id(),
-      Subject {
-        diffuse_texture_id: material.and_then(|material| material.diffuse_map_texture_id().clone()),
-        material_data,
-        normal: (normal, normal_bind_group),
-        transform: (transform, transform_bind_group),
-        vertices,
-        indices,
+    {
+      let mut render_pass = picker.begin_pass(&mut encoder);
+
+      for Subject {
+        transform: (_, transform_bind_group),
+        vertices: (vertices, _),
+        indices: (indices, index_count),
+        pick_index,
         pipeline,
-      },
-    );
-  }
+        ..
+      } in self.subjects.values()
+      {
+        let (Some(pick_index), Some(_)) = (pick_index, pipeline) else {
+          continue;
+        };
 
-  pub fn remove(&mut self, object: &impl Resource) {
-    self.subjects.shift_remove(&object.id());
-  }
+        if *index_count == 0 {
+          continue;
+        }
 
-  pub fn render(&self, renderer: &Renderer) {
-    let Ok(output) = renderer.surface().get_current_texture() else {
-      return;
+        render_pass.set_bind_group(1, transform_bind_group, &[]);
+        render_pass.set_bind_group(2, &pick_index.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, vertices.slice(..));
+        render_pass.set_index_buffer(indices.slice(..), IndexFormat::Uint32);
+        render_pass.draw_indexed(0..*index_count as u32, 0, 0..1);
+      }
+    }
+
+    let readback = picker.encode_readback(renderer, &mut encoder);
+    renderer.queue().submit([encoder.finish()]);
+
+    let (index, depth) = readback.read(renderer).await?;
+
+    let Some(id) = self.subjects.iter().find_map(|(id, subject)| {
+      subject
+        .pick_index
+        .as_ref()
+        .is_some_and(|pick_index| pick_index.index == index)
+        .then_some(*id)
+    }) else {
+      return Ok(None);
     };
 
+    let position = self
+      .camera_matrix
+      .inverse()
+      .project_point3(pixel_ndc(size, x, y).extend(depth));
+
+    Ok(Some(PickResult::new(id, depth, position)))
+  }
+
+  fn draw(
+    &self,
+    renderer: &Renderer,
+    encoder: &mut CommandEncoder,
+    target: &Texture,
+    timer: Option<&GpuTimer>,
+  ) -> RenderStats {
+    let mut stats = RenderStats::default();
+
+    // a frame without pixels leaves the targets at their previous size
+    if self.target_size() != (target.width(), target.height()) {
+      return stats;
+    }
+
     let (_, camera_bind_group) = &self.camera;
 
-    let view = output.texture.create_view(&Default::default());
+    self.shadow_maps.encode(
+      renderer,
+      encoder,
+      self.bounds(),
+      self.subjects.values(),
+      &self.texture_map_views,
+      &mut stats,
+    );
 
-    let mut encoder = renderer
-      .device()
-      .create_command_encoder(&Default::default());
+    if let Some(environment) = &self.environment {
+      environment.prepare(renderer, self.camera_matrix);
+    }
+
+    self
+      .post_processing
+      .prepare(renderer, self.tone_mapping, self.exposure);
+
+    renderer.queue().write_buffer(
+      &self.fog_buffer,
+      0,
+      bytemuck::cast_slice(&[FogBuffer::new(
+        self.fog,
+        self.fog_color,
+        self.fog_height_falloff,
+        self.camera_position,
+      )]),
+    );
+
+    let view = self.post_processing.input();
+
+    // with MSAA the scene is drawn into the multisampled target and resolved into the HDR one
+    let (view, resolve_target) = match &self.multisample {
+      Some(multisample) => (multisample, Some(view)),
+      None => (view, None),
+    };
 
     let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
       color_attachments: &[Some(RenderPassColorAttachment {
-        view: &view,
-        resolve_target: None,
+        view,
+        resolve_target,
         ops: Operations {
           load: LoadOp::Clear(Color {
             r: self.color.x as f64,
@@ -857,9 +1952,16 @@ impl Scene {
         }),
         stencil_ops: None,
       }),
+      timestamp_writes: timer.map(GpuTimer::timestamp_writes),
       ..Default::default()
     });
 
+    if let Some(environment) = &self.environment {
+      environment.draw(&mut render_pass, &mut stats);
+    }
+
+    let mut bound = BoundState::default();
+
     for Subject {
       diffuse_texture_id,
       material_data,
@@ -868,45 +1970,160 @@ impl Scene {
       vertices: (vertices, _),
       indices: (indices, index_count),
       pipeline,
-    } in self.subjects.values()
+      ..
+    } in self.draw_order(&mut stats)
     {
       let Some(pipeline) = pipeline else {
         continue;
       };
 
-      render_pass.set_pipeline(pipeline);
+      bound.set_pipeline(&mut render_pass, &mut stats, pipeline);
 
-      render_pass.set_bind_group(0, camera_bind_group, &[]);
-      render_pass.set_bind_group(1, transform_bind_group, &[]);
+      bound.set_bind_group(&mut render_pass, &mut stats, 0, camera_bind_group);
+      bound.set_bind_group(&mut render_pass, &mut stats, 1, transform_bind_group);
 
       if let Some((_, material_data_bind_group)) = material_data {
-        render_pass.set_bind_group(2, material_data_bind_group, &[]);
+        bound.set_bind_group(&mut render_pass, &mut stats, 2, material_data_bind_group);
       }
 
-      render_pass.set_bind_group(3, normal_bind_group, &[]);
-      render_pass.set_bind_group(4, &self.ambient_light.1, &[]);
-      render_pass.set_bind_group(5, &self.lights.bind_group, &[]);
+      bound.set_bind_group(&mut render_pass, &mut stats, 3, normal_bind_group);
+      bound.set_bind_group(&mut render_pass, &mut stats, 4, &self.ambient_light.1);
+      bound.set_bind_group(&mut render_pass, &mut stats, 5, &self.lights.bind_group);
 
-      if let Some((_, bind_group)) = diffuse_texture_id
+      if let Some((_, _, bind_group)) = diffuse_texture_id
         .as_ref()
         .and_then(|id| self.texture_map_views.get(id))
       {
-        render_pass.set_bind_group(6, bind_group, &[]);
+        bound.set_bind_group(&mut render_pass, &mut stats, 6, bind_group);
       }
 
       render_pass.set_vertex_buffer(0, vertices.slice(..));
       render_pass.set_index_buffer(indices.slice(..), IndexFormat::Uint32);
 
       render_pass.draw_indexed(0..*index_count as u32, 0, 0..1);
+      stats.record_draw(*index_count);
     }
 
     drop(render_pass);
 
-    renderer.queue().submit([encoder.finish()]);
-    output.present();
+    self.post_processing.encode(
+      encoder,
+      &target.create_view(&Default::default()),
+      &mut stats,
+    );
+
+    if let Some(timer) = timer {
+      timer.resolve(encoder);
+    }
+
+    let (buffer_memory, texture_memory) = self.memory_estimate(renderer);
+    stats.set_memory(buffer_memory, texture_memory);
+    stats.set_gpu_time(self.gpu_timer.as_ref().and_then(GpuTimer::last));
+
+    stats
+  }
+
+  /// Visible subjects, opaque ones in insertion order followed by the transparent ones sorted back
+  /// to front.
+  fn draw_order(&self, stats: &mut RenderStats) -> Vec<&Subject> {
+    let frustum = Frustum::from_matrix(self.camera_matrix);
+
+    let (opaque, mut transparent): (Vec<_>, Vec<_>) = self
+      .subjects
+      .values()
+      .filter(|subject| subject.pipeline.is_some())
+      .filter(|subject| {
+        let visible = subject
+          .bounds
+          .is_none_or(|(_, world)| frustum.intersects_aabb(&world));
+
+        if !visible {
+          stats.record_cull();
+        }
+
+        visible
+      })
+      .partition(|subject| !subject.alpha_mode.is_transparent());
+
+    let depth = |subject: &Subject| {
+      let center = subject
+        .bounds
+        .map(|(_, world)| world.center())
+        .unwrap_or_default();
+
+      self.camera_matrix.project_point3(center).z
+    };
+
+    transparent.sort_by(|a, b| depth(b).total_cmp(&depth(a)));
+
+    opaque.into_iter().chain(transparent).collect()
+  }
+
+  /// Bytes of buffer and texture memory owned by the scene.
+  fn memory_estimate(&self, renderer: &Renderer) -> (u64, u64) {
+    let subjects = self.subjects.values().map(|subject| {
+      subject.vertices.0.size()
+        + subject.indices.0.size()
+        + subject.transform.0.size()
+        + subject.normal.0.size()
+        + subject
+          .material_data
+          .as_ref()
+          .map(|(buffer, _)| buffer.size())
+          .unwrap_or_default()
+    });
+
+    let buffer_memory = [
+      &self.camera.0,
+      &self.ambient_light.0,
+      &self.lights.directional_lights,
+      &self.lights.point_lights,
+      &self.lights.spot_lights,
+      &self.lights.rect_area_lights,
+      &self.lights.counts,
+      &self.shadow_maps.casters,
+    ]
+    .into_iter()
+    .map(Buffer::size)
+    .chain(subjects)
+    .sum();
+
+    let (width, height) = self.target_size();
+    let multisample = self
+      .multisample
+      .as_ref()
+      .map(|_| {
+        width as u64
+          * height as u64
+          * self.depth.texture.sample_count() as u64
+          * renderer.hdr_format().block_copy_size(None).unwrap_or(8) as u64
+      })
+      .unwrap_or_default();
+
+    let texture_memory = self
+      .texture_map_views
+      .values()
+      .map(|(texture, _, _)| texture_size(texture))
+      .chain([
+        texture_size(&self.depth.texture),
+        texture_size(self.shadow_maps.texture()),
+        texture_size(self.lights.ltc.texture()),
+        self
+          .environment
+          .as_ref()
+          .map(|environment| texture_size(environment.texture()))
+          .unwrap_or_default(),
+        multisample,
+      ])
+      .chain(self.environment_lighting.textures().map(texture_size))
+      .chain(self.post_processing.textures().map(texture_size))
+      .sum();
+
+    (buffer_memory, texture_memory)
   }
 
   pub fn update_ambient_light(&mut self, renderer: &Renderer, ambient_light: &AmbientLight) {
+    self.bound.ambient_light = Some(ambient_light.clone());
     let (buffer, _) = &self.ambient_light;
 
     renderer.queue().write_buffer(
@@ -916,32 +2133,84 @@ impl Scene {
     );
   }
 
+  /// Replaces the hemisphere light, `HemisphereLight::default()` turns it off.
+  pub fn update_hemisphere_light(
+    &mut self,
+    renderer: &Renderer,
+    hemisphere_light: &HemisphereLight,
+  ) {
+    self.bound.hemisphere_light = Some(hemisphere_light.clone());
+    let (buffer, _) = &self.ambient_light;
+    let intensity = *hemisphere_light.intensity();
+
+    renderer.queue().write_buffer(
+      buffer,
+      size_of::<Vec4>() as u64,
+      bytemuck::cast_slice(&[
+        Vec4::from((*hemisphere_light.sky_color() * intensity, 0.0)),
+        Vec4::from((*hemisphere_light.ground_color() * intensity, 0.0)),
+        Vec4::from((hemisphere_light.up().normalize_or_zero(), 0.0)),
+      ]),
+    );
+  }
+
   pub fn update_point_light(
     &mut self,
     renderer: &Renderer,
     index: usize,
     point_light: &PointLight,
   ) {
+    if let Some(bound) = self.bound.point_lights.get_mut(index) {
+      *bound = point_light.clone();
+    }
+
+    if index >= light_storage::capacity::<PointLightBuffer>(&self.lights.point_lights) {
+      return;
+    }
+
     renderer.queue().write_buffer(
       &self.lights.point_lights,
       (index * size_of::<PointLightBuffer>()) as u64,
-      bytemuck::cast_slice(&[PointLightBuffer {
-        position: Vec4::from((*point_light.position(), 0.0)),
-        color: Vec4::from((*point_light.color(), 1.0)),
-      }]),
+      bytemuck::cast_slice(&[PointLightBuffer::new(point_light)]),
     )
   }
 
   pub fn update_spot_light(&mut self, renderer: &Renderer, index: usize, spot_light: &SpotLight) {
+    if let Some(bound) = self.bound.spot_lights.get_mut(index) {
+      *bound = spot_light.clone();
+    }
+
+    if index >= light_storage::capacity::<SpotLightBuffer>(&self.lights.spot_lights) {
+      return;
+    }
+
+    let shadow_index = self.set_spot_shadow(renderer, index, spot_light);
+
     renderer.queue().write_buffer(
       &self.lights.spot_lights,
       (index * size_of::<SpotLightBuffer>()) as u64,
-      bytemuck::cast_slice(&[SpotLightBuffer {
-        position: Vec4::from((*spot_light.position(), 0.0)),
-        color: Vec4::from((*spot_light.color(), 1.0)),
-        direction: Vec4::from((*spot_light.direction(), 1.0)),
-        cutoff_angle: Vec4::from((*spot_light.cutoff_angle(), Vec3::default())),
-      }]),
+      bytemuck::cast_slice(&[SpotLightBuffer::new(spot_light, shadow_index)]),
+    )
+  }
+
+  pub fn update_rect_area_light(
+    &mut self,
+    renderer: &Renderer,
+    index: usize,
+    rect_area_light: &RectAreaLight,
+  ) {
+    if let Some(bound) = self.bound.rect_area_lights.get_mut(index) {
+      *bound = rect_area_light.clone();
+    }
+
+    if index >= light_storage::capacity::<RectAreaLightBuffer>(&self.lights.rect_area_lights) {
+      return;
+    }
+
+    renderer.queue().write_buffer(
+      &self.lights.rect_area_lights,
+      (index * size_of::<RectAreaLightBuffer>()) as u64,
+      bytemuck::cast_slice(&[RectAreaLightBuffer::new(rect_area_light)]),
     )
   }
 
@@ -949,150 +2218,323 @@ impl Scene {
     &mut self,
     renderer: &Renderer,
     index: usize,
-    spot_light: &DirectionalLight,
+    directional_light: &DirectionalLight,
   ) {
+    if let Some(bound) = self.bound.directional_lights.get_mut(index) {
+      *bound = directional_light.clone();
+    }
+
+    if index >= light_storage::capacity::<DirectionalLightBuffer>(&self.lights.directional_lights) {
+      return;
+    }
+
+    let shadow_index = self.set_directional_shadow(renderer, index, directional_light);
+
     renderer.queue().write_buffer(
       &self.lights.directional_lights,
       (index * size_of::<DirectionalLightBuffer>()) as u64,
-      bytemuck::cast_slice(&[DirectionalLightBuffer {
-        color: Vec4::from((*spot_light.color(), 1.0)),
-        direction: Vec4::from((*spot_light.direction(), 1.0)),
-      }]),
+      bytemuck::cast_slice(&[DirectionalLightBuffer::new(directional_light, shadow_index)]),
     )
   }
 
-  pub fn bind_point_lights(&mut self, renderer: &Renderer, point_lights: &[PointLight]) {
-    let mut lights = [PointLightBuffer::default(); 16];
+  fn set_directional_shadow(
+    &mut self,
+    renderer: &Renderer,
+    index: usize,
+    light: &DirectionalLight,
+  ) -> i32 {
+    let source = ShadowSource::Directional {
+      direction: *light.direction(),
+    };
 
-    for (i, buffer) in (0..16).zip(point_lights.iter()).map(|(i, light)| {
-      (
-        i,
-        PointLightBuffer {
-          position: Vec4::from((*light.position(), 0.0)),
-          color: Vec4::from((*light.color(), 1.0)),
-        },
-      )
-    }) {
-      lights[i] = buffer;
-    }
+    self.set_shadow_source(
+      renderer,
+      directional_layer(index),
+      light.cast_shadow().then_some((source, *light.shadow())),
+    )
+  }
 
-    renderer
-      .queue()
-      .write_buffer(&self.lights.point_lights, 0, bytemuck::cast_slice(&lights));
+  fn set_spot_shadow(&mut self, renderer: &Renderer, index: usize, light: &SpotLight) -> i32 {
+    let source = ShadowSource::Spot {
+      position: *light.position(),
+      direction: *light.direction(),
+      outer_cone_angle: *light.outer_cone_angle(),
+    };
 
-    renderer.queue().write_buffer(
-      &self.lights.point_light_count,
-      0,
-      bytemuck::cast_slice(&[UVec4::from((
-        point_lights.len().min(16) as u32,
-        UVec3::default(),
-      ))]),
-    );
+    self.set_shadow_source(
+      renderer,
+      spot_layer(index),
+      light.cast_shadow().then_some((source, *light.shadow())),
+    )
   }
 
-  pub fn bind_spot_lights(&mut self, renderer: &Renderer, spot_lights: &[SpotLight]) {
-    let mut lights = [SpotLightBuffer::default(); 16];
+  /// Returns the layer the light is rendered into, `-1` when it casts no shadow.
+  fn set_shadow_source(
+    &mut self,
+    renderer: &Renderer,
+    layer: Option<usize>,
+    source: Option<(ShadowSource, Shadow)>,
+  ) -> i32 {
+    let Some(layer) = layer else {
+      return -1;
+    };
 
-    for (i, buffer) in (0..16).zip(spot_lights.iter()).map(|(i, light)| {
-      (
-        i,
-        SpotLightBuffer {
-          position: Vec4::from((*light.position(), 0.0)),
-          color: Vec4::from((*light.color(), 1.0)),
-          direction: Vec4::from((*light.direction(), 0.0)),
-          cutoff_angle: Vec4::from((*light.cutoff_angle(), Vec3::default())),
-        },
-      )
-    }) {
-      lights[i] = buffer;
+    if self.shadow_maps.set_source(renderer, layer, source) {
+      self
+        .lights
+        .rebind(renderer, &self.dynamic_lights_layout, &self.shadow_maps);
     }
 
-    renderer
-      .queue()
-      .write_buffer(&self.lights.spot_lights, 0, bytemuck::cast_slice(&lights));
+    source.map_or(-1, |_| layer as i32)
+  }
 
-    renderer.queue().write_buffer(
-      &self.lights.spot_light_count,
-      0,
-      bytemuck::cast_slice(&[UVec4::from((
-        spot_lights.len().min(16) as u32,
-        UVec3::default(),
-      ))]),
-    );
+  /// Only the first `MAX_UNIFORM_LIGHTS` lights are bound when storage buffers are unavailable,
+  /// the error reports the ones left out.
+  pub fn bind_point_lights(
+    &mut self,
+    renderer: &Renderer,
+    point_lights: &[PointLight],
+  ) -> Result<(), TooManyLights> {
+    self.bound.point_lights = point_lights.to_vec();
+    let lights = point_lights.iter().map(PointLightBuffer::new).collect();
+
+    self.write_lights(renderer, LightKind::Point, point_lights.len(), lights)
+  }
+
+  /// Only the first `MAX_UNIFORM_LIGHTS` lights are bound when storage buffers are unavailable,
+  /// the error reports the ones left out.
+  pub fn bind_spot_lights(
+    &mut self,
+    renderer: &Renderer,
+    spot_lights: &[SpotLight],
+  ) -> Result<(), TooManyLights> {
+    self.bound.spot_lights = spot_lights.to_vec();
+    self.shadow_maps.clear_sources(ShadowMaps::spot_layers());
+
+    let lights = spot_lights
+      .iter()
+      .take(self.max_lights())
+      .enumerate()
+      .map(|(i, light)| SpotLightBuffer::new(light, self.set_spot_shadow(renderer, i, light)))
+      .collect();
+
+    self.write_lights(renderer, LightKind::Spot, spot_lights.len(), lights)
   }
 
+  /// Only the first `MAX_UNIFORM_LIGHTS` lights are bound when storage buffers are unavailable,
+  /// the error reports the ones left out.
   pub fn bind_directional_lights(
     &mut self,
     renderer: &Renderer,
     directional_lights: &[DirectionalLight],
-  ) {
-    let mut lights = [DirectionalLightBuffer::default(); 16];
-
-    for (i, buffer) in (0..16).zip(directional_lights.iter()).map(|(i, light)| {
-      (
-        i,
-        DirectionalLightBuffer {
-          color: Vec4::from((*light.color(), 1.0)),
-          direction: Vec4::from((*light.direction(), 0.0)),
-        },
-      )
-    }) {
-      lights[i] = buffer;
+  ) -> Result<(), TooManyLights> {
+    self.bound.directional_lights = directional_lights.to_vec();
+    self
+      .shadow_maps
+      .clear_sources(ShadowMaps::directional_layers());
+
+    let lights = directional_lights
+      .iter()
+      .take(self.max_lights())
+      .enumerate()
+      .map(|(i, light)| {
+        DirectionalLightBuffer::new(light, self.set_directional_shadow(renderer, i, light))
+      })
+      .collect();
+
+    self.write_lights(
+      renderer,
+      LightKind::Directional,
+      directional_lights.len(),
+      lights,
+    )
+  }
+
+  /// Only the first `MAX_UNIFORM_LIGHTS` lights are bound when storage buffers are unavailable,
+  /// the error reports the ones left out.
+  pub fn bind_rect_area_lights(
+    &mut self,
+    renderer: &Renderer,
+    rect_area_lights: &[RectAreaLight],
+  ) -> Result<(), TooManyLights> {
+    self.bound.rect_area_lights = rect_area_lights.to_vec();
+    let lights = rect_area_lights
+      .iter()
+      .map(RectAreaLightBuffer::new)
+      .collect();
+
+    self.write_lights(
+      renderer,
+      LightKind::RectArea,
+      rect_area_lights.len(),
+      lights,
+    )
+  }
+
+  fn max_lights(&self) -> usize {
+    self.light_storage.max_lights().unwrap_or(usize::MAX)
+  }
+
+  /// Uploads the bound lights of one kind and their count, `count` is the number of lights
+  /// requested before they were limited to `max_lights`.
+  fn write_lights<T: Pod + Default>(
+    &mut self,
+    renderer: &Renderer,
+    kind: LightKind,
+    count: usize,
+    mut lights: Vec<T>,
+  ) -> Result<(), TooManyLights> {
+    let max = self.max_lights();
+    lights.truncate(max);
+
+    let (buffer, label) = self.lights.buffer(kind);
+    let reallocated = self
+      .light_storage
+      .reserve::<T>(renderer, buffer, label, lights.len());
+
+    if !lights.is_empty() {
+      renderer
+        .queue()
+        .write_buffer(buffer, 0, bytemuck::cast_slice(&lights));
     }
 
     renderer.queue().write_buffer(
-      &self.lights.directional_lights,
-      0,
-      bytemuck::cast_slice(&lights),
+      &self.lights.counts,
+      (kind as usize * size_of::<u32>()) as u64,
+      bytemuck::cast_slice(&[lights.len() as u32]),
     );
 
-    renderer.queue().write_buffer(
-      &self.lights.directional_light_count,
-      0,
-      bytemuck::cast_slice(&[UVec4::from((
-        directional_lights.len().min(16) as u32,
-        UVec3::default(),
-      ))]),
-    );
+    if reallocated {
+      self
+        .lights
+        .rebind(renderer, &self.dynamic_lights_layout, &self.shadow_maps);
+    }
+
+    if count > max {
+      return Err(TooManyLights { kind, count, max });
+    }
+
+    Ok(())
   }
 
   pub fn set_camera(&mut self, renderer: &Renderer, camera: &impl Camera) {
-    let mut camera_buffer_contents = [0.0; 4 * 5];
-    camera
-      .to_matrix()
-      .write_cols_to_slice(&mut camera_buffer_contents);
-    camera
-      .pos()
-      .write_to_slice(&mut camera_buffer_contents[16..]);
-
     let camera_buffer = renderer.device().create_buffer_init(&BufferInitDescriptor {
       label: Some("camera buffer"),
-      contents: bytemuck::cast_slice(&camera_buffer_contents),
+      contents: bytemuck::cast_slice(&self.camera_contents(camera)),
       usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
     });
 
-    let camera_bind_group = renderer.device().create_bind_group(&BindGroupDescriptor {
-      label: Some("camera bind group"),
-      layout: &self.camera_layout,
-      entries: &[BindGroupEntry {
-        binding: 0,
-        resource: camera_buffer.as_entire_binding(),
-      }],
-    });
+    let camera_bind_group = camera_bind_group(
+      renderer,
+      &self.camera_layout,
+      &camera_buffer,
+      &self.fog_buffer,
+    );
 
     self.camera = (camera_buffer, camera_bind_group);
   }
 
-  pub fn update_camera(&self, renderer: &Renderer, camera: &impl Camera) {
+  pub fn update_camera(&mut self, renderer: &Renderer, camera: &impl Camera) {
+    let contents = self.camera_contents(camera);
     let (camera_buffer, _) = &self.camera;
 
+    renderer
+      .queue()
+      .write_buffer(camera_buffer, 0, bytemuck::cast_slice(&contents));
+  }
+
+  fn camera_contents(&mut self, camera: &impl Camera) -> [f32; 4 * 5] {
+    self.camera_matrix = camera.to_matrix();
+    self.camera_position = *camera.pos();
+
+    let mut contents = [0.0; 4 * 5];
+    self.camera_matrix.write_cols_to_slice(&mut contents);
+    camera.pos().write_to_slice(&mut contents[16..]);
+    self.stored_camera = contents;
+
+    contents
+  }
+
+  /// Replaces the environment, `None` removes it. The textures of its source have to be in
+  /// `texture_loader` already. PBR materials are lit by it, which is baked right away.
+  pub fn set_environment(
+    &mut self,
+    renderer: &Renderer,
+    texture_loader: &TextureLoader,
+    environment: Option<&Environment>,
+  ) -> Result<(), EnvironmentError> {
+    self.environment = environment
+      .map(|environment| Skybox::new(renderer, texture_loader, environment))
+      .transpose()?;
+    self.bound.environment = environment.cloned();
+
+    match &self.environment {
+      Some(skybox) => {
+        self
+          .environment_lighting
+          .bake(renderer, &skybox.view, skybox.texture().width())
+      }
+      None => self.environment_lighting.clear(renderer),
+    }
+
+    self.ambient_light.1 = ambient_light_bind_group(
+      renderer,
+      &self.ambient_light_layout,
+      &self.ambient_light.0,
+      &self.environment_lighting,
+    );
+    self.write_environment_lighting(renderer);
+
+    Ok(())
+  }
+
+  /// Replaces the effects applied to the rendered frame, the textures they look up have to be in
+  /// `texture_loader` already. The `PostStage::Hdr` effects are applied in order before the tone
+  /// mapping and the `PostStage::Ldr` ones in order after it, wherever they are in `effects`.
+  pub fn set_post_processing(
+    &mut self,
+    renderer: &Renderer,
+    texture_loader: &TextureLoader,
+    effects: &[&dyn PostEffect],
+  ) -> Result<(), PostProcessingError> {
+    let effects = effects
+      .iter()
+      .map(|effect| (effect.stage(), effect.passes()))
+      .collect::<Vec<_>>();
+
+    self
+      .post_processing
+      .set_effects(renderer, texture_loader, &effects)?;
+    self.bound.post_processing = effects;
+
+    Ok(())
+  }
+
+  /// Scales the lighting of PBR materials by the environment, `1.0` by default.
+  pub fn set_environment_intensity(&mut self, renderer: &Renderer, intensity: f32) {
+    self.environment_intensity = intensity;
+    self.write_environment_lighting(renderer);
+  }
+
+  fn write_environment_lighting(&self, renderer: &Renderer) {
     renderer.queue().write_buffer(
-      camera_buffer,
-      0,
-      bytemuck::cast_slice(&[camera.to_matrix()]),
+      &self.ambient_light.0,
+      4 * size_of::<Vec4>() as u64,
+      bytemuck::cast_slice(&[Vec4::new(
+        self.environment_intensity,
+        self.environment_lighting.max_level(),
+        0.0,
+        0.0,
+      )]),
     );
   }
 
+  /// Ray from the current camera through a point of the viewport, see `Ray::from_camera`.
+  pub fn camera_ray(&self, screen: Vec2) -> Ray {
+    Ray::from_view_projection(self.camera_matrix, screen)
+  }
+
+  /// Fails like `insert`, keeping the current material.
   pub fn update_material(
     &mut self,
     renderer: &Renderer,
@@ -1100,18 +2542,25 @@ impl Scene {
     texture_loader: &TextureLoader,
     material_loader: &MaterialLoader,
     material_id: Id,
-  ) {
+  ) -> Result<(), LightArrayError> {
     let (Some(subject), Some(material)) = (
       self.subjects.get_mut(&resource.id()),
       material_loader.get_from_id(material_id),
     ) else {
-      return;
+      return Ok(());
     };
 
+    let fragment_shader = self.light_storage.shader(material.fragment_shader())?;
+
     if let Some(id) = material.diffuse_map_texture_id() {
-      if let Some((content, dimensions)) = texture_loader
-        .get_from_id(*id)
-        .map(|texture| (texture.content.as_slice(), texture.dimensions))
+      if let Some((content, dimensions, texel_format)) =
+        texture_loader.get_from_id(*id).map(|texture| {
+          (
+            texture.content.as_slice(),
+            texture.dimensions,
+            texture.format,
+          )
+        })
       {
         let size = Extent3d {
           width: dimensions.0,
@@ -1124,7 +2573,7 @@ impl Scene {
           mip_level_count: 1,
           sample_count: 1,
           dimension: TextureDimension::D2,
-          format: TextureFormat::Rgba8UnormSrgb,
+          format: texel_format.wgpu_format(),
           usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
           label: Some(&format!(
             r#"TextureId({:?})"#,
@@ -1143,7 +2592,7 @@ impl Scene {
           content,
           ImageDataLayout {
             offset: 0,
-            bytes_per_row: Some(4 * dimensions.0),
+            bytes_per_row: Some(texel_format.bytes_per_texel() * dimensions.0),
             rows_per_image: Some(dimensions.1),
           },
           size,
@@ -1171,7 +2620,7 @@ impl Scene {
 
         self
           .texture_map_views
-          .insert(*id, (texture_view, texture_bind_group));
+          .insert(*id, (texture, texture_view, texture_bind_group));
       }
     }
 
@@ -1181,7 +2630,7 @@ impl Scene {
         label: Some("transform bind group layout"),
         entries: &[BindGroupLayoutEntry {
           binding: 0,
-          visibility: ShaderStages::VERTEX,
+          visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
           ty: BindingType::Buffer {
             ty: BufferBindingType::Uniform,
             has_dynamic_offset: false,
@@ -1257,16 +2706,9 @@ impl Scene {
             push_constant_ranges: &[],
           });
 
-        let target = [renderer.supported_format().map(|format| ColorTargetState {
-          format,
-          blend: Some(BlendState {
-            color: BlendComponent {
-              src_factor: BlendFactor::SrcAlpha,
-              dst_factor: BlendFactor::OneMinusSrcAlpha,
-              operation: BlendOperation::Add,
-            },
-            alpha: BlendComponent::OVER,
-          }),
+        let target = [Some(ColorTargetState {
+          format: *renderer.hdr_format(),
+          blend: material.alpha_mode().blend_state(),
           write_mask: ColorWrites::ALL,
         })];
 
@@ -1274,9 +2716,7 @@ impl Scene {
           .device()
           .create_shader_module(material.vertex_shader().clone());
 
-        let fragment_shader = renderer
-          .device()
-          .create_shader_module(material.fragment_shader().clone());
+        let fragment_shader = renderer.device().create_shader_module(fragment_shader);
 
         let pipeline = Arc::new(renderer.device().create_render_pipeline(
           &RenderPipelineDescriptor {
@@ -1292,20 +2732,20 @@ impl Scene {
               topology: PrimitiveTopology::TriangleList,
               strip_index_format: None,
               front_face: FrontFace::Ccw,
-              cull_mode: Some(Face::Back),
+              cull_mode: material.cull_mode(),
               polygon_mode: PolygonMode::Fill,
               unclipped_depth: false,
               conservative: false,
             },
             depth_stencil: Some(DepthStencilState {
               format: TextureFormat::Depth32Float,
-              depth_write_enabled: true,
+              depth_write_enabled: !material.alpha_mode().is_transparent(),
               depth_compare: CompareFunction::Less,
               stencil: StencilState::default(),
               bias: DepthBiasState::default(),
             }),
             multisample: MultisampleState {
-              count: 1,
+              count: *renderer.sample_count(),
               mask: !0,
               alpha_to_coverage_enabled: false,
             },
@@ -1329,9 +2769,13 @@ impl Scene {
 
     subject.pipeline = Some(pipeline);
     subject.diffuse_texture_id = material.diffuse_map_texture_id().clone();
+    subject.alpha_mode = *material.alpha_mode();
+    subject.shadow_mask = self.shadow_maps.create_mask(renderer, material);
     subject.material_data = Some((fragment_data_buffer, fragment_data_bind_group));
 
     resource.set_material_id(material_id);
+
+    Ok(())
   }
 
   pub fn update_geometry(
@@ -1363,7 +2807,11 @@ impl Scene {
       geometry.indices().len(),
     );
 
+    let world = self.world_matrix(resource.id()).unwrap_or_default();
+
     if let Some(subject) = self.subjects.get_mut(&resource.id()) {
+      subject.geometry_id = Some(geometry_id);
+      subject.bounds = Some((*geometry.aabb(), geometry.aabb().transform(world)));
       subject.indices = indices;
       subject.vertices = vertices;
     }
@@ -1371,21 +2819,45 @@ impl Scene {
     resource.set_geometry_id(geometry_id);
   }
 
+  /// Writes the fragment data of `material` to the subject of `resource`, along with the color and
+  /// cutoff its shadow is alpha tested with. `material` must use the shaders the subject was
+  /// inserted or last updated with.
   pub fn update_material_data(
     &self,
     renderer: &Renderer,
     resource: &(impl Resource + Object3D),
-    data: &[u8],
+    material: &Material,
   ) {
     let Some(subject) = self.subjects.get(&resource.id()) else {
       return;
     };
 
     if let Some((buffer, _)) = &subject.material_data {
-      renderer.queue().write_buffer(buffer, 0, data);
+      renderer
+        .queue()
+        .write_buffer(buffer, 0, material.fragment_data());
+    }
+
+    if let Some((buffer, _)) = &subject.shadow_mask {
+      ShadowMaps::write_mask(renderer, buffer, material);
     }
   }
 
+  /// Applies the `cast_shadow` and `receive_shadow` flags of `resource`.
+  pub fn update_shadows(&mut self, renderer: &Renderer, resource: &(impl Resource + Object3D)) {
+    let Some(subject) = self.subjects.get_mut(&resource.id()) else {
+      return;
+    };
+
+    subject.cast_shadow = resource.cast_shadow();
+
+    renderer.queue().write_buffer(
+      &subject.transform.0,
+      size_of::<Mat4>() as u64,
+      bytemuck::cast_slice(&[transform_flags(resource)]),
+    );
+  }
+
   pub fn remove_material(&mut self, resource: &(impl Resource + Object3D)) {
     let Some(subject) = self.subjects.get_mut(&resource.id()) else {
       return;
@@ -0,0 +1,43 @@
+// Halves a level of a cubemap into the next one, one face per three vertices.
+
+@group(0) @binding(0)
+var source: texture_cube<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+  @location(1) @interpolate(flat) face: u32,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  let corner = index % 3u;
+  let uv = vec2<f32>(f32((corner << 1u) & 2u), f32(corner & 2u));
+
+  var out: VertexOutput;
+  out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+  out.uv = uv;
+  out.face = index / 3u;
+
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  // a bilinear tap between four texels of the level above averages them
+  return textureSampleLevel(source, source_sampler, cube_direction(in.face, in.uv * 2.0 - 1.0), 0.0);
+}
+
+// Direction sampled at `st` on `face`, with `t` pointing down the face.
+fn cube_direction(face: u32, st: vec2<f32>) -> vec3<f32> {
+  switch face {
+    case 0u: { return vec3<f32>(1.0, -st.y, -st.x); }
+    case 1u: { return vec3<f32>(-1.0, -st.y, st.x); }
+    case 2u: { return vec3<f32>(st.x, 1.0, st.y); }
+    case 3u: { return vec3<f32>(st.x, -1.0, -st.y); }
+    case 4u: { return vec3<f32>(st.x, -st.y, 1.0); }
+    default: { return vec3<f32>(-st.x, -st.y, -1.0); }
+  }
+}
@@ -0,0 +1,40 @@
+// `FOG_SHADER`, prepended by `include_fragment_wgsl!` to the fragment shaders of the built-in
+// materials, which use `fog` and `apply_fog` without declaring them.
+
+struct Fog {
+  color: vec3<f32>,
+  mode: u32,
+  camera_position: vec3<f32>,
+  density: f32,
+  near: f32,
+  far: f32,
+  height_falloff: f32,
+  pad0: f32,
+}
+
+@group(0) @binding(1)
+var<uniform> fog: Fog;
+
+fn apply_fog(color: vec4<f32>, world_position: vec3<f32>) -> vec4<f32> {
+  let view_distance = length(world_position - fog.camera_position);
+
+  var amount = 0.0;
+  switch fog.mode {
+    case 1u: {
+      amount = clamp((view_distance - fog.near) / max(fog.far - fog.near, 0.0001), 0.0, 1.0);
+    }
+    case 2u: {
+      amount = 1.0 - exp(-fog.density * view_distance);
+    }
+    case 3u: {
+      let optical_depth = fog.density * view_distance;
+      amount = 1.0 - exp(-optical_depth * optical_depth);
+    }
+    default: {}
+  }
+
+  // thins out above the ground plane
+  amount *= exp(-fog.height_falloff * max(world_position.y, 0.0));
+
+  return vec4<f32>(mix(color.rgb, fog.color, amount), color.a);
+}
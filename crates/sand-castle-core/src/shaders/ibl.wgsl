@@ -0,0 +1,177 @@
+// Bakes the image based lighting of an environment cubemap, one face per three vertices: the
+// diffuse irradiance, the specular prefiltered for a roughness and the split sum BRDF.
+
+struct Bake {
+  roughness: f32,
+  // faces of the first level of the environment and of the level being rendered
+  source_size: f32,
+  target_size: f32,
+  sample_count: u32,
+}
+
+@group(0) @binding(0)
+var environment: texture_cube<f32>;
+@group(0) @binding(1)
+var environment_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> bake: Bake;
+
+const PI = radians(180.0);
+const BRDF_SAMPLES = 512u;
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+  @location(1) @interpolate(flat) face: u32,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  let corner = index % 3u;
+  let uv = vec2<f32>(f32((corner << 1u) & 2u), f32(corner & 2u));
+
+  var out: VertexOutput;
+  out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+  out.uv = uv;
+  out.face = index / 3u;
+
+  return out;
+}
+
+// Cosine weighted average of the environment around the normal, the irradiance over PI.
+@fragment
+fn fs_irradiance(in: VertexOutput) -> @location(0) vec4<f32> {
+  let normal = normalize(cube_direction(in.face, in.uv * 2.0 - 1.0));
+  let frame = tangent_frame(normal);
+
+  var irradiance = vec3<f32>(0.0, 0.0, 0.0);
+  for (var index = 0u; index < bake.sample_count; index++) {
+    let xi = hammersley(index, bake.sample_count);
+    let cos_theta = sqrt(1.0 - xi.y);
+    let sin_theta = sqrt(xi.y);
+    let phi = 2.0 * PI * xi.x;
+    let direction = frame * vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+
+    irradiance += textureSampleLevel(environment, environment_sampler, direction, source_level(cos_theta / PI)).rgb;
+  }
+
+  return vec4<f32>(irradiance / f32(bake.sample_count), 1.0);
+}
+
+// Environment reflected by a GGX lobe of `bake.roughness`, with the view along the normal.
+@fragment
+fn fs_prefiltered(in: VertexOutput) -> @location(0) vec4<f32> {
+  let normal = normalize(cube_direction(in.face, in.uv * 2.0 - 1.0));
+
+  if bake.roughness == 0.0 {
+    let level = max(log2(bake.source_size / bake.target_size), 0.0);
+    return textureSampleLevel(environment, environment_sampler, normal, level);
+  }
+
+  let alpha = bake.roughness * bake.roughness;
+  let frame = tangent_frame(normal);
+
+  var color = vec3<f32>(0.0, 0.0, 0.0);
+  var weight = 0.0;
+  for (var index = 0u; index < bake.sample_count; index++) {
+    let xi = hammersley(index, bake.sample_count);
+    let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    let phi = 2.0 * PI * xi.x;
+    let half_vector = frame * vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+    let light = 2.0 * dot(normal, half_vector) * half_vector - normal;
+    let n_dot_l = dot(normal, light);
+
+    if n_dot_l > 0.0 {
+      // the view is the normal, so the pdf of the light direction is D / 4
+      let pdf = ggx(cos_theta, alpha) / 4.0;
+      color += textureSampleLevel(environment, environment_sampler, light, source_level(pdf)).rgb * n_dot_l;
+      weight += n_dot_l;
+    }
+  }
+
+  return vec4<f32>(color / max(weight, 0.0001), 1.0);
+}
+
+// Scale and bias applied to F0 by the GGX BRDF, by the cosine of the view along `x` and the
+// roughness along `y`.
+@fragment
+fn fs_brdf(in: VertexOutput) -> @location(0) vec4<f32> {
+  let n_dot_v = max(in.uv.x, 0.001);
+  let alpha = in.uv.y * in.uv.y;
+  let view = vec3<f32>(sqrt(1.0 - n_dot_v * n_dot_v), 0.0, n_dot_v);
+  let k = alpha / 2.0;
+
+  var scale = 0.0;
+  var bias = 0.0;
+  for (var index = 0u; index < BRDF_SAMPLES; index++) {
+    let xi = hammersley(index, BRDF_SAMPLES);
+    let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    let phi = 2.0 * PI * xi.x;
+    let half_vector = vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+    let light = 2.0 * dot(view, half_vector) * half_vector - view;
+
+    let n_dot_l = light.z;
+    let v_dot_h = max(dot(view, half_vector), 0.0);
+
+    if n_dot_l > 0.0 {
+      let geometry = n_dot_v / (n_dot_v * (1.0 - k) + k) * n_dot_l / (n_dot_l * (1.0 - k) + k);
+      let visibility = geometry * v_dot_h / (cos_theta * n_dot_v);
+      let fresnel = pow(1.0 - v_dot_h, 5.0);
+
+      scale += (1.0 - fresnel) * visibility;
+      bias += fresnel * visibility;
+    }
+  }
+
+  return vec4<f32>(scale / f32(BRDF_SAMPLES), bias / f32(BRDF_SAMPLES), 0.0, 1.0);
+}
+
+fn ggx(n_dot_h: f32, alpha: f32) -> f32 {
+  let alpha2 = alpha * alpha;
+  let d = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+
+  return alpha2 / (PI * d * d);
+}
+
+// Level of the environment whose texels cover the solid angle of a sample drawn with `pdf`, which
+// keeps few samples from aliasing.
+fn source_level(pdf: f32) -> f32 {
+  let sample_solid_angle = 1.0 / (f32(bake.sample_count) * pdf + 0.0001);
+  let texel_solid_angle = 4.0 * PI / (6.0 * bake.source_size * bake.source_size);
+
+  return max(0.5 * log2(sample_solid_angle / texel_solid_angle) + 1.0, 0.0);
+}
+
+// Rotates directions around z into directions around `normal`.
+fn tangent_frame(normal: vec3<f32>) -> mat3x3<f32> {
+  let up = select(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 0.0, 1.0), abs(normal.z) < 0.999);
+  let tangent = normalize(cross(up, normal));
+
+  return mat3x3<f32>(tangent, cross(normal, tangent), normal);
+}
+
+// Low discrepancy point `index` out of `count`, the bits are reversed by hand as GLSL ES 3.0 has
+// no `bitfieldReverse`.
+fn hammersley(index: u32, count: u32) -> vec2<f32> {
+  var bits = (index << 16u) | (index >> 16u);
+  bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+  bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+  bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+  bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+
+  return vec2<f32>(f32(index) / f32(count), f32(bits) * 2.3283064365386963e-10);
+}
+
+// Direction sampled at `st` on `face`, with `t` pointing down the face.
+fn cube_direction(face: u32, st: vec2<f32>) -> vec3<f32> {
+  switch face {
+    case 0u: { return vec3<f32>(1.0, -st.y, -st.x); }
+    case 1u: { return vec3<f32>(-1.0, -st.y, st.x); }
+    case 2u: { return vec3<f32>(st.x, 1.0, st.y); }
+    case 3u: { return vec3<f32>(st.x, -1.0, -st.y); }
+    case 4u: { return vec3<f32>(st.x, -st.y, 1.0); }
+    default: { return vec3<f32>(-st.x, -st.y, -1.0); }
+  }
+}
@@ -0,0 +1,199 @@
+// Prepended to the lit fragment shaders by `include_lit_fragment_wgsl!`, after `fog.wgsl`.
+
+@group(1) @binding(0)
+var<uniform> transform: Transform;
+
+struct Transform {
+  world: mat4x4<f32>,
+  // `x` is non zero when the subject receives shadows
+  flags: vec4<u32>,
+}
+
+@group(4) @binding(0)
+var<uniform> ambient_light: AmbientLight;
+
+struct AmbientLight {
+  color: vec4<f32>,
+  // the hemisphere light, blended from the ground to the sky color along `up`
+  sky_color: vec4<f32>,
+  ground_color: vec4<f32>,
+  up: vec4<f32>,
+  // `x` scales the environment lighting, `y` is the level of the prefiltered map for a roughness of one
+  environment: vec4<f32>,
+}
+
+// the light arrays become runtime sized storage arrays where storage buffers are supported
+@group(5) @binding(0)
+var<uniform> directional_lights: array<DirectionalLight, 16>;
+@group(5) @binding(1)
+var<uniform> point_lights: array<PointLight, 16>;
+@group(5) @binding(2)
+var<uniform> spot_lights: array<SpotLight, 16>;
+@group(5) @binding(3)
+var<uniform> rect_area_lights: array<RectAreaLight, 16>;
+@group(5) @binding(4)
+var<uniform> light_counts: LightCounts;
+@group(5) @binding(5)
+var<uniform> shadow_casters: array<ShadowCaster, 8>;
+@group(5) @binding(6)
+var shadow_maps: texture_depth_2d_array;
+@group(5) @binding(7)
+var shadow_sampler: sampler_comparison;
+
+struct SpotLight {
+  point_light: PointLight,
+  direction: vec3<f32>,
+  outer_cone_cos: f32,
+  inner_cone_cos: f32,
+  shadow_index: i32,
+}
+
+struct PointLight {
+  pos: vec3<f32>,
+  // `0.0` for no cutoff
+  range: f32,
+  color: vec3<f32>,
+  intensity: f32,
+  decay: f32,
+}
+
+struct DirectionalLight {
+  color: vec3<f32>,
+  intensity: f32,
+  direction: vec3<f32>,
+  shadow_index: i32,
+}
+
+// Rectangle spanning `half_width` and `half_height` around `position`, emitting towards
+// `cross(half_height, half_width)`.
+struct RectAreaLight {
+  position: vec3<f32>,
+  half_width: vec3<f32>,
+  half_height: vec3<f32>,
+  color: vec3<f32>,
+  intensity: f32,
+}
+
+struct ShadowCaster {
+  view_projection: mat4x4<f32>,
+  bias: f32,
+  normal_bias: f32,
+  radius: f32,
+  pad0: f32,
+}
+
+struct LightCounts {
+  directional: u32,
+  point: u32,
+  spot: u32,
+  rect_area: u32,
+}
+
+// Inverse distance falloff raised to `decay`, smoothly faded to zero at `range`.
+fn distance_attenuation(light: PointLight, distance: f32) -> f32 {
+  var attenuation = 1.0 / pow(max(distance, 0.01), light.decay);
+
+  if light.range > 0.0 {
+    let window = clamp(1.0 - pow(distance / light.range, 4.0), 0.0, 1.0);
+    attenuation *= window * window;
+  }
+
+  return attenuation;
+}
+
+// Smooth penumbra between the inner and the outer cone, `light_direction` points towards the light.
+fn spot_cone(light: SpotLight, light_direction: vec3<f32>) -> f32 {
+  return smoothstep(light.outer_cone_cos, light.inner_cone_cos, dot(-light_direction, light.direction));
+}
+
+fn hemisphere_light(normal: vec3<f32>) -> vec4<f32> {
+  let weight = 0.5 * dot(normal, ambient_light.up.xyz) + 0.5;
+
+  return mix(ambient_light.ground_color, ambient_light.sky_color, weight);
+}
+
+// Rotates world directions into a frame with `normal` along z and `towards` in the xz plane.
+fn shading_frame(normal: vec3<f32>, towards: vec3<f32>) -> mat3x3<f32> {
+  var tangent = towards - normal * dot(towards, normal);
+
+  if dot(tangent, tangent) < 0.000001 {
+    let axis = select(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 1.0, 0.0), abs(normal.x) > 0.9);
+    tangent = axis - normal * dot(axis, normal);
+  }
+
+  tangent = normalize(tangent);
+
+  return transpose(mat3x3<f32>(tangent, cross(normal, tangent), normal));
+}
+
+// Cosine weighted solid angle over PI of a rect area light seen from `position`, once its corners
+// are transformed by `m`. Nothing is emitted behind the light.
+fn rect_form_factor(light: RectAreaLight, position: vec3<f32>, m: mat3x3<f32>) -> f32 {
+  if dot(cross(light.half_height, light.half_width), position - light.position) <= 0.0 {
+    return 0.0;
+  }
+
+  let center = light.position - position;
+  var corners = array<vec3<f32>, 4>(
+    normalize(m * (center + light.half_width + light.half_height)),
+    normalize(m * (center - light.half_width + light.half_height)),
+    normalize(m * (center - light.half_width - light.half_height)),
+    normalize(m * (center + light.half_width - light.half_height)),
+  );
+
+  var form_factor = vec3<f32>(0.0, 0.0, 0.0);
+  for (var index = 0; index < 4; index++) {
+    form_factor += edge_form_factor(corners[index], corners[(index + 1) % 4]);
+  }
+
+  // the part below the horizon is clipped by approximating the polygon with a sphere
+  let length_ = length(form_factor);
+
+  return max((length_ * length_ + form_factor.z) / (length_ + 1.0), 0.0);
+}
+
+// Vector form factor of the arc between two unit vectors, with a rational fit of theta / sin(theta).
+fn edge_form_factor(v1: vec3<f32>, v2: vec3<f32>) -> vec3<f32> {
+  let x = dot(v1, v2);
+  let y = abs(x);
+
+  let a = 0.8543985 + (0.4965155 + 0.0145206 * y) * y;
+  let b = 3.4175940 + (4.1616724 + y) * y;
+  let v = a / b;
+
+  let theta_sin_theta = select(0.5 * inverseSqrt(max(1.0 - x * x, 0.0000001)) - v, v, x > 0.0);
+
+  return cross(v1, v2) * theta_sin_theta;
+}
+
+// Fraction of the light of shadow caster `index` reaching `world_position`, one without a caster
+// or when the subject doesn't receive shadows.
+fn shadow_factor(index: i32, world_position: vec3<f32>, normal: vec3<f32>) -> f32 {
+  if index < 0 || transform.flags.x == 0u {
+    return 1.0;
+  }
+
+  let caster = shadow_casters[index];
+  let position = world_position + normalize(normal) * caster.normal_bias;
+  let clip = caster.view_projection * vec4<f32>(position, 1.0);
+  let ndc = clip.xyz / clip.w;
+  let uv = ndc.xy * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5, 0.5);
+
+  // nothing outside of the light frustum is shadowed
+  if clip.w <= 0.0 || ndc.z > 1.0 || any(uv < vec2<f32>(0.0, 0.0)) || any(uv > vec2<f32>(1.0, 1.0)) {
+    return 1.0;
+  }
+
+  let texel = caster.radius / vec2<f32>(textureDimensions(shadow_maps));
+  var lit = 0.0;
+
+  // 3x3 PCF, every tap is also filtered by the comparison sampler
+  for (var y = -1; y <= 1; y++) {
+    for (var x = -1; x <= 1; x++) {
+      let offset = vec2<f32>(f32(x), f32(y)) * texel;
+      lit += textureSampleCompareLevel(shadow_maps, shadow_sampler, uv + offset, index, ndc.z - caster.bias);
+    }
+  }
+
+  return lit / 9.0;
+}
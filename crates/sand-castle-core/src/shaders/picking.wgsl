@@ -0,0 +1,39 @@
+struct Camera {
+  view_matrix: mat4x4<f32>,
+  position: vec3<f32>,
+  pad0: f32,
+}
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+};
+
+struct FragmentOutput {
+  @location(0) id: u32,
+  @location(1) depth: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+@group(1) @binding(0)
+var<uniform> transformation: mat4x4<f32>;
+
+// only `x` is used, uniforms are padded to 16 bytes for WebGL
+@group(2) @binding(0)
+var<uniform> pick_index: vec4<u32>;
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>) -> VertexOutput {
+  var out: VertexOutput;
+  out.clip_position = camera.view_matrix * transformation * vec4<f32>(position, 1.0);
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+  var out: FragmentOutput;
+  out.id = pick_index.x;
+  out.depth = bitcast<u32>(in.clip_position.z);
+  return out;
+}
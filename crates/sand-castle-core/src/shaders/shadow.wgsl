@@ -0,0 +1,52 @@
+@group(0) @binding(0)
+var<uniform> light: mat4x4<f32>;
+
+@group(1) @binding(0)
+var<uniform> transformation: mat4x4<f32>;
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+  return light * transformation * vec4<f32>(position, 1.0);
+}
+
+struct MaskedOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) tex_coords: vec2<f32>,
+}
+
+struct Mask {
+  color: vec4<f32>,
+  alpha_cutoff: f32,
+  pad0: f32,
+  pad1: f32,
+  pad2: f32,
+}
+
+@group(2) @binding(0)
+var<uniform> mask: Mask;
+
+@group(3) @binding(0)
+var diffuse_map: texture_2d<f32>;
+@group(3) @binding(1)
+var diffuse_sampler: sampler;
+
+@vertex
+fn vs_masked(
+  @location(0) position: vec3<f32>,
+  @location(2) tex_coords: vec2<f32>,
+) -> MaskedOutput {
+  var out: MaskedOutput;
+  out.clip_position = light * transformation * vec4<f32>(position, 1.0);
+  out.tex_coords = tex_coords;
+  return out;
+}
+
+// same test as the built-in materials, a subject without a diffuse map samples a white texel
+@fragment
+fn fs_masked(in: MaskedOutput) {
+  let alpha = mask.color.a * textureSample(diffuse_map, diffuse_sampler, in.tex_coords).a;
+
+  if alpha < mask.alpha_cutoff {
+    discard;
+  }
+}
@@ -0,0 +1,41 @@
+// Draws the environment cubemap behind everything with a single triangle covering the screen.
+
+struct Skybox {
+  inverse_view_projection: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> skybox: Skybox;
+@group(0) @binding(1)
+var environment_map: texture_cube<f32>;
+@group(0) @binding(2)
+var environment_sampler: sampler;
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) ndc: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  let ndc = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+
+  var out: VertexOutput;
+  out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+  out.ndc = ndc;
+
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  // the view ray through the fragment, which also works for orthographic cameras
+  let near = skybox.inverse_view_projection * vec4<f32>(in.ndc, 0.0, 1.0);
+  let far = skybox.inverse_view_projection * vec4<f32>(in.ndc, 1.0, 1.0);
+  let direction = far.xyz / far.w - near.xyz / near.w;
+
+  // cubemap faces are laid out for a left handed frame, the world is right handed
+  let color = textureSample(environment_map, environment_sampler, direction * vec3<f32>(-1.0, 1.0, 1.0));
+
+  return vec4<f32>(color.rgb, 1.0);
+}
@@ -0,0 +1,623 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3, Vec4};
+use indexmap::IndexMap;
+use wgpu::{
+  include_wgsl,
+  util::{BufferInitDescriptor, DeviceExt, TextureDataOrder},
+  AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+  BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+  BufferBindingType, BufferUsages, CommandEncoder, CompareFunction, DepthBiasState,
+  DepthStencilState, Extent3d, FilterMode, FragmentState, FrontFace, IndexFormat, LoadOp,
+  MultisampleState, Operations, PipelineLayoutDescriptor, PolygonMode, PrimitiveState,
+  PrimitiveTopology, RenderPass, RenderPassDepthStencilAttachment, RenderPassDescriptor,
+  RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor,
+  ShaderModule, ShaderStages, StencilState, StoreOp, Texture, TextureDescriptor, TextureDimension,
+  TextureFormat, TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor,
+  TextureViewDimension, VertexState,
+};
+
+use crate::{
+  bounds::Aabb,
+  renderer::Renderer,
+  resource::{
+    geometry::Geometry,
+    lighting::{
+      light::shadow::Shadow,
+      material::{AlphaMode, Material},
+    },
+    texture::TextureId,
+  },
+  scene::Subject,
+  stats::RenderStats,
+};
+
+pub(crate) const SHADOW_MAP_SIZE: u32 = 1024;
+
+/// Only the first lights of each kind cast shadows, each of them owns a fixed layer of the shadow
+/// maps so the indices stored with the lights never move.
+pub(crate) const MAX_DIRECTIONAL_SHADOWS: usize = 4;
+pub(crate) const MAX_SPOT_SHADOWS: usize = 4;
+
+const LAYER_COUNT: usize = MAX_DIRECTIONAL_SHADOWS + MAX_SPOT_SHADOWS;
+
+/// Light a shadow map is rendered from.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum ShadowSource {
+  Directional {
+    direction: Vec3,
+  },
+  Spot {
+    position: Vec3,
+    direction: Vec3,
+    outer_cone_angle: f32,
+  },
+}
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Default, Clone, Copy)]
+struct ShadowCasterBuffer {
+  view_projection: Mat4,
+  bias: f32,
+  normal_bias: f32,
+  radius: f32,
+  _padding: f32,
+}
+
+/// `Mask` in `shadow.wgsl`.
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+struct ShadowMaskBuffer {
+  color: Vec4,
+  alpha_cutoff: f32,
+  _padding: [f32; 3],
+}
+
+struct ShadowLayer {
+  view: TextureView,
+  light: (Buffer, BindGroup),
+  source: Option<(ShadowSource, Shadow)>,
+}
+
+/// Depth maps of the shadow casting lights, stored in the layers of a single texture array.
+pub(crate) struct ShadowMaps {
+  pipeline: RenderPipeline,
+  /// Discards the fragments of `AlphaMode::Mask` subjects below their cutoff.
+  masked_pipeline: RenderPipeline,
+  mask_layout: BindGroupLayout,
+  /// White texel sampled by masked subjects without a diffuse map.
+  blank_texture: BindGroup,
+  texture: Texture,
+  pub(crate) view: TextureView,
+  pub(crate) sampler: Sampler,
+  /// `array<ShadowCaster, 8>` read by the lit materials.
+  pub(crate) casters: Buffer,
+  layers: Vec<ShadowLayer>,
+  light_layout: BindGroupLayout,
+}
+
+/// Layer owned by the directional light at `index`, if it can cast shadows.
+pub(crate) fn directional_layer(index: usize) -> Option<usize> {
+  (index < MAX_DIRECTIONAL_SHADOWS).then_some(index)
+}
+
+/// Layer owned by the spot light at `index`, if it can cast shadows.
+pub(crate) fn spot_layer(index: usize) -> Option<usize> {
+  (index < MAX_SPOT_SHADOWS).then_some(MAX_DIRECTIONAL_SHADOWS + index)
+}
+
+fn uniform_layout(renderer: &Renderer, label: &str, visibility: ShaderStages) -> BindGroupLayout {
+  renderer
+    .device()
+    .create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some(label),
+      entries: &[BindGroupLayoutEntry {
+        binding: 0,
+        visibility,
+        ty: BindingType::Buffer {
+          ty: BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
+      }],
+    })
+}
+
+/// Picks an up vector that isn't parallel to `direction`.
+fn up_for(direction: Vec3) -> Vec3 {
+  if direction.cross(Vec3::Y).length_squared() < 1e-6 {
+    Vec3::Z
+  } else {
+    Vec3::Y
+  }
+}
+
+impl ShadowSource {
+  /// View-projection of the light, fitted around `bounds`.
+  fn matrix(&self, bounds: Aabb) -> Mat4 {
+    let sphere = bounds.bounding_sphere();
+    let (center, radius) = (*sphere.center(), sphere.radius().max(0.01));
+
+    match *self {
+      ShadowSource::Directional { direction } => {
+        let direction = direction.try_normalize().unwrap_or(Vec3::NEG_Y);
+        let eye = center - direction * radius * 2.0;
+
+        Mat4::orthographic_rh(-radius, radius, -radius, radius, radius, radius * 3.0)
+          * Mat4::look_at_rh(eye, center, up_for(direction))
+      }
+      ShadowSource::Spot {
+        position,
+        direction,
+        outer_cone_angle,
+      } => {
+        let direction = direction.try_normalize().unwrap_or(Vec3::NEG_Y);
+        let fov = (2.0 * outer_cone_angle).clamp(0.1, 3.0);
+        let far = (position.distance(center) + radius).max(0.1);
+
+        Mat4::perspective_rh(fov, 1.0, far * 0.001, far)
+          * Mat4::look_at_rh(position, position + direction, up_for(direction))
+      }
+    }
+  }
+}
+
+impl ShadowMaps {
+  pub(crate) fn new(renderer: &Renderer) -> Self {
+    let light_layout = uniform_layout(renderer, "shadow light layout", ShaderStages::VERTEX);
+    let transform_layout = uniform_layout(
+      renderer,
+      "shadow transform layout",
+      ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+    );
+
+    let mask_layout = uniform_layout(renderer, "shadow mask layout", ShaderStages::FRAGMENT);
+    // same entries as the diffuse maps of the scene
+    let texture_layout = renderer
+      .device()
+      .create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("shadow texture layout"),
+        entries: &[
+          BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+              multisampled: false,
+              view_dimension: TextureViewDimension::D2,
+              sample_type: TextureSampleType::Float { filterable: true },
+            },
+            count: None,
+          },
+          BindGroupLayoutEntry {
+            binding: 1,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Sampler(SamplerBindingType::Filtering),
+            count: None,
+          },
+        ],
+      });
+
+    let shader = renderer
+      .device()
+      .create_shader_module(include_wgsl!("shaders/shadow.wgsl"));
+
+    let pipeline = Self::create_pipeline(
+      renderer,
+      &shader,
+      &[&light_layout, &transform_layout],
+      false,
+    );
+    let masked_pipeline = Self::create_pipeline(
+      renderer,
+      &shader,
+      &[
+        &light_layout,
+        &transform_layout,
+        &mask_layout,
+        &texture_layout,
+      ],
+      true,
+    );
+
+    let casters = renderer.device().create_buffer_init(&BufferInitDescriptor {
+      label: Some("shadow casters buffer"),
+      contents: bytemuck::cast_slice(&[ShadowCasterBuffer::default(); LAYER_COUNT]),
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+    });
+
+    let sampler = renderer.device().create_sampler(&SamplerDescriptor {
+      label: Some("shadow sampler"),
+      address_mode_u: AddressMode::ClampToEdge,
+      address_mode_v: AddressMode::ClampToEdge,
+      address_mode_w: AddressMode::ClampToEdge,
+      mag_filter: FilterMode::Linear,
+      min_filter: FilterMode::Linear,
+      mipmap_filter: FilterMode::Nearest,
+      compare: Some(CompareFunction::LessEqual),
+      ..Default::default()
+    });
+
+    let blank_texture = renderer.device().create_texture_with_data(
+      renderer.queue(),
+      &TextureDescriptor {
+        label: Some("shadow blank texture"),
+        size: Extent3d {
+          width: 1,
+          height: 1,
+          depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::Rgba8Unorm,
+        usage: TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+      },
+      TextureDataOrder::LayerMajor,
+      &[255; 4],
+    );
+
+    let blank_texture = renderer.device().create_bind_group(&BindGroupDescriptor {
+      label: Some("shadow blank texture bind group"),
+      layout: &texture_layout,
+      entries: &[
+        BindGroupEntry {
+          binding: 0,
+          resource: BindingResource::TextureView(&blank_texture.create_view(&Default::default())),
+        },
+        BindGroupEntry {
+          binding: 1,
+          resource: BindingResource::Sampler(
+            &renderer
+              .device()
+              .create_sampler(&SamplerDescriptor::default()),
+          ),
+        },
+      ],
+    });
+
+    // nothing casts shadows yet, the maps grow once a light does
+    let texture = Self::create_texture(renderer, 1);
+
+    let mut shadow_maps = Self {
+      pipeline,
+      masked_pipeline,
+      mask_layout,
+      blank_texture,
+      view: Self::create_view(&texture),
+      texture,
+      sampler,
+      casters,
+      layers: vec![],
+      light_layout,
+    };
+
+    shadow_maps.layers = (0..LAYER_COUNT)
+      .map(|layer| shadow_maps.create_layer(renderer, layer))
+      .collect();
+
+    shadow_maps
+  }
+
+  fn create_pipeline(
+    renderer: &Renderer,
+    shader: &ShaderModule,
+    bind_group_layouts: &[&BindGroupLayout],
+    masked: bool,
+  ) -> RenderPipeline {
+    let pipeline_layout = renderer
+      .device()
+      .create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("shadow pipeline layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+      });
+
+    renderer
+      .device()
+      .create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("shadow pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: VertexState {
+          module: shader,
+          entry_point: if masked { "vs_masked" } else { "vs_main" },
+          buffers: &[Geometry::vertex_desc()],
+          compilation_options: Default::default(),
+        },
+        primitive: PrimitiveState {
+          topology: PrimitiveTopology::TriangleList,
+          strip_index_format: None,
+          front_face: FrontFace::Ccw,
+          // thin and double sided geometry has to cast shadows too
+          cull_mode: None,
+          polygon_mode: PolygonMode::Fill,
+          unclipped_depth: false,
+          conservative: false,
+        },
+        depth_stencil: Some(DepthStencilState {
+          format: TextureFormat::Depth32Float,
+          depth_write_enabled: true,
+          depth_compare: CompareFunction::Less,
+          stencil: StencilState::default(),
+          bias: DepthBiasState {
+            constant: 2,
+            slope_scale: 2.0,
+            clamp: 0.0,
+          },
+        }),
+        multisample: MultisampleState::default(),
+        // depth only, the masked subjects still need a stage to discard in
+        fragment: masked.then_some(FragmentState {
+          module: shader,
+          entry_point: "fs_masked",
+          compilation_options: Default::default(),
+          targets: &[],
+        }),
+        multiview: None,
+        cache: None,
+      })
+  }
+
+  /// Uniform of the masked pipeline, `None` unless `material` uses `AlphaMode::Mask`.
+  pub(crate) fn create_mask(
+    &self,
+    renderer: &Renderer,
+    material: &Material,
+  ) -> Option<(Buffer, BindGroup)> {
+    let buffer = renderer.device().create_buffer_init(&BufferInitDescriptor {
+      label: Some("shadow mask buffer"),
+      contents: bytemuck::cast_slice(&[Self::mask_contents(material)?]),
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+    });
+
+    let bind_group = renderer.device().create_bind_group(&BindGroupDescriptor {
+      label: Some("shadow mask bind group"),
+      layout: &self.mask_layout,
+      entries: &[BindGroupEntry {
+        binding: 0,
+        resource: buffer.as_entire_binding(),
+      }],
+    });
+
+    Some((buffer, bind_group))
+  }
+
+  /// Rewrites a buffer made by `create_mask` from the current color and cutoff of `material`.
+  pub(crate) fn write_mask(renderer: &Renderer, buffer: &Buffer, material: &Material) {
+    if let Some(contents) = Self::mask_contents(material) {
+      renderer
+        .queue()
+        .write_buffer(buffer, 0, bytemuck::cast_slice(&[contents]));
+    }
+  }
+
+  fn mask_contents(material: &Material) -> Option<ShadowMaskBuffer> {
+    let AlphaMode::Mask(alpha_cutoff) = *material.alpha_mode() else {
+      return None;
+    };
+
+    Some(ShadowMaskBuffer {
+      color: material.mask_color,
+      alpha_cutoff,
+      _padding: [0.0; 3],
+    })
+  }
+
+  fn create_texture(renderer: &Renderer, size: u32) -> Texture {
+    renderer.device().create_texture(&TextureDescriptor {
+      label: Some("shadow maps"),
+      size: Extent3d {
+        width: size,
+        height: size,
+        depth_or_array_layers: LAYER_COUNT as u32,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: TextureDimension::D2,
+      format: TextureFormat::Depth32Float,
+      usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    })
+  }
+
+  fn create_view(texture: &Texture) -> TextureView {
+    texture.create_view(&TextureViewDescriptor {
+      label: Some("shadow maps view"),
+      dimension: Some(TextureViewDimension::D2Array),
+      ..Default::default()
+    })
+  }
+
+  fn create_layer(&self, renderer: &Renderer, layer: usize) -> ShadowLayer {
+    let buffer = renderer.device().create_buffer_init(&BufferInitDescriptor {
+      label: Some("shadow light buffer"),
+      contents: bytemuck::cast_slice(&[Mat4::IDENTITY]),
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+    });
+
+    let bind_group = renderer.device().create_bind_group(&BindGroupDescriptor {
+      label: Some("shadow light bind group"),
+      layout: &self.light_layout,
+      entries: &[BindGroupEntry {
+        binding: 0,
+        resource: buffer.as_entire_binding(),
+      }],
+    });
+
+    ShadowLayer {
+      view: self.layer_view(layer),
+      light: (buffer, bind_group),
+      source: None,
+    }
+  }
+
+  fn layer_view(&self, layer: usize) -> TextureView {
+    self.texture.create_view(&TextureViewDescriptor {
+      label: Some("shadow map layer view"),
+      dimension: Some(TextureViewDimension::D2),
+      base_array_layer: layer as u32,
+      array_layer_count: Some(1),
+      ..Default::default()
+    })
+  }
+
+  pub(crate) fn texture(&self) -> &Texture {
+    &self.texture
+  }
+
+  /// Sets the light rendered into `layer`, returns `true` when the maps were reallocated and the
+  /// bind groups reading them have to be recreated.
+  pub(crate) fn set_source(
+    &mut self,
+    renderer: &Renderer,
+    layer: usize,
+    source: Option<(ShadowSource, Shadow)>,
+  ) -> bool {
+    let Some(shadow_layer) = self.layers.get_mut(layer) else {
+      return false;
+    };
+
+    shadow_layer.source = source;
+
+    if source.is_none() || self.texture.width() == SHADOW_MAP_SIZE {
+      return false;
+    }
+
+    self.texture = Self::create_texture(renderer, SHADOW_MAP_SIZE);
+    self.view = Self::create_view(&self.texture);
+
+    for layer in 0..LAYER_COUNT {
+      self.layers[layer].view = self.layer_view(layer);
+    }
+
+    true
+  }
+
+  /// Clears the light of every layer in `layers`.
+  pub(crate) fn clear_sources(&mut self, layers: std::ops::Range<usize>) {
+    for layer in &mut self.layers[layers] {
+      layer.source = None;
+    }
+  }
+
+  pub(crate) fn directional_layers() -> std::ops::Range<usize> {
+    0..MAX_DIRECTIONAL_SHADOWS
+  }
+
+  pub(crate) fn spot_layers() -> std::ops::Range<usize> {
+    MAX_DIRECTIONAL_SHADOWS..LAYER_COUNT
+  }
+
+  /// Fits the light of each layer around `bounds` and renders the casting subjects into it.
+  pub(crate) fn encode<'a>(
+    &self,
+    renderer: &Renderer,
+    encoder: &mut CommandEncoder,
+    bounds: Option<Aabb>,
+    subjects: impl Iterator<Item = &'a Subject> + Clone,
+    textures: &IndexMap<TextureId, (Texture, TextureView, BindGroup)>,
+    stats: &mut RenderStats,
+  ) {
+    let Some(bounds) = bounds else {
+      return;
+    };
+
+    let casters = self
+      .layers
+      .iter()
+      .map(|layer| {
+        layer
+          .source
+          .map(|(source, shadow)| ShadowCasterBuffer {
+            view_projection: source.matrix(bounds),
+            bias: *shadow.bias(),
+            normal_bias: *shadow.normal_bias(),
+            radius: *shadow.radius(),
+            _padding: 0.0,
+          })
+          .unwrap_or_default()
+      })
+      .collect::<Vec<_>>();
+
+    renderer
+      .queue()
+      .write_buffer(&self.casters, 0, bytemuck::cast_slice(&casters));
+
+    for (layer, caster) in self.layers.iter().zip(&casters) {
+      if layer.source.is_none() {
+        continue;
+      }
+
+      renderer.queue().write_buffer(
+        &layer.light.0,
+        0,
+        bytemuck::cast_slice(&[caster.view_projection]),
+      );
+
+      let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some("shadow render pass"),
+        color_attachments: &[],
+        depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+          view: &layer.view,
+          depth_ops: Some(Operations {
+            load: LoadOp::Clear(1.0),
+            store: StoreOp::Store,
+          }),
+          stencil_ops: None,
+        }),
+        timestamp_writes: None,
+        occlusion_query_set: None,
+      });
+
+      let casting = subjects
+        .clone()
+        .filter(|subject| subject.cast_shadow && subject.pipeline.is_some());
+
+      render_pass.set_pipeline(&self.pipeline);
+      render_pass.set_bind_group(0, &layer.light.1, &[]);
+      stats.record_pipeline_switch();
+
+      for subject in casting
+        .clone()
+        .filter(|subject| subject.shadow_mask.is_none())
+      {
+        Self::draw_subject(&mut render_pass, subject, stats);
+      }
+
+      let mut masked = casting
+        .filter_map(|subject| Some((subject, &subject.shadow_mask.as_ref()?.1)))
+        .peekable();
+
+      if masked.peek().is_none() {
+        continue;
+      }
+
+      render_pass.set_pipeline(&self.masked_pipeline);
+      render_pass.set_bind_group(0, &layer.light.1, &[]);
+      stats.record_pipeline_switch();
+
+      for (subject, mask) in masked {
+        let diffuse_map = subject
+          .diffuse_texture_id
+          .as_ref()
+          .and_then(|id| textures.get(id))
+          .map_or(&self.blank_texture, |(_, _, bind_group)| bind_group);
+
+        render_pass.set_bind_group(2, mask, &[]);
+        render_pass.set_bind_group(3, diffuse_map, &[]);
+        Self::draw_subject(&mut render_pass, subject, stats);
+      }
+    }
+  }
+
+  fn draw_subject(render_pass: &mut RenderPass, subject: &Subject, stats: &mut RenderStats) {
+    let (vertices, _) = &subject.vertices;
+    let (indices, index_count) = &subject.indices;
+
+    render_pass.set_bind_group(1, &subject.transform.1, &[]);
+    render_pass.set_vertex_buffer(0, vertices.slice(..));
+    render_pass.set_index_buffer(indices.slice(..), IndexFormat::Uint32);
+    render_pass.draw_indexed(0..*index_count as u32, 0, 0..1);
+    stats.record_draw(*index_count);
+  }
+}
@@ -0,0 +1,598 @@
+use glam::Mat4;
+use wgpu::{
+  include_wgsl,
+  util::{BufferInitDescriptor, DeviceExt},
+  AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+  BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+  BufferBindingType, BufferUsages, Color, ColorTargetState, ColorWrites, CompareFunction,
+  DepthStencilState, Extent3d, FilterMode, FragmentState, ImageCopyTexture, ImageDataLayout,
+  LoadOp, MultisampleState, Operations, Origin3d, PipelineLayoutDescriptor, PrimitiveState,
+  RenderPass, RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline,
+  RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages,
+  StencilState, StoreOp, Texture, TextureAspect, TextureDescriptor, TextureDimension,
+  TextureFormat, TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor,
+  TextureViewDimension, VertexState,
+};
+
+use crate::{
+  renderer::Renderer,
+  resource::{
+    environment::{Environment, EnvironmentError, EnvironmentSource},
+    loader::textures::TextureLoader,
+    texture::{TexelFormat, Texture as SourceTexture, TextureId},
+  },
+  stats::RenderStats,
+};
+
+/// Cubemap of the environment and the pipeline drawing it behind the subjects.
+pub(crate) struct Skybox {
+  pipeline: RenderPipeline,
+  uniform: Buffer,
+  texture: Texture,
+  /// The whole mip chain, also sampled when baking the image based lighting.
+  pub(crate) view: TextureView,
+  bind_group: BindGroup,
+  background: bool,
+}
+
+fn texture_layout_entry(
+  binding: u32,
+  view_dimension: TextureViewDimension,
+) -> BindGroupLayoutEntry {
+  BindGroupLayoutEntry {
+    binding,
+    visibility: ShaderStages::FRAGMENT,
+    ty: BindingType::Texture {
+      multisampled: false,
+      view_dimension,
+      sample_type: TextureSampleType::Float { filterable: true },
+    },
+    count: None,
+  }
+}
+
+fn sampler_layout_entry(binding: u32) -> BindGroupLayoutEntry {
+  BindGroupLayoutEntry {
+    binding,
+    visibility: ShaderStages::FRAGMENT,
+    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+    count: None,
+  }
+}
+
+pub(crate) fn linear_sampler(
+  renderer: &Renderer,
+  label: &str,
+  address_mode_u: AddressMode,
+) -> Sampler {
+  renderer.device().create_sampler(&SamplerDescriptor {
+    label: Some(label),
+    address_mode_u,
+    address_mode_v: AddressMode::ClampToEdge,
+    address_mode_w: AddressMode::ClampToEdge,
+    mag_filter: FilterMode::Linear,
+    min_filter: FilterMode::Linear,
+    mipmap_filter: FilterMode::Linear,
+    ..Default::default()
+  })
+}
+
+/// Number of levels down to a single texel.
+pub(crate) fn mip_level_count(size: u32) -> u32 {
+  size.max(1).ilog2() + 1
+}
+
+/// View of a single level of a single face to render into.
+pub(crate) fn face_view(texture: &Texture, face: u32, level: u32) -> TextureView {
+  texture.create_view(&TextureViewDescriptor {
+    label: Some("cubemap face view"),
+    dimension: Some(TextureViewDimension::D2),
+    base_mip_level: level,
+    mip_level_count: Some(1),
+    base_array_layer: face,
+    array_layer_count: Some(1),
+    ..Default::default()
+  })
+}
+
+fn source_texture(
+  texture_loader: &TextureLoader,
+  id: TextureId,
+) -> Result<&SourceTexture, EnvironmentError> {
+  texture_loader
+    .get_from_id(id)
+    .ok_or(EnvironmentError::MissingTexture(id))
+}
+
+/// Fails for formats the adapter can't render into, as the mipmaps are rendered.
+fn create_cube_texture(
+  renderer: &Renderer,
+  size: u32,
+  format: TexelFormat,
+) -> Result<Texture, EnvironmentError> {
+  if !renderer.renderable(format.wgpu_format()) {
+    return Err(EnvironmentError::UnsupportedFormat(format));
+  }
+
+  Ok(renderer.device().create_texture(&TextureDescriptor {
+    label: Some("environment cubemap"),
+    size: Extent3d {
+      width: size,
+      height: size,
+      depth_or_array_layers: 6,
+    },
+    mip_level_count: mip_level_count(size),
+    sample_count: 1,
+    dimension: TextureDimension::D2,
+    format: format.wgpu_format(),
+    usage: TextureUsages::TEXTURE_BINDING
+      | TextureUsages::COPY_DST
+      | TextureUsages::RENDER_ATTACHMENT,
+    view_formats: &[],
+  }))
+}
+
+fn write_layer(renderer: &Renderer, texture: &Texture, layer: u32, source: &SourceTexture) {
+  let (width, height) = source.dimensions;
+
+  renderer.queue().write_texture(
+    ImageCopyTexture {
+      aspect: TextureAspect::All,
+      texture,
+      mip_level: 0,
+      origin: Origin3d {
+        x: 0,
+        y: 0,
+        z: layer,
+      },
+    },
+    &source.content,
+    ImageDataLayout {
+      offset: 0,
+      bytes_per_row: Some(source.format.bytes_per_texel() * width),
+      rows_per_image: Some(height),
+    },
+    Extent3d {
+      width,
+      height,
+      depth_or_array_layers: 1,
+    },
+  );
+}
+
+/// Uploads six square faces of the same size.
+fn cube_from_faces(
+  renderer: &Renderer,
+  texture_loader: &TextureLoader,
+  ids: &[TextureId; 6],
+) -> Result<Texture, EnvironmentError> {
+  let faces = ids
+    .iter()
+    .map(|id| source_texture(texture_loader, *id))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  let (size, _) = faces[0].dimensions;
+
+  if let Some(face) = faces.iter().find(|face| face.dimensions != (size, size)) {
+    return Err(EnvironmentError::FaceSize {
+      expected: (size, size),
+      actual: face.dimensions,
+    });
+  }
+
+  let format = faces[0].format;
+
+  if let Some(face) = faces.iter().find(|face| face.format != format) {
+    return Err(EnvironmentError::FaceFormat {
+      expected: format,
+      actual: face.format,
+    });
+  }
+
+  let texture = create_cube_texture(renderer, size.max(1), format)?;
+
+  for (layer, face) in faces.into_iter().enumerate() {
+    write_layer(renderer, &texture, layer as u32, face);
+  }
+
+  Ok(texture)
+}
+
+/// Renders the panorama into the six faces of a cubemap a quarter as wide as it.
+fn cube_from_panorama(
+  renderer: &Renderer,
+  texture_loader: &TextureLoader,
+  id: TextureId,
+) -> Result<Texture, EnvironmentError> {
+  let source = source_texture(texture_loader, id)?;
+  let (width, height) = source.dimensions;
+
+  let size = (width / 4).clamp(1, renderer.device().limits().max_texture_dimension_2d);
+  let texture = create_cube_texture(renderer, size, source.format)?;
+
+  let panorama = renderer.device().create_texture(&TextureDescriptor {
+    label: Some("environment panorama"),
+    size: Extent3d {
+      width: width.max(1),
+      height: height.max(1),
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: TextureDimension::D2,
+    format: source.format.wgpu_format(),
+    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+    view_formats: &[],
+  });
+  write_layer(renderer, &panorama, 0, source);
+
+  let layout = renderer
+    .device()
+    .create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some("environment panorama layout"),
+      entries: &[
+        texture_layout_entry(0, TextureViewDimension::D2),
+        sampler_layout_entry(1),
+      ],
+    });
+
+  let bind_group = renderer.device().create_bind_group(&BindGroupDescriptor {
+    label: Some("environment panorama bind group"),
+    layout: &layout,
+    entries: &[
+      BindGroupEntry {
+        binding: 0,
+        resource: BindingResource::TextureView(&panorama.create_view(&Default::default())),
+      },
+      BindGroupEntry {
+        binding: 1,
+        resource: BindingResource::Sampler(&linear_sampler(
+          renderer,
+          "environment panorama sampler",
+          AddressMode::Repeat,
+        )),
+      },
+    ],
+  });
+
+  let shader = renderer
+    .device()
+    .create_shader_module(include_wgsl!("shaders/equirect_to_cube.wgsl"));
+
+  let pipeline = renderer
+    .device()
+    .create_render_pipeline(&RenderPipelineDescriptor {
+      label: Some("equirect to cube pipeline"),
+      layout: Some(
+        &renderer
+          .device()
+          .create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("equirect to cube pipeline layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+          }),
+      ),
+      vertex: VertexState {
+        module: &shader,
+        entry_point: "vs_main",
+        buffers: &[],
+        compilation_options: Default::default(),
+      },
+      primitive: PrimitiveState::default(),
+      depth_stencil: None,
+      multisample: MultisampleState::default(),
+      fragment: Some(FragmentState {
+        module: &shader,
+        entry_point: "fs_main",
+        targets: &[Some(ColorTargetState {
+          format: source.format.wgpu_format(),
+          blend: None,
+          write_mask: ColorWrites::ALL,
+        })],
+        compilation_options: Default::default(),
+      }),
+      multiview: None,
+      cache: None,
+    });
+
+  let mut encoder = renderer
+    .device()
+    .create_command_encoder(&Default::default());
+
+  for face in 0..6 {
+    let view = face_view(&texture, face, 0);
+
+    let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+      label: Some("equirect to cube pass"),
+      color_attachments: &[Some(RenderPassColorAttachment {
+        view: &view,
+        resolve_target: None,
+        ops: Operations {
+          load: LoadOp::Clear(Color::BLACK),
+          store: StoreOp::Store,
+        },
+      })],
+      ..Default::default()
+    });
+
+    render_pass.set_pipeline(&pipeline);
+    render_pass.set_bind_group(0, &bind_group, &[]);
+    // the face is picked from the vertex index
+    render_pass.draw(face * 3..face * 3 + 3, 0..1);
+  }
+
+  renderer.queue().submit([encoder.finish()]);
+
+  Ok(texture)
+}
+
+/// Fills the levels below the first of every face by halving the level above.
+fn generate_mipmaps(renderer: &Renderer, texture: &Texture) {
+  let layout = renderer
+    .device()
+    .create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some("downsample layout"),
+      entries: &[
+        texture_layout_entry(0, TextureViewDimension::Cube),
+        sampler_layout_entry(1),
+      ],
+    });
+
+  let shader = renderer
+    .device()
+    .create_shader_module(include_wgsl!("shaders/downsample.wgsl"));
+
+  let pipeline = renderer
+    .device()
+    .create_render_pipeline(&RenderPipelineDescriptor {
+      label: Some("downsample pipeline"),
+      layout: Some(
+        &renderer
+          .device()
+          .create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("downsample pipeline layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+          }),
+      ),
+      vertex: VertexState {
+        module: &shader,
+        entry_point: "vs_main",
+        buffers: &[],
+        compilation_options: Default::default(),
+      },
+      primitive: PrimitiveState::default(),
+      depth_stencil: None,
+      multisample: MultisampleState::default(),
+      fragment: Some(FragmentState {
+        module: &shader,
+        entry_point: "fs_main",
+        targets: &[Some(ColorTargetState {
+          format: texture.format(),
+          blend: None,
+          write_mask: ColorWrites::ALL,
+        })],
+        compilation_options: Default::default(),
+      }),
+      multiview: None,
+      cache: None,
+    });
+
+  let sampler = linear_sampler(renderer, "downsample sampler", AddressMode::ClampToEdge);
+
+  let mut encoder = renderer
+    .device()
+    .create_command_encoder(&Default::default());
+
+  for level in 1..texture.mip_level_count() {
+    // only the level above is visible to the shader while this one is rendered
+    let source = texture.create_view(&TextureViewDescriptor {
+      label: Some("downsample source view"),
+      dimension: Some(TextureViewDimension::Cube),
+      base_mip_level: level - 1,
+      mip_level_count: Some(1),
+      ..Default::default()
+    });
+
+    let bind_group = renderer.device().create_bind_group(&BindGroupDescriptor {
+      label: Some("downsample bind group"),
+      layout: &layout,
+      entries: &[
+        BindGroupEntry {
+          binding: 0,
+          resource: BindingResource::TextureView(&source),
+        },
+        BindGroupEntry {
+          binding: 1,
+          resource: BindingResource::Sampler(&sampler),
+        },
+      ],
+    });
+
+    for face in 0..6 {
+      let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some("downsample pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+          view: &face_view(texture, face, level),
+          resolve_target: None,
+          ops: Operations {
+            load: LoadOp::Clear(Color::BLACK),
+            store: StoreOp::Store,
+          },
+        })],
+        ..Default::default()
+      });
+
+      render_pass.set_pipeline(&pipeline);
+      render_pass.set_bind_group(0, &bind_group, &[]);
+      // the face is picked from the vertex index
+      render_pass.draw(face * 3..face * 3 + 3, 0..1);
+    }
+  }
+
+  renderer.queue().submit([encoder.finish()]);
+}
+
+impl Skybox {
+  pub(crate) fn new(
+    renderer: &Renderer,
+    texture_loader: &TextureLoader,
+    environment: &Environment,
+  ) -> Result<Self, EnvironmentError> {
+    let texture = match environment.source() {
+      EnvironmentSource::Cubemap(ids) => cube_from_faces(renderer, texture_loader, ids)?,
+      EnvironmentSource::Equirectangular(id) => cube_from_panorama(renderer, texture_loader, *id)?,
+    };
+    generate_mipmaps(renderer, &texture);
+
+    let view = texture.create_view(&TextureViewDescriptor {
+      label: Some("environment cubemap view"),
+      dimension: Some(TextureViewDimension::Cube),
+      ..Default::default()
+    });
+    let sampler = linear_sampler(renderer, "environment sampler", AddressMode::ClampToEdge);
+
+    let uniform = renderer.device().create_buffer_init(&BufferInitDescriptor {
+      label: Some("skybox buffer"),
+      contents: bytemuck::cast_slice(&[Mat4::IDENTITY]),
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+    });
+
+    let layout = Self::layout(renderer);
+
+    let bind_group = renderer.device().create_bind_group(&BindGroupDescriptor {
+      label: Some("skybox bind group"),
+      layout: &layout,
+      entries: &[
+        BindGroupEntry {
+          binding: 0,
+          resource: uniform.as_entire_binding(),
+        },
+        BindGroupEntry {
+          binding: 1,
+          resource: BindingResource::TextureView(&view),
+        },
+        BindGroupEntry {
+          binding: 2,
+          resource: BindingResource::Sampler(&sampler),
+        },
+      ],
+    });
+
+    Ok(Self {
+      pipeline: Self::create_pipeline(renderer, &layout),
+      uniform,
+      texture,
+      view,
+      bind_group,
+      background: *environment.background(),
+    })
+  }
+
+  fn layout(renderer: &Renderer) -> BindGroupLayout {
+    renderer
+      .device()
+      .create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("skybox layout"),
+        entries: &[
+          BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+              ty: BufferBindingType::Uniform,
+              has_dynamic_offset: false,
+              min_binding_size: None,
+            },
+            count: None,
+          },
+          texture_layout_entry(1, TextureViewDimension::Cube),
+          sampler_layout_entry(2),
+        ],
+      })
+  }
+
+  fn create_pipeline(renderer: &Renderer, layout: &BindGroupLayout) -> RenderPipeline {
+    let shader = renderer
+      .device()
+      .create_shader_module(include_wgsl!("shaders/skybox.wgsl"));
+
+    let target = [Some(ColorTargetState {
+      format: *renderer.hdr_format(),
+      blend: None,
+      write_mask: ColorWrites::ALL,
+    })];
+
+    renderer
+      .device()
+      .create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("skybox pipeline"),
+        layout: Some(
+          &renderer
+            .device()
+            .create_pipeline_layout(&PipelineLayoutDescriptor {
+              label: Some("skybox pipeline layout"),
+              bind_group_layouts: &[layout],
+              push_constant_ranges: &[],
+            }),
+        ),
+        vertex: VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[],
+          compilation_options: Default::default(),
+        },
+        primitive: PrimitiveState::default(),
+        // drawn first, every subject covers it
+        depth_stencil: Some(DepthStencilState {
+          format: TextureFormat::Depth32Float,
+          depth_write_enabled: false,
+          depth_compare: CompareFunction::Always,
+          stencil: StencilState::default(),
+          bias: Default::default(),
+        }),
+        multisample: MultisampleState {
+          count: *renderer.sample_count(),
+          ..Default::default()
+        },
+        fragment: Some(FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &target,
+          compilation_options: Default::default(),
+        }),
+        multiview: None,
+        cache: None,
+      })
+  }
+
+  pub(crate) fn texture(&self) -> &Texture {
+    &self.texture
+  }
+
+  /// Uploads the camera the background is seen from, before the render pass drawing it.
+  pub(crate) fn prepare(&self, renderer: &Renderer, view_projection: Mat4) {
+    if !self.background {
+      return;
+    }
+
+    renderer.queue().write_buffer(
+      &self.uniform,
+      0,
+      bytemuck::cast_slice(&[view_projection.inverse()]),
+    );
+  }
+
+  pub(crate) fn draw<'a>(&'a self, render_pass: &mut RenderPass<'a>, stats: &mut RenderStats) {
+    if !self.background {
+      return;
+    }
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, &self.bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+
+    stats.record_pipeline_switch();
+    stats.record_bind_group_switch();
+    stats.record_draw(3);
+  }
+}
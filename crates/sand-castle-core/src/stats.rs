@@ -0,0 +1,187 @@
+use std::{
+  ops::{Add, AddAssign},
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+
+use getset::Getters;
+use wgpu::{
+  Buffer, BufferDescriptor, BufferUsages, CommandEncoder, MapMode, QuerySet, QuerySetDescriptor,
+  QueryType, RenderPassTimestampWrites, QUERY_SIZE,
+};
+
+use crate::renderer::Renderer;
+
+/// What drawing a frame cost, returned by `Scene::render`.
+#[derive(Getters, Default, Clone, Copy, Debug, PartialEq)]
+#[getset(get = "pub")]
+pub struct RenderStats {
+  draw_calls: u32,
+  /// Subjects skipped because their bounds are outside of the camera frustum.
+  culled: u32,
+  triangles: u64,
+  pipeline_switches: u32,
+  bind_group_switches: u32,
+  /// Estimated size in bytes of the buffers owned by the scene.
+  buffer_memory: u64,
+  /// Estimated size in bytes of the textures and render targets owned by the scene.
+  texture_memory: u64,
+  /// GPU time of the scene render pass, only measured when the `Renderer` was built with
+  /// `gpu_timing` on an adapter supporting `Features::TIMESTAMP_QUERY`. Lags a few frames behind.
+  gpu_time: Option<Duration>,
+}
+
+impl RenderStats {
+  pub(crate) fn record_draw(&mut self, index_count: usize) {
+    self.draw_calls += 1;
+    self.triangles += index_count as u64 / 3;
+  }
+
+  pub(crate) fn record_cull(&mut self) {
+    self.culled += 1;
+  }
+
+  pub(crate) fn record_pipeline_switch(&mut self) {
+    self.pipeline_switches += 1;
+  }
+
+  pub(crate) fn record_bind_group_switch(&mut self) {
+    self.bind_group_switches += 1;
+  }
+
+  pub(crate) fn set_memory(&mut self, buffer_memory: u64, texture_memory: u64) {
+    self.buffer_memory = buffer_memory;
+    self.texture_memory = texture_memory;
+  }
+
+  pub(crate) fn set_gpu_time(&mut self, gpu_time: Option<Duration>) {
+    self.gpu_time = gpu_time;
+  }
+}
+
+/// Sums the stats of several scenes drawn in the same frame.
+impl Add for RenderStats {
+  type Output = RenderStats;
+
+  fn add(self, other: RenderStats) -> RenderStats {
+    RenderStats {
+      draw_calls: self.draw_calls + other.draw_calls,
+      culled: self.culled + other.culled,
+      triangles: self.triangles + other.triangles,
+      pipeline_switches: self.pipeline_switches + other.pipeline_switches,
+      bind_group_switches: self.bind_group_switches + other.bind_group_switches,
+      buffer_memory: self.buffer_memory + other.buffer_memory,
+      texture_memory: self.texture_memory + other.texture_memory,
+      gpu_time: match (self.gpu_time, other.gpu_time) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+      },
+    }
+  }
+}
+
+impl AddAssign for RenderStats {
+  fn add_assign(&mut self, other: RenderStats) {
+    *self = *self + other;
+  }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TimerState {
+  Idle,
+  Mapping,
+  Mapped,
+}
+
+/// Measures the duration of a render pass with timestamp queries, reading results back without
+/// ever blocking on the GPU.
+pub(crate) struct GpuTimer {
+  query_set: QuerySet,
+  resolve: Buffer,
+  readback: Buffer,
+  state: Arc<Mutex<TimerState>>,
+  last: Mutex<Option<Duration>>,
+  period: f32,
+}
+
+impl GpuTimer {
+  pub(crate) fn new(renderer: &Renderer) -> Self {
+    let size = 2 * QUERY_SIZE as u64;
+
+    Self {
+      query_set: renderer.device().create_query_set(&QuerySetDescriptor {
+        label: Some("gpu timer query set"),
+        ty: QueryType::Timestamp,
+        count: 2,
+      }),
+      resolve: renderer.device().create_buffer(&BufferDescriptor {
+        label: Some("gpu timer resolve buffer"),
+        size,
+        usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+      }),
+      readback: renderer.device().create_buffer(&BufferDescriptor {
+        label: Some("gpu timer readback buffer"),
+        size,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+      }),
+      state: Arc::new(Mutex::new(TimerState::Idle)),
+      last: Mutex::new(None),
+      period: renderer.queue().get_timestamp_period(),
+    }
+  }
+
+  /// Collects a finished measurement, returns whether the timer is free to measure this frame.
+  pub(crate) fn begin_frame(&self) -> bool {
+    let mut state = self.state.lock().unwrap();
+
+    if *state == TimerState::Mapped {
+      let timestamps: [u64; 2] = {
+        let data = self.readback.slice(..).get_mapped_range();
+        bytemuck::pod_read_unaligned(&data)
+      };
+      self.readback.unmap();
+
+      let nanos = timestamps[1].saturating_sub(timestamps[0]) as f64 * self.period as f64;
+      *self.last.lock().unwrap() = Some(Duration::from_nanos(nanos as u64));
+      *state = TimerState::Idle;
+    }
+
+    *state == TimerState::Idle
+  }
+
+  pub(crate) fn timestamp_writes(&self) -> RenderPassTimestampWrites<'_> {
+    RenderPassTimestampWrites {
+      query_set: &self.query_set,
+      beginning_of_pass_write_index: Some(0),
+      end_of_pass_write_index: Some(1),
+    }
+  }
+
+  pub(crate) fn resolve(&self, encoder: &mut CommandEncoder) {
+    encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve, 0);
+    encoder.copy_buffer_to_buffer(&self.resolve, 0, &self.readback, 0, self.resolve.size());
+  }
+
+  /// Must be called after the command buffer holding `resolve` has been submitted.
+  pub(crate) fn map(&self) {
+    *self.state.lock().unwrap() = TimerState::Mapping;
+
+    let state = self.state.clone();
+    self
+      .readback
+      .slice(..)
+      .map_async(MapMode::Read, move |result| {
+        *state.lock().unwrap() = if result.is_ok() {
+          TimerState::Mapped
+        } else {
+          TimerState::Idle
+        };
+      });
+  }
+
+  pub(crate) fn last(&self) -> Option<Duration> {
+    *self.last.lock().unwrap()
+  }
+}
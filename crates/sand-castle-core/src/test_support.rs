@@ -0,0 +1,345 @@
+//! Helpers for rendering scenes headlessly and comparing them against reference images.
+//!
+//! References are PNG files stored in a directory of the caller's choosing. Running with
+//! `SAND_CASTLE_UPDATE_GOLDEN=1` (re)writes them from the current output, a mismatch writes the
+//! actual frame and a diff image into a `failures` directory next to the references.
+
+use std::{fs, path::Path};
+
+use glam::{Quat, Vec3, Vec4};
+
+use crate::{
+  capture::FrameCapture,
+  light_storage::LightArrayError,
+  post_processing::PostEffect,
+  renderer::{Backend, Offscreen, Renderer, RendererBuilder},
+  resource::{
+    camera::perspective::PerspectiveCamera,
+    environment::Environment,
+    geometry::{cuboid::Cuboid, ToGeometry},
+    lighting::{
+      light::{
+        ambient_light::AmbientLight, directional_light::DirectionalLight,
+        hemisphere_light::HemisphereLight, point_light::PointLight, rect_area_light::RectAreaLight,
+        spot_light::SpotLight,
+      },
+      material::{basic::BasicMaterial, ToMaterial},
+    },
+    loader::{geometry::GeometryLoader, material::MaterialLoader, textures::TextureLoader},
+    object_3d::mesh::Mesh,
+    texture::{Texture, TextureId},
+  },
+  scene::Scene,
+};
+
+pub const UPDATE_GOLDEN_ENV: &str = "SAND_CASTLE_UPDATE_GOLDEN";
+
+/// Builds a headless `Renderer`, preferring the GL backend (llvmpipe on machines without a GPU)
+/// and falling back to the primary native backends.
+pub fn headless_renderer(width: u32, height: u32) -> Renderer {
+  headless_renderer_with(width, height, |builder| builder)
+}
+
+/// Like `headless_renderer`, with `configure` applied to the builder of every backend attempted.
+pub fn headless_renderer_with(
+  width: u32,
+  height: u32,
+  configure: impl Fn(RendererBuilder) -> RendererBuilder,
+) -> Renderer {
+  [Backend::WebGL, Backend::WebGPU]
+    .into_iter()
+    .find_map(|backend| {
+      pollster::block_on(
+        configure(Renderer::builder())
+          .backend(backend)
+          .offscreen(Offscreen::new(width, height))
+          .build(),
+      )
+      .ok()
+    })
+    .expect("no adapter available for a headless `Renderer`")
+}
+
+/// A headless scene together with the loaders needed to populate it.
+pub struct GoldenScene {
+  pub renderer: Renderer,
+  pub scene: Scene,
+  pub geometry_loader: GeometryLoader,
+  pub material_loader: MaterialLoader,
+  pub texture_loader: TextureLoader,
+}
+
+impl GoldenScene {
+  pub fn new(width: u32, height: u32) -> Self {
+    Self::with_renderer(headless_renderer(width, height))
+  }
+
+  pub fn with_renderer(renderer: Renderer) -> Self {
+    let scene = Scene::builder(&renderer)
+      .color(Vec4::new(0.1, 0.1, 0.1, 1.0))
+      .build();
+
+    Self {
+      renderer,
+      scene,
+      geometry_loader: GeometryLoader::new(),
+      material_loader: MaterialLoader::new(),
+      texture_loader: TextureLoader::new(),
+    }
+  }
+
+  pub fn look_at(&mut self, position: Vec3, target: Vec3) {
+    let direction = (target - position).normalize();
+    let (width, height) = self.renderer.size();
+
+    let camera = PerspectiveCamera::builder()
+      .yaw(direction.z.atan2(direction.x))
+      .pitch(direction.y.asin())
+      .fov(1.0)
+      .aspect_ratio(width as f32 / height as f32)
+      .position(position)
+      .rotation(Quat::IDENTITY)
+      .scale(Default::default())
+      .build();
+
+    self.scene.set_camera(&self.renderer, &camera);
+  }
+
+  pub fn insert_cuboid(&mut self, material: &impl ToMaterial) -> Mesh {
+    self
+      .try_insert_cuboid(material)
+      .expect("failed to insert the cuboid")
+  }
+
+  pub fn try_insert_cuboid(&mut self, material: &impl ToMaterial) -> Result<Mesh, LightArrayError> {
+    let geometry = Cuboid::builder().build().to_geometry();
+    let geometry_id = *geometry.id();
+    self.geometry_loader.insert(geometry);
+
+    let material = material.to_material();
+    let material_id = *material.id();
+    self.material_loader.insert(material);
+
+    let mesh = Mesh::builder()
+      .geometry_id(geometry_id)
+      .material_id(material_id)
+      .position(Vec3::ZERO)
+      .rotation(Quat::IDENTITY)
+      .scale(Default::default())
+      .build();
+
+    self.scene.insert(
+      &self.renderer,
+      &self.geometry_loader,
+      &self.material_loader,
+      &self.texture_loader,
+      &mesh,
+    )?;
+
+    Ok(mesh)
+  }
+
+  /// Inserts a texture of `dimensions` filled by `pixel(x, y)` into the texture loader.
+  pub fn insert_texture(
+    &mut self,
+    dimensions: (u32, u32),
+    pixel: impl Fn(u32, u32) -> [u8; 4],
+  ) -> TextureId {
+    let content = (0..dimensions.1)
+      .flat_map(|y| (0..dimensions.0).map(move |x| (x, y)))
+      .flat_map(|(x, y)| pixel(x, y))
+      .collect();
+
+    let texture = Texture::from_rgba(dimensions, content);
+    let id = *texture.id();
+    self.texture_loader.insert(texture);
+
+    id
+  }
+
+  /// Inserts an HDR texture of `dimensions` filled by `pixel(x, y)` into the texture loader.
+  pub fn insert_hdr_texture(
+    &mut self,
+    dimensions: (u32, u32),
+    pixel: impl Fn(u32, u32) -> [f32; 4],
+  ) -> TextureId {
+    let pixels = (0..dimensions.1)
+      .flat_map(|y| (0..dimensions.0).map(move |x| (x, y)))
+      .flat_map(|(x, y)| pixel(x, y))
+      .collect::<Vec<_>>();
+
+    let texture = Texture::from_rgba_f32(dimensions, &pixels);
+    let id = *texture.id();
+    self.texture_loader.insert(texture);
+
+    id
+  }
+
+  pub fn environment(&mut self, environment: &Environment) {
+    self
+      .scene
+      .set_environment(&self.renderer, &self.texture_loader, Some(environment))
+      .expect("the environment textures are loaded");
+  }
+
+  pub fn post_processing(&mut self, effects: &[&dyn PostEffect]) {
+    self
+      .scene
+      .set_post_processing(&self.renderer, &self.texture_loader, effects)
+      .expect("the post processing textures are loaded");
+  }
+
+  pub fn ambient_light(&mut self, light: &AmbientLight) {
+    self.scene.update_ambient_light(&self.renderer, light);
+  }
+
+  pub fn hemisphere_light(&mut self, light: &HemisphereLight) {
+    self.scene.update_hemisphere_light(&self.renderer, light);
+  }
+
+  pub fn directional_lights(&mut self, lights: &[DirectionalLight]) {
+    self
+      .scene
+      .bind_directional_lights(&self.renderer, lights)
+      .expect("too many directional lights for the renderer");
+  }
+
+  pub fn point_lights(&mut self, lights: &[PointLight]) {
+    self
+      .scene
+      .bind_point_lights(&self.renderer, lights)
+      .expect("too many point lights for the renderer");
+  }
+
+  pub fn spot_lights(&mut self, lights: &[SpotLight]) {
+    self
+      .scene
+      .bind_spot_lights(&self.renderer, lights)
+      .expect("too many spot lights for the renderer");
+  }
+
+  pub fn rect_area_lights(&mut self, lights: &[RectAreaLight]) {
+    self
+      .scene
+      .bind_rect_area_lights(&self.renderer, lights)
+      .expect("too many rect area lights for the renderer");
+  }
+
+  pub fn render(&mut self) -> FrameCapture {
+    pollster::block_on(self.scene.render_to_image(&self.renderer))
+      .expect("failed to capture the rendered frame")
+  }
+}
+
+/// 96x64 scene looking at the origin, where `GoldenScene::insert_cuboid` puts its cuboids.
+pub fn cuboid_scene() -> GoldenScene {
+  let mut scene = GoldenScene::new(96, 64);
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+  scene
+}
+
+/// Unlit material the cuboids of most tests are drawn with.
+pub fn cuboid_material() -> BasicMaterial {
+  BasicMaterial::with_color(Vec4::new(0.2, 0.6, 0.9, 1.0))
+}
+
+/// RGBA of the pixel at `x`, `y` of `frame`.
+pub fn pixel(frame: &FrameCapture, x: u32, y: u32) -> [u8; 4] {
+  let index = ((y * frame.width() + x) * 4) as usize;
+  frame.pixels()[index..index + 4].try_into().unwrap()
+}
+
+/// Result of comparing two frames pixel by pixel.
+pub struct Comparison {
+  /// Number of pixels where any channel differs by more than the tolerance.
+  pub mismatched: usize,
+  /// Mismatched pixels in red over a dimmed copy of the expected frame.
+  pub diff: FrameCapture,
+}
+
+pub fn compare(expected: &FrameCapture, actual: &FrameCapture, tolerance: u8) -> Comparison {
+  assert_eq!(
+    (expected.width(), expected.height()),
+    (actual.width(), actual.height()),
+    "frames have different dimensions"
+  );
+
+  let mut mismatched = 0;
+  let mut diff = Vec::with_capacity(expected.pixels().len());
+
+  for (expected, actual) in expected
+    .pixels()
+    .chunks_exact(4)
+    .zip(actual.pixels().chunks_exact(4))
+  {
+    let differs = expected
+      .iter()
+      .zip(actual)
+      .any(|(expected, actual)| expected.abs_diff(*actual) > tolerance);
+
+    if differs {
+      mismatched += 1;
+      diff.extend_from_slice(&[255, 0, 0, 255]);
+    } else {
+      diff.extend(expected[..3].iter().map(|channel| channel / 4));
+      diff.push(255);
+    }
+  }
+
+  Comparison {
+    mismatched,
+    diff: FrameCapture::new(*expected.width(), *expected.height(), diff),
+  }
+}
+
+fn load_png(path: &Path) -> Option<FrameCapture> {
+  let image = image::open(path).ok()?.to_rgba8();
+
+  Some(FrameCapture::new(
+    image.width(),
+    image.height(),
+    image.into_raw(),
+  ))
+}
+
+fn write_png(path: &Path, frame: &FrameCapture) {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).expect("failed to create golden image directory");
+  }
+
+  fs::write(path, frame.to_png().expect("failed to encode png"))
+    .expect("failed to write golden image");
+}
+
+/// Compares `actual` with the reference image `{dir}/{name}.png`, panicking on mismatch.
+pub fn assert_golden(dir: impl AsRef<Path>, name: &str, actual: &FrameCapture, tolerance: u8) {
+  let dir = dir.as_ref();
+  let reference_path = dir.join(format!("{name}.png"));
+
+  if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+    write_png(&reference_path, actual);
+    return;
+  }
+
+  let Some(expected) = load_png(&reference_path) else {
+    panic!(
+      "missing golden image {}, rerun with `{UPDATE_GOLDEN_ENV}=1` to create it",
+      reference_path.display()
+    );
+  };
+
+  let Comparison { mismatched, diff } = compare(&expected, actual, tolerance);
+
+  if mismatched == 0 {
+    return;
+  }
+
+  let failures = dir.join("failures");
+  write_png(&failures.join(format!("{name}.actual.png")), actual);
+  write_png(&failures.join(format!("{name}.diff.png")), &diff);
+
+  panic!(
+    "{mismatched} pixels of `{name}` differ by more than {tolerance}, see {}",
+    failures.display()
+  );
+}
@@ -0,0 +1,97 @@
+use sand_castle_core::{
+  bounds::{Aabb, Frustum},
+  resource::{
+    geometry::{cuboid::Cuboid, ToGeometry},
+    object_3d::{Scale, SceneTransform},
+  },
+  test_support::{cuboid_material, GoldenScene},
+  Mat4, Quat, Vec3,
+};
+
+#[test]
+fn geometry_computes_bounds() {
+  let geometry = Cuboid::builder().build().to_geometry();
+
+  assert_eq!(
+    *geometry.aabb(),
+    Aabb::new(Vec3::splat(-1.0), Vec3::splat(1.0))
+  );
+  assert_eq!(*geometry.bounding_sphere().center(), Vec3::ZERO);
+  assert!((geometry.bounding_sphere().radius() - 3.0f32.sqrt()).abs() < 1e-5);
+}
+
+#[test]
+fn transformed_aabb_encloses_rotated_box() {
+  let aabb = Aabb::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+  let rotated = aabb.transform(Mat4::from_rotation_y(std::f32::consts::FRAC_PI_4));
+
+  let extent = 2.0f32.sqrt();
+  assert!(rotated
+    .max()
+    .abs_diff_eq(Vec3::new(extent, 1.0, extent), 1e-5));
+  assert!(rotated.min().abs_diff_eq(-*rotated.max(), 1e-5));
+}
+
+#[test]
+fn scene_bounds_follow_transforms() {
+  let mut scene = GoldenScene::new(32, 32);
+  assert_eq!(scene.scene.bounds(), None);
+
+  scene.insert_cuboid(&cuboid_material());
+  let mut moved = scene.insert_cuboid(&cuboid_material());
+
+  moved.update_pos(&mut scene.scene, &scene.renderer, Vec3::new(4.0, 0.0, 0.0));
+  moved.update_scale(
+    &mut scene.scene,
+    &scene.renderer,
+    Scale {
+      width: 1.0,
+      height: 3.0,
+      depth: 1.0,
+    },
+  );
+
+  assert_eq!(
+    scene.scene.bounds(),
+    Some(Aabb::new(
+      Vec3::new(-1.0, -3.0, -1.0),
+      Vec3::new(5.0, 3.0, 1.0)
+    ))
+  );
+}
+
+#[test]
+fn frustum_rejects_boxes_behind_the_camera() {
+  let mut scene = GoldenScene::new(32, 32);
+  scene.look_at(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO);
+
+  let frustum = Frustum::from_matrix(*scene.scene.camera_matrix());
+  let unit = Aabb::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+
+  assert!(frustum.intersects_aabb(&unit));
+  assert!(!frustum.intersects_aabb(&unit.transform(Mat4::from_translation(Vec3::Z * 10.0))));
+  assert!(!frustum.intersects_aabb(&unit.transform(Mat4::from_translation(Vec3::X * 20.0))));
+  assert!(!frustum.intersects_aabb(&unit.transform(Mat4::from_translation(Vec3::NEG_Z * 2000.0))));
+  assert!(frustum.intersects_sphere(&unit.bounding_sphere()));
+}
+
+#[test]
+fn render_skips_subjects_outside_the_frustum() {
+  let mut scene = GoldenScene::new(32, 32);
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+
+  scene.insert_cuboid(&cuboid_material());
+  let mut hidden = scene.insert_cuboid(&cuboid_material());
+  hidden.update_rot(
+    &mut scene.scene,
+    &scene.renderer,
+    Quat::from_rotation_x(0.3),
+  );
+  hidden.update_pos(&mut scene.scene, &scene.renderer, Vec3::new(0.0, 0.0, 20.0));
+
+  let stats = scene.scene.render(&scene.renderer);
+
+  // the cuboid in view and the tone mapping pass
+  assert_eq!(*stats.draw_calls(), 2);
+  assert_eq!(*stats.culled(), 1);
+}
@@ -0,0 +1,291 @@
+use std::f32::consts::PI;
+
+use sand_castle_core::{
+  resource::{
+    environment::{Environment, EnvironmentError, EnvironmentSource},
+    lighting::material::{basic::BasicMaterial, pbr::PbrMaterial},
+    texture::{TexelFormat, TextureId},
+  },
+  test_support::{compare, GoldenScene},
+  Vec3, Vec4,
+};
+use wgpu::TextureFormat;
+
+const FACE_COLORS: [[u8; 4]; 6] = [
+  [230, 60, 60, 255],
+  [60, 230, 60, 255],
+  [60, 60, 230, 255],
+  [230, 230, 60, 255],
+  [60, 230, 230, 255],
+  [230, 60, 230, 255],
+];
+
+fn environment_scene() -> GoldenScene {
+  let mut scene = GoldenScene::new(64, 48);
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+  scene
+}
+
+fn cubemap(scene: &mut GoldenScene, size: u32) -> [TextureId; 6] {
+  FACE_COLORS.map(|color| scene.insert_texture((size, size), |_, _| color))
+}
+
+/// Lights the scene from every direction with `faces` without drawing them.
+fn lighting_environment(scene: &mut GoldenScene, faces: [TextureId; 6]) {
+  scene.environment(
+    &Environment::builder()
+      .source(EnvironmentSource::Cubemap(faces))
+      .background(false)
+      .build(),
+  );
+}
+
+fn metal() -> PbrMaterial {
+  PbrMaterial::builder()
+    .color(Vec4::new(0.9, 0.9, 0.9, 1.0))
+    .roughness(0.4)
+    .metalness(1.0)
+    .build()
+}
+
+/// Red channel at the center of the frame, on the cuboid.
+fn center_red(scene: &mut GoldenScene) -> u8 {
+  scene.render().pixels()[((48 / 2) * 64 + 64 / 2) * 4]
+}
+
+/// Face of a cubemap seen in `direction`, the faces are laid out for a left handed frame.
+fn face(direction: Vec3) -> usize {
+  let direction = direction * Vec3::new(-1.0, 1.0, 1.0);
+  let abs = direction.abs();
+
+  if abs.x >= abs.y && abs.x >= abs.z {
+    if direction.x > 0.0 {
+      0
+    } else {
+      1
+    }
+  } else if abs.y >= abs.z {
+    if direction.y > 0.0 {
+      2
+    } else {
+      3
+    }
+  } else if direction.z > 0.0 {
+    4
+  } else {
+    5
+  }
+}
+
+#[test]
+fn equirectangular_matches_the_cubemap_it_was_projected_from() {
+  let mut cube_scene = environment_scene();
+  let faces = cubemap(&mut cube_scene, 64);
+  cube_scene.environment(
+    &Environment::builder()
+      .source(EnvironmentSource::Cubemap(faces))
+      .build(),
+  );
+
+  let (width, height) = (1024, 512);
+  let mut panorama_scene = environment_scene();
+  let panorama = panorama_scene.insert_texture((width, height), |x, y| {
+    let longitude = ((x as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+    let latitude = (0.5 - (y as f32 + 0.5) / height as f32) * PI;
+
+    FACE_COLORS[face(Vec3::new(
+      latitude.cos() * longitude.cos(),
+      latitude.sin(),
+      latitude.cos() * longitude.sin(),
+    ))]
+  });
+  panorama_scene.environment(
+    &Environment::builder()
+      .source(EnvironmentSource::Equirectangular(panorama))
+      .build(),
+  );
+
+  let comparison = compare(&cube_scene.render(), &panorama_scene.render(), 8);
+
+  // only the edges between the faces are resampled differently
+  assert!(
+    comparison.mismatched < 64 * 48 / 20,
+    "{} pixels differ",
+    comparison.mismatched
+  );
+}
+
+#[test]
+fn subjects_are_drawn_over_the_background() {
+  let mut scene = environment_scene();
+  let faces = cubemap(&mut scene, 4);
+  scene.environment(
+    &Environment::builder()
+      .source(EnvironmentSource::Cubemap(faces))
+      .build(),
+  );
+  scene.insert_cuboid(&BasicMaterial::with_color(Vec4::new(0.0, 0.0, 0.0, 1.0)));
+
+  let frame = scene.render();
+  let center = ((48 / 2) * 64 + 64 / 2) * 4;
+
+  assert_eq!(&frame.pixels()[center..center + 4], &[0, 0, 0, 255]);
+}
+
+#[test]
+fn hidden_backgrounds_clear_to_the_scene_color() {
+  let mut scene = environment_scene();
+  let faces = cubemap(&mut scene, 4);
+  scene.environment(
+    &Environment::builder()
+      .source(EnvironmentSource::Cubemap(faces))
+      .background(false)
+      .build(),
+  );
+
+  assert_eq!(scene.render(), environment_scene().render());
+}
+
+#[test]
+fn textures_have_to_be_loaded_first() {
+  let mut scene = environment_scene();
+  let missing = TextureId::new();
+
+  assert_eq!(
+    scene.scene.set_environment(
+      &scene.renderer,
+      &scene.texture_loader,
+      Some(
+        &Environment::builder()
+          .source(EnvironmentSource::Equirectangular(missing))
+          .build()
+      ),
+    ),
+    Err(EnvironmentError::MissingTexture(missing))
+  );
+}
+
+#[test]
+fn cubemap_faces_have_to_be_square_and_equal() {
+  let mut scene = environment_scene();
+  let mut faces = cubemap(&mut scene, 4);
+  faces[3] = scene.insert_texture((4, 2), |_, _| [0, 0, 0, 255]);
+
+  assert_eq!(
+    scene.scene.set_environment(
+      &scene.renderer,
+      &scene.texture_loader,
+      Some(
+        &Environment::builder()
+          .source(EnvironmentSource::Cubemap(faces))
+          .build()
+      ),
+    ),
+    Err(EnvironmentError::FaceSize {
+      expected: (4, 4),
+      actual: (4, 2),
+    })
+  );
+}
+
+#[test]
+fn cubemap_faces_have_to_share_a_format() {
+  let mut scene = environment_scene();
+  let mut faces = cubemap(&mut scene, 4);
+  faces[5] = scene.insert_hdr_texture((4, 4), |_, _| [1.0, 1.0, 1.0, 1.0]);
+
+  assert_eq!(
+    scene.scene.set_environment(
+      &scene.renderer,
+      &scene.texture_loader,
+      Some(
+        &Environment::builder()
+          .source(EnvironmentSource::Cubemap(faces))
+          .build()
+      ),
+    ),
+    Err(EnvironmentError::FaceFormat {
+      expected: TexelFormat::Rgba8Srgb,
+      actual: TexelFormat::Rgba16Float,
+    })
+  );
+}
+
+#[test]
+fn hdr_environments_need_a_renderable_float_format() {
+  let mut scene = environment_scene();
+  let panorama = scene.insert_hdr_texture((8, 4), |_, _| [1.0, 1.0, 1.0, 1.0]);
+
+  let result = scene.scene.set_environment(
+    &scene.renderer,
+    &scene.texture_loader,
+    Some(
+      &Environment::builder()
+        .source(EnvironmentSource::Equirectangular(panorama))
+        .build(),
+    ),
+  );
+
+  // the scene falls back to an 8 bit target for the same reason
+  if *scene.renderer.hdr_format() == TextureFormat::Rgba16Float {
+    assert_eq!(result, Ok(()));
+  } else {
+    assert_eq!(
+      result,
+      Err(EnvironmentError::UnsupportedFormat(
+        TexelFormat::Rgba16Float
+      ))
+    );
+  }
+}
+
+#[test]
+fn metallic_surfaces_reflect_the_environment() {
+  let mut scene = environment_scene();
+  scene.insert_cuboid(&metal());
+  assert_eq!(center_red(&mut scene), 0);
+
+  let faces = [(); 6].map(|_| scene.insert_texture((8, 8), |_, _| [128, 128, 128, 255]));
+  lighting_environment(&mut scene, faces);
+  let lit = center_red(&mut scene);
+  assert!(lit > 32, "the cuboid is barely lit: {lit}");
+
+  scene.scene.set_environment_intensity(&scene.renderer, 0.5);
+  let dimmed = center_red(&mut scene);
+  assert!(dimmed < lit, "{dimmed} isn't dimmer than {lit}");
+
+  scene.scene.set_environment_intensity(&scene.renderer, 0.0);
+  assert_eq!(center_red(&mut scene), 0);
+}
+
+#[test]
+fn removing_the_environment_removes_its_lighting() {
+  let mut scene = environment_scene();
+  scene.insert_cuboid(&metal());
+  let unlit = scene.render();
+
+  let faces = cubemap(&mut scene, 8);
+  lighting_environment(&mut scene, faces);
+  scene
+    .scene
+    .set_environment(&scene.renderer, &scene.texture_loader, None)
+    .unwrap();
+
+  assert_eq!(scene.render(), unlit);
+}
+
+#[test]
+fn hdr_environments_light_beyond_white() {
+  let mut white = environment_scene();
+  white.insert_cuboid(&metal());
+  let faces = [(); 6].map(|_| white.insert_texture((8, 8), |_, _| [255, 255, 255, 255]));
+  lighting_environment(&mut white, faces);
+
+  let mut bright = environment_scene();
+  bright.insert_cuboid(&metal());
+  let faces = [(); 6].map(|_| bright.insert_hdr_texture((8, 8), |_, _| [4.0, 4.0, 4.0, 1.0]));
+  lighting_environment(&mut bright, faces);
+
+  let (white, bright) = (center_red(&mut white), center_red(&mut bright));
+  assert!(bright > white, "{bright} isn't brighter than {white}");
+}
@@ -0,0 +1,137 @@
+use sand_castle_core::{
+  capture::FrameCapture,
+  fog::{Fog, FOG_SHADER},
+  resource::lighting::material::{basic::BasicMaterial, shader::ShaderMaterial},
+  test_support::{compare, pixel, GoldenScene},
+  Vec3, Vec4,
+};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 48;
+
+/// Same as the background of `GoldenScene`.
+const BACKGROUND: Vec3 = Vec3::new(0.1, 0.1, 0.1);
+
+fn foggy_scene(fog: Option<Fog>) -> GoldenScene {
+  let mut scene = GoldenScene::new(WIDTH, HEIGHT);
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+  scene.scene.set_fog(fog);
+  scene.scene.set_fog_color(BACKGROUND);
+  scene
+}
+
+fn background() -> FrameCapture {
+  foggy_scene(None).render()
+}
+
+#[test]
+fn dense_fog_hides_the_subjects() {
+  for fog in [
+    Fog::Linear {
+      near: 0.0,
+      far: 0.01,
+    },
+    Fog::Exponential { density: 100.0 },
+    Fog::ExponentialSquared { density: 100.0 },
+  ] {
+    let mut scene = foggy_scene(Some(fog));
+    scene.insert_cuboid(&BasicMaterial::with_color(Vec4::new(0.9, 0.4, 0.2, 1.0)));
+
+    let comparison = compare(&background(), &scene.render(), 1);
+    assert_eq!(comparison.mismatched, 0, "{fog:?} left the cuboid visible");
+  }
+}
+
+#[test]
+fn linear_fog_starts_at_near() {
+  let mut plain = foggy_scene(None);
+  plain.insert_cuboid(&BasicMaterial::with_color(Vec4::new(0.9, 0.4, 0.2, 1.0)));
+
+  let mut scene = foggy_scene(Some(Fog::Linear {
+    near: 10.0,
+    far: 20.0,
+  }));
+  scene.insert_cuboid(&BasicMaterial::with_color(Vec4::new(0.9, 0.4, 0.2, 1.0)));
+
+  assert_eq!(scene.render(), plain.render());
+}
+
+#[test]
+fn squared_exponential_fog_is_thinner_close_to_the_camera() {
+  let center = |fog: Option<Fog>| {
+    let mut scene = foggy_scene(fog);
+    scene.insert_cuboid(&BasicMaterial::with_color(Vec4::new(1.0, 1.0, 1.0, 1.0)));
+    pixel(&scene.render(), WIDTH / 2, HEIGHT / 2)[0]
+  };
+
+  let clear = center(None);
+  let squared = center(Some(Fog::ExponentialSquared { density: 0.2 }));
+  let exponential = center(Some(Fog::Exponential { density: 0.2 }));
+
+  assert!(clear > squared && squared > exponential);
+}
+
+#[test]
+fn height_falloff_thins_the_fog_above_the_ground() {
+  let mut plain = foggy_scene(None);
+  plain.insert_cuboid(&BasicMaterial::with_color(Vec4::new(0.9, 0.4, 0.2, 1.0)));
+  let plain = plain.render();
+
+  let mut scene = foggy_scene(Some(Fog::Exponential { density: 100.0 }));
+  scene.scene.set_fog_height_falloff(100.0);
+  scene.insert_cuboid(&BasicMaterial::with_color(Vec4::new(0.9, 0.4, 0.2, 1.0)));
+  let frame = scene.render();
+
+  // the top of the cuboid pokes out of the fog, its lower half stays hidden
+  let clear = compare(&background(), &frame, 1).mismatched;
+  assert!(clear > 0);
+  assert!(clear < compare(&background(), &plain, 1).mismatched);
+}
+
+#[test]
+fn shader_materials_apply_the_fog_of_the_scene() {
+  let material = ShaderMaterial::builder()
+    .vertex_shader(
+      "struct VertexOutput {
+        @builtin(position) clip_position: vec4<f32>,
+        @location(0) world_position: vec3<f32>,
+      };
+
+      @group(0) @binding(0)
+      var<uniform> camera: mat4x4<f32>;
+
+      @group(1) @binding(0)
+      var<uniform> transformation: mat4x4<f32>;
+
+      @vertex
+      fn vs_main(@location(0) position: vec3<f32>) -> VertexOutput {
+        let world_position = transformation * vec4<f32>(position, 1.0);
+
+        var out: VertexOutput;
+        out.clip_position = camera * world_position;
+        out.world_position = world_position.xyz;
+        return out;
+      }",
+    )
+    .fragment_shader(format!(
+      "{FOG_SHADER}
+      struct VertexOutput {{
+        @builtin(position) clip_position: vec4<f32>,
+        @location(0) world_position: vec3<f32>,
+      }};
+
+      @fragment
+      fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {{
+        return apply_fog(vec4<f32>(1.0, 0.0, 0.0, 1.0), in.world_position);
+      }}"
+    ))
+    .build();
+
+  let mut clear = foggy_scene(None);
+  clear.insert_cuboid(&material);
+  assert!(compare(&background(), &clear.render(), 1).mismatched > 0);
+
+  let mut scene = foggy_scene(Some(Fog::Exponential { density: 100.0 }));
+  scene.insert_cuboid(&material);
+  assert_eq!(compare(&background(), &scene.render(), 1).mismatched, 0);
+}
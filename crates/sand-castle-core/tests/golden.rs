@@ -0,0 +1,670 @@
+use sand_castle_core::{
+  capture::FrameCapture,
+  fog::Fog,
+  post_processing::{bloom::Bloom, fxaa::Fxaa, sharpen::Sharpen, vignette::Vignette},
+  resource::{
+    environment::{Environment, EnvironmentSource},
+    lighting::{
+      light::{
+        ambient_light::AmbientLight, directional_light::DirectionalLight,
+        hemisphere_light::HemisphereLight, point_light::PointLight, rect_area_light::RectAreaLight,
+        spot_light::SpotLight,
+      },
+      material::{
+        basic::BasicMaterial, pbr::PbrMaterial, phong::PhongMaterial, AlphaMode, ToMaterial,
+      },
+    },
+    object_3d::{group::Group, mesh::Mesh, Object3D, Scale, SceneTransform, Transform},
+    Resource,
+  },
+  test_support::{
+    assert_golden, cuboid_material, cuboid_scene, headless_renderer_with, GoldenScene,
+  },
+  Quat, Vec3, Vec4,
+};
+
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+const TOLERANCE: u8 = 2;
+
+fn golden(name: &str, frame: &FrameCapture) {
+  assert_golden(GOLDEN_DIR, name, frame, TOLERANCE);
+}
+
+fn phong() -> PhongMaterial {
+  PhongMaterial::with_color(Vec4::new(0.9, 0.4, 0.2, 1.0))
+}
+
+fn pbr() -> PbrMaterial {
+  PbrMaterial::builder()
+    .color(Vec4::new(0.9, 0.4, 0.2, 1.0))
+    .roughness(0.5)
+    .metalness(0.1)
+    .build()
+}
+
+fn ambient_light() -> AmbientLight {
+  AmbientLight::builder()
+    .color(Vec3::new(0.3, 0.3, 0.3))
+    .build()
+}
+
+fn directional_light() -> DirectionalLight {
+  DirectionalLight::builder()
+    .color(Vec3::new(1.0, 1.0, 1.0))
+    .direction(Vec3::new(-1.0, -2.0, -1.5).normalize())
+    .build()
+}
+
+fn point_light() -> PointLight {
+  PointLight::builder()
+    .color(Vec3::new(1.0, 1.0, 1.0))
+    .position(Vec3::new(2.0, 3.0, 3.0))
+    .intensity(20.0)
+    .build()
+}
+
+fn spot_light() -> SpotLight {
+  SpotLight::builder()
+    .color(Vec3::new(1.0, 1.0, 1.0))
+    .position(Vec3::new(0.0, 4.0, 0.0))
+    .direction(Vec3::new(0.0, -1.0, 0.0))
+    .intensity(14.0)
+    .inner_cone_angle(0.2)
+    .outer_cone_angle(0.35)
+    .build()
+}
+
+fn hemisphere_light() -> HemisphereLight {
+  HemisphereLight::builder()
+    .sky_color(Vec3::new(0.6, 0.7, 1.0))
+    .ground_color(Vec3::new(0.3, 0.2, 0.1))
+    .up(Vec3::new(1.0, 1.0, 0.0))
+    .build()
+}
+
+fn rect_area_light() -> RectAreaLight {
+  let position = Vec3::new(1.5, 2.0, 0.5);
+
+  RectAreaLight::builder()
+    .color(Vec3::new(1.0, 1.0, 1.0))
+    .position(position)
+    .rotation(Quat::from_rotation_arc(Vec3::NEG_Z, -position.normalize()))
+    .width(2.0)
+    .height(1.0)
+    .intensity(4.0)
+    .build()
+}
+
+#[test]
+fn basic_material() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&cuboid_material());
+
+  golden("basic_material", &scene.render());
+}
+
+#[test]
+fn basic_material_msaa() {
+  let mut scene = GoldenScene::with_renderer(headless_renderer_with(96, 64, |builder| {
+    builder.sample_count(4)
+  }));
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+  scene.insert_cuboid(&cuboid_material());
+
+  assert_eq!(*scene.renderer.sample_count(), 4);
+  golden("basic_material_msaa", &scene.render());
+}
+
+#[test]
+fn basic_material_resized() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&cuboid_material());
+  scene.render();
+
+  scene.renderer.set_offscreen_size(64, 96);
+  scene.scene.resize(&scene.renderer);
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+
+  golden("basic_material_resized", &scene.render());
+}
+
+#[test]
+fn basic_material_resized_without_resizing_the_scene() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&cuboid_material());
+  scene.render();
+
+  // rendering catches up with the size of the renderer on its own
+  scene.renderer.set_offscreen_size(64, 96);
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+
+  golden("basic_material_resized", &scene.render());
+}
+
+#[test]
+fn basic_material_rebuilt_after_device_loss() {
+  let mut scene = cuboid_scene();
+  let mesh = scene.insert_cuboid(&cuboid_material());
+
+  scene.renderer.device().destroy();
+  scene.renderer = pollster::block_on(scene.renderer.rebuild()).expect("failed to rebuild");
+  scene.scene.rebuild(
+    &scene.renderer,
+    &scene.geometry_loader,
+    &scene.material_loader,
+    &scene.texture_loader,
+    [&mesh],
+  );
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+
+  golden("basic_material", &scene.render());
+}
+
+#[test]
+fn phong_directional_light_rebuilt_after_device_loss() {
+  let mut scene = cuboid_scene();
+  let mesh = scene.insert_cuboid(&phong());
+  scene.directional_lights(&[directional_light()]);
+
+  // the camera and the lights are restored along with the subjects
+  scene.renderer.device().destroy();
+  scene.renderer = pollster::block_on(scene.renderer.rebuild()).expect("failed to rebuild");
+  scene.scene.rebuild(
+    &scene.renderer,
+    &scene.geometry_loader,
+    &scene.material_loader,
+    &scene.texture_loader,
+    [&mesh],
+  );
+
+  golden("phong_directional_light", &scene.render());
+}
+
+#[test]
+fn basic_material_in_group() {
+  let mut scene = cuboid_scene();
+  let mesh = scene.insert_cuboid(&cuboid_material());
+
+  let mut group = Group::builder()
+    .rotation(Quat::from_rotation_y(0.6))
+    .build();
+
+  scene.scene.insert_group(&group);
+  scene
+    .scene
+    .set_parent(&scene.renderer, mesh.id(), Some(group.id()));
+
+  // moving the group after parenting has to move the mesh along with it
+  group.update_pos(&mut scene.scene, &scene.renderer, Vec3::new(-0.6, 0.3, 0.0));
+
+  assert_eq!(
+    scene.scene.world_matrix(mesh.id()),
+    Some(group.matrix() * mesh.matrix())
+  );
+
+  golden("basic_material_in_group", &scene.render());
+}
+
+#[test]
+fn phong_ambient_light() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&phong());
+  scene.ambient_light(&ambient_light());
+
+  golden("phong_ambient_light", &scene.render());
+}
+
+#[test]
+fn phong_directional_light() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&phong());
+  scene.directional_lights(&[directional_light()]);
+
+  golden("phong_directional_light", &scene.render());
+}
+
+#[test]
+fn phong_point_light() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&phong());
+  scene.point_lights(&[point_light()]);
+
+  golden("phong_point_light", &scene.render());
+}
+
+#[test]
+fn phong_point_light_scaled() {
+  let mut scene = cuboid_scene();
+  let mut mesh = scene.insert_cuboid(&phong());
+  scene.point_lights(&[point_light()]);
+
+  mesh.update_scale(
+    &mut scene.scene,
+    &scene.renderer,
+    Scale {
+      width: 1.8,
+      height: 0.5,
+      depth: 1.0,
+    },
+  );
+
+  assert_eq!(scene.scene.world_matrix(mesh.id()), Some(mesh.matrix()));
+
+  golden("phong_point_light_scaled", &scene.render());
+}
+
+#[test]
+fn phong_spot_light() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&phong());
+  scene.spot_lights(&[spot_light()]);
+
+  golden("phong_spot_light", &scene.render());
+}
+
+#[test]
+fn phong_hemisphere_light() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&phong());
+  scene.hemisphere_light(&hemisphere_light());
+
+  golden("phong_hemisphere_light", &scene.render());
+}
+
+#[test]
+fn phong_rect_area_light() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&phong());
+  scene.rect_area_lights(&[rect_area_light()]);
+
+  golden("phong_rect_area_light", &scene.render());
+}
+
+#[test]
+fn pbr_ambient_light() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&pbr());
+  scene.ambient_light(&ambient_light());
+
+  golden("pbr_ambient_light", &scene.render());
+}
+
+#[test]
+fn pbr_directional_light() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&pbr());
+  scene.directional_lights(&[directional_light()]);
+
+  golden("pbr_directional_light", &scene.render());
+}
+
+#[test]
+fn pbr_point_light() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&pbr());
+  scene.point_lights(&[point_light()]);
+
+  golden("pbr_point_light", &scene.render());
+}
+
+#[test]
+fn pbr_spot_light() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&pbr());
+  scene.spot_lights(&[spot_light()]);
+
+  golden("pbr_spot_light", &scene.render());
+}
+
+#[test]
+fn pbr_hemisphere_light() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&pbr());
+  scene.hemisphere_light(&hemisphere_light());
+
+  golden("pbr_hemisphere_light", &scene.render());
+}
+
+#[test]
+fn pbr_rect_area_light() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&pbr());
+  scene.rect_area_lights(&[rect_area_light()]);
+
+  golden("pbr_rect_area_light", &scene.render());
+}
+
+fn translucent(color: Vec4) -> BasicMaterial {
+  BasicMaterial::builder()
+    .color(color)
+    .alpha_mode(AlphaMode::Blend)
+    .build()
+}
+
+#[test]
+fn transparent_subjects_are_drawn_back_to_front() {
+  let mut scene = cuboid_scene();
+  let half = Scale {
+    width: 0.5,
+    height: 0.5,
+    depth: 0.5,
+  };
+
+  // inserted front to back, the opaque box last, so insertion order would hide what is behind
+  for (material, pos) in [
+    (
+      translucent(Vec4::new(0.9, 0.2, 0.2, 0.5)),
+      Vec3::new(1.0, 0.2, 0.4),
+    ),
+    (translucent(Vec4::new(0.2, 0.9, 0.2, 0.5)), Vec3::ZERO),
+  ] {
+    let mut mesh = scene.insert_cuboid(&material);
+    mesh.update_scale(&mut scene.scene, &scene.renderer, half);
+    mesh.update_pos(&mut scene.scene, &scene.renderer, pos);
+  }
+
+  let mut opaque = scene.insert_cuboid(&BasicMaterial::with_color(Vec4::new(0.2, 0.3, 0.9, 1.0)));
+  opaque.update_scale(&mut scene.scene, &scene.renderer, half);
+  opaque.update_pos(
+    &mut scene.scene,
+    &scene.renderer,
+    Vec3::new(-1.0, -0.2, -0.4),
+  );
+
+  golden("transparent_back_to_front", &scene.render());
+}
+
+#[test]
+fn alpha_mask_discards_below_cutoff() {
+  let mut scene = cuboid_scene();
+
+  let mut kept = scene.insert_cuboid(
+    &BasicMaterial::builder()
+      .color(Vec4::new(0.9, 0.4, 0.2, 0.6))
+      .alpha_mode(AlphaMode::Mask(0.5))
+      .build(),
+  );
+  kept.update_pos(&mut scene.scene, &scene.renderer, Vec3::new(1.2, 0.0, -1.2));
+
+  let mut discarded = scene.insert_cuboid(
+    &BasicMaterial::builder()
+      .color(Vec4::new(0.2, 0.6, 0.9, 0.4))
+      .alpha_mode(AlphaMode::Mask(0.5))
+      .build(),
+  );
+  discarded.update_pos(&mut scene.scene, &scene.renderer, Vec3::new(-1.2, 0.0, 1.2));
+
+  golden("alpha_mask", &scene.render());
+}
+
+/// Inserts a ground slab receiving shadows and a cube casting them onto it.
+fn shadow_scene(material: &PhongMaterial) -> GoldenScene {
+  shadow_scene_with(cuboid_scene(), material, material).0
+}
+
+fn shadow_scene_with(
+  mut scene: GoldenScene,
+  ground: &PhongMaterial,
+  caster: &PhongMaterial,
+) -> (GoldenScene, Mesh) {
+  let mut ground = scene.insert_cuboid(ground);
+  ground.set_receive_shadow(true);
+  scene.scene.update_shadows(&scene.renderer, &ground);
+  ground.update_scale(
+    &mut scene.scene,
+    &scene.renderer,
+    Scale {
+      width: 4.0,
+      height: 0.1,
+      depth: 4.0,
+    },
+  );
+  ground.update_pos(&mut scene.scene, &scene.renderer, Vec3::new(0.0, -0.6, 0.0));
+
+  let mut caster = scene.insert_cuboid(caster);
+  caster.set_cast_shadow(true);
+  scene.scene.update_shadows(&scene.renderer, &caster);
+  caster.update_scale(
+    &mut scene.scene,
+    &scene.renderer,
+    Scale {
+      width: 0.6,
+      height: 0.6,
+      depth: 0.6,
+    },
+  );
+  caster.update_pos(&mut scene.scene, &scene.renderer, Vec3::new(0.0, 0.3, 0.0));
+
+  scene.ambient_light(&ambient_light());
+  (scene, caster)
+}
+
+#[test]
+fn shadow_directional_light() {
+  let mut scene = shadow_scene(&phong());
+  // the shadow falls towards the camera
+  let mut light = directional_light();
+  light.set_direction(Vec3::new(0.8, -2.0, 1.0).normalize());
+  light.set_cast_shadow(true);
+  scene.directional_lights(&[light]);
+
+  golden("shadow_directional_light", &scene.render());
+}
+
+#[test]
+fn shadow_spot_light() {
+  let mut scene = shadow_scene(&phong());
+  let mut light = spot_light();
+  // low and behind the cube so the shadow falls towards the camera
+  light.set_position(Vec3::new(-2.0, 2.0, -2.0));
+  light.set_direction(Vec3::new(1.0, -1.0, 1.0).normalize());
+  light.set_outer_cone_angle(0.8);
+  light.set_cast_shadow(true);
+  scene.spot_lights(&[light]);
+
+  golden("shadow_spot_light", &scene.render());
+}
+
+#[test]
+fn shadow_is_removed_when_the_light_stops_casting() {
+  let mut scene = shadow_scene(&phong());
+  let mut light = directional_light();
+  light.set_cast_shadow(true);
+  scene.directional_lights(&[light.clone()]);
+  scene.render();
+
+  light.set_cast_shadow(false);
+  scene.directional_lights(&[light]);
+  let unshadowed = scene.render();
+
+  let mut reference = shadow_scene(&phong());
+  reference.directional_lights(&[directional_light()]);
+
+  assert_eq!(unshadowed, reference.render());
+}
+
+#[test]
+fn shadow_skips_the_fragments_discarded_by_an_alpha_mask() {
+  let masked = |alpha: u8, textured: bool, cast_shadow: bool| {
+    let mut scene = cuboid_scene();
+
+    let caster = if textured {
+      PhongMaterial::builder()
+        .diffuse_map_texture_id(Some(
+          scene.insert_texture((2, 2), |_, _| [230, 100, 50, alpha]),
+        ))
+        .alpha_mode(AlphaMode::Mask(0.5))
+        .build()
+    } else {
+      PhongMaterial::builder()
+        .color(Vec4::new(0.9, 0.4, 0.2, alpha as f32 / 255.0))
+        .alpha_mode(AlphaMode::Mask(0.5))
+        .build()
+    };
+
+    let (mut scene, _) = shadow_scene_with(scene, &phong(), &caster);
+    let mut light = directional_light();
+    light.set_cast_shadow(cast_shadow);
+    scene.directional_lights(&[light]);
+    scene.render()
+  };
+
+  for textured in [false, true] {
+    // the discarded cube neither shows up nor darkens the ground
+    assert_eq!(masked(100, textured, true), masked(100, textured, false));
+    assert_ne!(masked(160, textured, true), masked(160, textured, false));
+  }
+}
+
+#[test]
+fn shadow_mask_follows_the_material_data_updated_after_insert() {
+  let caster = |alpha: f32| {
+    PhongMaterial::builder()
+      .color(Vec4::new(0.9, 0.4, 0.2, alpha))
+      .alpha_mode(AlphaMode::Mask(0.5))
+      .build()
+  };
+  let render = |inserted: f32, updated: f32| {
+    let (mut scene, mesh) = shadow_scene_with(cuboid_scene(), &phong(), &caster(inserted));
+    scene
+      .scene
+      .update_material_data(&scene.renderer, &mesh, &caster(updated).to_material());
+    let mut light = directional_light();
+    light.set_cast_shadow(true);
+    scene.directional_lights(&[light]);
+    scene.render()
+  };
+
+  assert_eq!(render(0.4, 0.6), render(0.6, 0.6));
+  assert_eq!(render(0.6, 0.4), render(0.4, 0.4));
+}
+
+/// Checkered faces tinted by their axis, which show both the face and its orientation.
+fn checkered_environment(scene: &mut GoldenScene) {
+  let faces = [
+    [1.0, 0.3, 0.3],
+    [0.5, 0.15, 0.15],
+    [0.3, 1.0, 0.3],
+    [0.15, 0.5, 0.15],
+    [0.3, 0.3, 1.0],
+    [0.15, 0.15, 0.5],
+  ]
+  .map(|tint: [f32; 3]| {
+    scene.insert_texture((32, 32), |x, y| {
+      let shade = if (x / 8 + y / 16) % 2 == 0 {
+        255.0
+      } else {
+        140.0
+      };
+      [
+        (tint[0] * shade) as u8,
+        (tint[1] * shade) as u8,
+        (tint[2] * shade) as u8,
+        255,
+      ]
+    })
+  });
+
+  scene.environment(
+    &Environment::builder()
+      .source(EnvironmentSource::Cubemap(faces))
+      .build(),
+  );
+}
+
+#[test]
+fn environment_cubemap() {
+  let mut scene = cuboid_scene();
+  checkered_environment(&mut scene);
+  scene.insert_cuboid(&phong());
+  scene.directional_lights(&[directional_light()]);
+
+  golden("environment_cubemap", &scene.render());
+}
+
+#[test]
+fn pbr_environment_lighting() {
+  let mut scene = cuboid_scene();
+  checkered_environment(&mut scene);
+  scene.insert_cuboid(
+    &PbrMaterial::builder()
+      .color(Vec4::new(0.9, 0.9, 0.9, 1.0))
+      .roughness(0.2)
+      .metalness(1.0)
+      .build(),
+  );
+
+  golden("pbr_environment_lighting", &scene.render());
+}
+
+#[test]
+fn pbr_environment_lighting_rebuilt_after_device_loss() {
+  let mut scene = cuboid_scene();
+  checkered_environment(&mut scene);
+  let mesh = scene.insert_cuboid(
+    &PbrMaterial::builder()
+      .color(Vec4::new(0.9, 0.9, 0.9, 1.0))
+      .roughness(0.2)
+      .metalness(1.0)
+      .build(),
+  );
+
+  scene.renderer.device().destroy();
+  scene.renderer = pollster::block_on(scene.renderer.rebuild()).expect("failed to rebuild");
+  scene.scene.rebuild(
+    &scene.renderer,
+    &scene.geometry_loader,
+    &scene.material_loader,
+    &scene.texture_loader,
+    [&mesh],
+  );
+
+  golden("pbr_environment_lighting", &scene.render());
+}
+
+#[test]
+fn post_processing_chain() {
+  let mut scene = cuboid_scene();
+  scene.insert_cuboid(&phong());
+  scene.directional_lights(&[directional_light()]);
+  scene.post_processing(&[
+    &Bloom::builder().threshold(0.5).build(),
+    &Fxaa::builder().build(),
+    &Sharpen::builder().build(),
+    &Vignette::builder().build(),
+  ]);
+
+  golden("post_processing_chain", &scene.render());
+}
+
+#[test]
+fn fog_exponential_squared() {
+  let mut scene = cuboid_scene();
+  scene.directional_lights(&[directional_light()]);
+  scene
+    .scene
+    .set_fog(Some(Fog::ExponentialSquared { density: 0.15 }));
+  scene.scene.set_fog_color(Vec3::new(0.1, 0.1, 0.1));
+
+  // a row of boxes fading away with the distance
+  for pos in [
+    Vec3::new(-0.5, 0.2, 0.9),
+    Vec3::new(-1.4, -0.7, -0.9),
+    Vec3::new(-1.5, -1.7, -3.3),
+    Vec3::new(-0.7, -2.7, -6.4),
+  ] {
+    let mut mesh = scene.insert_cuboid(&pbr());
+    mesh.update_scale(
+      &mut scene.scene,
+      &scene.renderer,
+      Scale {
+        width: 0.5,
+        height: 0.5,
+        depth: 0.5,
+      },
+    );
+    mesh.update_pos(&mut scene.scene, &scene.renderer, pos);
+  }
+
+  golden("fog_exponential_squared", &scene.render());
+}
@@ -0,0 +1,166 @@
+use sand_castle_core::{
+  light_storage::{LightArrayError, LightKind, LightStorage, TooManyLights, MAX_UNIFORM_LIGHTS},
+  resource::lighting::{
+    light::{point_light::PointLight, rect_area_light::RectAreaLight},
+    material::{phong::PhongMaterial, shader::ShaderMaterial},
+  },
+  test_support::{headless_renderer, headless_renderer_with, GoldenScene},
+  Quat, Vec3, Vec4,
+};
+
+fn point_lights(count: usize, color: Vec3) -> Vec<PointLight> {
+  (0..count)
+    .map(|i| {
+      let angle = i as f32 / count as f32 * std::f32::consts::TAU;
+
+      PointLight::builder()
+        .color(color)
+        .position(Vec3::new(angle.cos() * 3.0, 3.0, angle.sin() * 3.0))
+        .build()
+    })
+    .collect()
+}
+
+fn lit_scene(renderer: sand_castle_core::renderer::Renderer) -> GoldenScene {
+  let mut scene = GoldenScene::with_renderer(renderer);
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+  scene.insert_cuboid(&PhongMaterial::with_color(Vec4::new(0.9, 0.4, 0.2, 1.0)));
+  scene
+}
+
+#[test]
+fn uniform_lights_report_the_ones_left_out() {
+  let mut scene = lit_scene(headless_renderer_with(32, 32, |builder| {
+    builder.storage_buffers(false)
+  }));
+
+  assert_eq!(*scene.scene.light_storage(), LightStorage::Uniform);
+  assert_eq!(
+    scene.scene.light_storage().max_lights(),
+    Some(MAX_UNIFORM_LIGHTS)
+  );
+
+  let lights = point_lights(MAX_UNIFORM_LIGHTS, Vec3::ONE);
+  assert_eq!(
+    scene.scene.bind_point_lights(&scene.renderer, &lights),
+    Ok(())
+  );
+
+  let lights = point_lights(20, Vec3::ONE);
+  assert_eq!(
+    scene.scene.bind_point_lights(&scene.renderer, &lights),
+    Err(TooManyLights {
+      kind: LightKind::Point,
+      count: 20,
+      max: MAX_UNIFORM_LIGHTS,
+    })
+  );
+
+  scene.render();
+}
+
+#[test]
+fn storage_lights_are_not_limited() {
+  let mut scene = lit_scene(headless_renderer(32, 32));
+
+  if *scene.scene.light_storage() != LightStorage::Storage {
+    eprintln!("storage buffers are unsupported by the adapter, skipping");
+    return;
+  }
+
+  // only the lights past the uniform limit emit anything
+  let mut lights = point_lights(MAX_UNIFORM_LIGHTS * 2, Vec3::splat(0.1));
+  for light in &mut lights[..MAX_UNIFORM_LIGHTS] {
+    light.set_color(Vec3::ZERO);
+  }
+
+  scene
+    .scene
+    .bind_point_lights(&scene.renderer, &lights)
+    .expect("storage buffers hold any number of lights");
+
+  let mut reference = lit_scene(headless_renderer(32, 32));
+  reference
+    .scene
+    .bind_point_lights(&reference.renderer, &lights[MAX_UNIFORM_LIGHTS..])
+    .expect("the lights fit into a uniform array");
+
+  assert_eq!(scene.render(), reference.render());
+}
+
+#[test]
+fn lights_out_of_range_emit_nothing() {
+  let mut scene = lit_scene(headless_renderer(32, 32));
+  let light = PointLight::builder()
+    .position(Vec3::new(2.0, 3.0, 3.0))
+    .intensity(20.0)
+    .range(2.0)
+    .build();
+  scene
+    .scene
+    .bind_point_lights(&scene.renderer, &[light])
+    .expect("a single light always fits");
+
+  let mut unlit = lit_scene(headless_renderer(32, 32));
+
+  assert_eq!(scene.render(), unlit.render());
+}
+
+#[test]
+fn rect_area_lights_only_emit_from_their_front() {
+  let position = Vec3::new(1.5, 2.0, 0.5);
+  let facing_away = RectAreaLight::builder()
+    .position(position)
+    .rotation(Quat::from_rotation_arc(Vec3::NEG_Z, position.normalize()))
+    .intensity(4.0)
+    .build();
+
+  let mut scene = lit_scene(headless_renderer(32, 32));
+  scene
+    .scene
+    .bind_rect_area_lights(&scene.renderer, &[facing_away])
+    .expect("a single light always fits");
+
+  let mut unlit = lit_scene(headless_renderer(32, 32));
+
+  assert_eq!(scene.render(), unlit.render());
+}
+
+#[test]
+fn shaders_declaring_only_some_light_arrays_are_rejected() {
+  let mut scene = lit_scene(headless_renderer(32, 32));
+
+  let material = ShaderMaterial::builder()
+    .vertex_shader(
+      "@group(0) @binding(0)
+      var<uniform> camera: mat4x4<f32>;
+
+      @group(1) @binding(0)
+      var<uniform> transformation: mat4x4<f32>;
+
+      @vertex
+      fn vs_main(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+        return camera * transformation * vec4<f32>(position, 1.0);
+      }",
+    )
+    .fragment_shader(
+      "struct PointLight {
+        position: vec3<f32>,
+        range: f32,
+      }
+
+      @group(5) @binding(1)
+      var<uniform> point_lights: array<PointLight, 16>;
+
+      @fragment
+      fn fs_main() -> @location(0) vec4<f32> {
+        return vec4<f32>(point_lights[0].position, 1.0);
+      }",
+    )
+    .build();
+
+  assert_eq!(
+    scene.try_insert_cuboid(&material).err(),
+    Some(LightArrayError::Partial { declared: 1 })
+  );
+}
@@ -0,0 +1,79 @@
+use sand_castle_core::{
+  picking::PickError,
+  resource::{object_3d::SceneTransform, Resource},
+  test_support::{cuboid_material, cuboid_scene},
+  Vec2, Vec3,
+};
+
+#[test]
+fn pick_requires_enabling() {
+  let scene = cuboid_scene();
+
+  let result = pollster::block_on(scene.scene.pick(&scene.renderer, 48, 32));
+
+  assert!(matches!(result, Err(PickError::NotEnabled)));
+}
+
+#[test]
+fn pick_returns_the_object_under_the_pixel() {
+  let mut scene = cuboid_scene();
+  // subjects inserted before enabling have to be pickable too
+  let mesh = scene.insert_cuboid(&cuboid_material());
+  scene.scene.enable_picking(&scene.renderer);
+
+  let (x, y) = (48, 32);
+  let picked = pollster::block_on(scene.scene.pick(&scene.renderer, x, y))
+    .expect("failed to pick")
+    .expect("nothing under the center pixel");
+
+  assert_eq!(*picked.id(), mesh.id());
+
+  let ray = scene
+    .scene
+    .camera_ray(Vec2::new((x as f32 + 0.5) / 96.0, (y as f32 + 0.5) / 64.0));
+  let hit = scene
+    .scene
+    .raycast(&scene.geometry_loader, *ray.origin(), *ray.direction())[0];
+
+  assert!(
+    picked.position().distance(*hit.point()) < 0.01,
+    "picked {}, raycast hit {}",
+    picked.position(),
+    hit.point()
+  );
+
+  let corner = pollster::block_on(scene.scene.pick(&scene.renderer, 1, 1)).expect("failed to pick");
+  assert_eq!(corner, None);
+
+  let outside =
+    pollster::block_on(scene.scene.pick(&scene.renderer, 96, 0)).expect("failed to pick");
+  assert_eq!(outside, None);
+}
+
+#[test]
+fn pick_returns_the_nearest_object() {
+  let mut scene = cuboid_scene();
+  scene.scene.enable_picking(&scene.renderer);
+
+  let mut far = scene.insert_cuboid(&cuboid_material());
+  far.update_pos(
+    &mut scene.scene,
+    &scene.renderer,
+    Vec3::new(-0.5, -0.4, -0.6),
+  );
+  let near = scene.insert_cuboid(&cuboid_material());
+
+  let picked = pollster::block_on(scene.scene.pick(&scene.renderer, 48, 32))
+    .expect("failed to pick")
+    .expect("nothing under the center pixel");
+
+  assert_eq!(*picked.id(), near.id());
+
+  scene.scene.remove(&scene.renderer, &near);
+
+  let picked = pollster::block_on(scene.scene.pick(&scene.renderer, 48, 32))
+    .expect("failed to pick")
+    .expect("nothing under the center pixel");
+
+  assert_eq!(*picked.id(), far.id());
+}
@@ -0,0 +1,293 @@
+use sand_castle_core::{
+  capture::FrameCapture,
+  post_processing::{
+    bloom::Bloom, color_grading::ColorGrading, tone_mapping::ToneMapping, vignette::Vignette,
+    PostEffect, PostPass, PostProcessingError, PostStage,
+  },
+  resource::{
+    lighting::light::directional_light::DirectionalLight,
+    lighting::material::{basic::BasicMaterial, phong::PhongMaterial},
+    texture::TextureId,
+  },
+  test_support::{compare, pixel, GoldenScene},
+  Vec3, Vec4,
+};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 48;
+
+fn lit_scene() -> GoldenScene {
+  let mut scene = GoldenScene::new(WIDTH, HEIGHT);
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+  scene.insert_cuboid(&PhongMaterial::with_color(Vec4::new(0.9, 0.4, 0.2, 1.0)));
+  scene.directional_lights(&[DirectionalLight::builder()
+    .color(Vec3::new(1.0, 1.0, 1.0))
+    .direction(Vec3::new(-1.0, -2.0, -1.5).normalize())
+    .build()]);
+  scene
+}
+
+fn invert() -> PostPass {
+  PostPass::builder()
+    .shader(
+      "@fragment
+      fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+        let color = textureSampleLevel(previous, post_sampler, in.uv, 0.0);
+        return vec4<f32>(1.0 - color.rgb, color.a);
+      }",
+    )
+    .build()
+}
+
+/// Covers the frame with `color`, passed as the uniform of the pass.
+fn fill(color: Vec4) -> PostPass {
+  PostPass::builder()
+    .shader(
+      "struct Fill {
+        color: vec4<f32>,
+      };
+
+      @group(1) @binding(0)
+      var<uniform> fill: Fill;
+
+      @fragment
+      fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+        return fill.color;
+      }",
+    )
+    .uniforms(bytemuck::cast_slice(&[color]).to_vec())
+    .build()
+}
+
+#[test]
+fn neutral_effects_leave_the_frame_unchanged() {
+  let plain = lit_scene().render();
+
+  let mut scene = lit_scene();
+  scene.post_processing(&[&Vignette::builder().intensity(0.0).build()]);
+  // half float intermediates may round a channel by one
+  assert_eq!(compare(&plain, &scene.render(), 1).mismatched, 0);
+
+  scene.post_processing(&[]);
+  assert_eq!(scene.render(), plain);
+}
+
+#[test]
+fn vignette_darkens_the_corners() {
+  let plain = lit_scene().render();
+
+  let mut scene = lit_scene();
+  scene.post_processing(&[&Vignette::builder().intensity(0.8).build()]);
+  let frame = scene.render();
+
+  let (center_x, center_y) = (WIDTH / 2, HEIGHT / 2);
+  assert_eq!(
+    pixel(&frame, center_x, center_y),
+    pixel(&plain, center_x, center_y)
+  );
+  assert!(pixel(&frame, 0, 0)[0] < pixel(&plain, 0, 0)[0]);
+}
+
+#[test]
+fn effects_apply_in_order() {
+  let only = |frame: FrameCapture, color: [u8; 4]| {
+    frame.pixels().chunks_exact(4).all(|pixel| pixel == color)
+  };
+
+  let mut scene = lit_scene();
+  scene.post_processing(&[&fill(Vec4::new(0.0, 1.0, 0.0, 1.0)), &invert()]);
+  assert!(only(scene.render(), [255, 0, 255, 255]));
+
+  scene.post_processing(&[&invert(), &fill(Vec4::new(0.0, 1.0, 0.0, 1.0))]);
+  assert!(only(scene.render(), [0, 255, 0, 255]));
+}
+
+#[test]
+fn later_passes_read_the_input_of_their_effect() {
+  let plain = lit_scene().render();
+
+  let passes = vec![
+    PostPass::builder()
+      .shader(
+        "@fragment
+        fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+          return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+        }",
+      )
+      .scale(0.25)
+      .build(),
+    PostPass::builder()
+      .shader(
+        "@fragment
+        fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+          return textureSampleLevel(source, post_sampler, in.uv, 0.0);
+        }",
+      )
+      .build(),
+  ];
+
+  let mut scene = lit_scene();
+  scene
+    .scene
+    .set_post_processing(&scene.renderer, &scene.texture_loader, &[&passes])
+    .unwrap();
+
+  assert_eq!(compare(&plain, &scene.render(), 1).mismatched, 0);
+}
+
+#[test]
+fn lookup_textures_have_to_be_loaded_first() {
+  let mut scene = lit_scene();
+  scene.post_processing(&[&invert()]);
+  let inverted = scene.render();
+
+  let missing = TextureId::new();
+  assert_eq!(
+    scene.scene.set_post_processing(
+      &scene.renderer,
+      &scene.texture_loader,
+      &[&ColorGrading::builder().lut(missing).build()],
+    ),
+    Err(PostProcessingError::MissingTexture(missing))
+  );
+
+  // the previous effects are kept
+  assert_eq!(scene.render(), inverted);
+}
+
+#[test]
+fn identity_color_grading_keeps_the_colors() {
+  let plain = lit_scene().render();
+
+  let mut scene = lit_scene();
+  let size = 16;
+  let lut = scene.insert_texture((size * size, size), |x, y| {
+    let level = |value: u32| (value * 255 / (size - 1)) as u8;
+    [level(x % size), level(y), level(x / size), 255]
+  });
+  scene.post_processing(&[&ColorGrading::builder().lut(lut).build()]);
+
+  let comparison = compare(&plain, &scene.render(), 4);
+  assert_eq!(comparison.mismatched, 0);
+}
+
+#[test]
+fn bloom_spreads_bright_pixels_around_them() {
+  let bright_scene = || {
+    let mut scene = GoldenScene::new(WIDTH, HEIGHT);
+    scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+    scene.insert_cuboid(&BasicMaterial::with_color(Vec4::new(1.0, 1.0, 1.0, 1.0)));
+    scene
+  };
+
+  let plain = bright_scene().render();
+  let mut scene = bright_scene();
+  scene.post_processing(&[&Bloom::builder().build()]);
+  let bloomed = scene.render();
+
+  // the background right next to the cuboid lights up, while the corners stay dark
+  let brighter = plain
+    .pixels()
+    .chunks_exact(4)
+    .zip(bloomed.pixels().chunks_exact(4))
+    .filter(|(plain, bloomed)| bloomed[0] > plain[0].saturating_add(8))
+    .count();
+  assert!(brighter > 0);
+  assert_eq!(pixel(&bloomed, 0, 0), pixel(&plain, 0, 0));
+}
+
+fn hdr_scene(color: Vec4) -> GoldenScene {
+  let mut scene = GoldenScene::new(WIDTH, HEIGHT);
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+  scene.insert_cuboid(&BasicMaterial::with_color(color));
+  scene
+}
+
+#[test]
+fn tone_mapping_keeps_highlights_below_white() {
+  let center = |scene: &mut GoldenScene| pixel(&scene.render(), WIDTH / 2, HEIGHT / 2);
+
+  let mut scene = hdr_scene(Vec4::new(4.0, 2.0, 1.0, 1.0));
+  assert_eq!(center(&mut scene), [255, 255, 255, 255]);
+
+  for tone_mapping in [
+    ToneMapping::Reinhard,
+    ToneMapping::Aces,
+    ToneMapping::AgX,
+    ToneMapping::Filmic,
+  ] {
+    scene.scene.set_tone_mapping(tone_mapping);
+    let [red, green, blue, _] = center(&mut scene);
+    assert!(red < 255, "{tone_mapping:?} clipped the highlight");
+    assert!(red > green && green > blue, "{tone_mapping:?} lost the hue");
+  }
+}
+
+#[test]
+fn exposure_scales_the_frame() {
+  let plain = hdr_scene(Vec4::new(0.25, 0.25, 0.25, 1.0)).render();
+
+  // the background is scaled as well
+  let mut scene = hdr_scene(Vec4::new(1.0, 1.0, 1.0, 1.0));
+  scene.scene.set_color(Vec4::new(0.4, 0.4, 0.4, 1.0));
+  scene.scene.set_exposure(0.25);
+
+  assert_eq!(compare(&plain, &scene.render(), 1).mismatched, 0);
+}
+
+/// Halves the colors of the frame, before or after the tone mapping.
+struct Halve(PostStage);
+
+impl PostEffect for Halve {
+  fn passes(&self) -> Vec<PostPass> {
+    vec![PostPass::builder()
+      .shader(
+        "@fragment
+        fn fs_main(in: PostVertexOutput) -> @location(0) vec4<f32> {
+          let color = textureSampleLevel(previous, post_sampler, in.uv, 0.0);
+          return vec4<f32>(color.rgb * 0.5, color.a);
+        }",
+      )
+      .build()]
+  }
+
+  fn stage(&self) -> PostStage {
+    self.0
+  }
+}
+
+#[test]
+fn ldr_effects_see_the_tone_mapped_frame() {
+  let halved = |stage: PostStage| {
+    let mut scene = hdr_scene(Vec4::new(4.0, 4.0, 4.0, 1.0));
+    scene.post_processing(&[&Halve(stage)]);
+    scene.render()
+  };
+
+  // halved before the tone mapping the cuboid is still clipped to white
+  assert_eq!(
+    pixel(&halved(PostStage::Hdr), WIDTH / 2, HEIGHT / 2),
+    [255, 255, 255, 255]
+  );
+
+  // halved after it the clipped white ends up where a cuboid of half the intensity is
+  let plain = hdr_scene(Vec4::new(0.5, 0.5, 0.5, 1.0)).render();
+  assert_eq!(
+    pixel(&halved(PostStage::Ldr), WIDTH / 2, HEIGHT / 2),
+    pixel(&plain, WIDTH / 2, HEIGHT / 2)
+  );
+}
+
+#[test]
+fn ldr_effects_follow_the_hdr_ones_wherever_they_are_listed() {
+  let render = |effects: &[&dyn PostEffect]| {
+    let mut scene = lit_scene();
+    scene.post_processing(effects);
+    scene.render()
+  };
+
+  let bloom = Bloom::builder().threshold(0.2).build();
+  let vignette = Vignette::builder().intensity(0.8).build();
+
+  assert_eq!(render(&[&vignette, &bloom]), render(&[&bloom, &vignette]));
+}
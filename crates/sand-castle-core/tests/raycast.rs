@@ -0,0 +1,127 @@
+use sand_castle_core::{
+  raycast::Ray,
+  resource::{
+    camera::perspective::PerspectiveCamera,
+    object_3d::{Scale, SceneTransform},
+    Resource,
+  },
+  test_support::{cuboid_material, cuboid_scene, GoldenScene},
+  Quat, Vec2, Vec3,
+};
+
+fn assert_near(actual: Vec3, expected: Vec3) {
+  assert!(
+    actual.abs_diff_eq(expected, 1e-4),
+    "expected {expected}, got {actual}"
+  );
+}
+
+#[test]
+fn raycast_returns_sorted_hits() {
+  let mut scene = GoldenScene::new(32, 32);
+  let mesh = scene.insert_cuboid(&cuboid_material());
+
+  let hits = scene.scene.raycast(
+    &scene.geometry_loader,
+    Vec3::new(0.2, 0.3, 5.0),
+    Vec3::new(0.0, 0.0, -2.0),
+  );
+
+  assert_eq!(hits.len(), 2);
+  assert!(hits.iter().all(|hit| *hit.id() == mesh.id()));
+
+  assert!((hits[0].distance() - 4.0).abs() < 1e-4);
+  assert_near(*hits[0].point(), Vec3::new(0.2, 0.3, 1.0));
+  assert_near(*hits[0].normal(), Vec3::Z);
+
+  assert!((hits[1].distance() - 6.0).abs() < 1e-4);
+  assert_near(*hits[1].normal(), Vec3::NEG_Z);
+}
+
+#[test]
+fn raycast_uses_world_transforms() {
+  let mut scene = GoldenScene::new(32, 32);
+  let mut mesh = scene.insert_cuboid(&cuboid_material());
+
+  mesh.update_pos(&mut scene.scene, &scene.renderer, Vec3::new(3.0, 0.0, 0.0));
+  mesh.update_scale(
+    &mut scene.scene,
+    &scene.renderer,
+    Scale {
+      width: 2.0,
+      height: 1.0,
+      depth: 1.0,
+    },
+  );
+
+  let cast = |x: f32| {
+    scene
+      .scene
+      .raycast(&scene.geometry_loader, Vec3::new(x, 0.0, 5.0), Vec3::NEG_Z)
+  };
+
+  assert_eq!(cast(0.0).len(), 0);
+  assert_eq!(cast(4.5).len(), 2);
+  assert_eq!(cast(5.5).len(), 0);
+
+  // the side faces are stretched, their normals have to stay unit length and axis aligned
+  let side = scene.scene.raycast(
+    &scene.geometry_loader,
+    Vec3::new(10.0, 0.5, 0.5),
+    Vec3::NEG_X,
+  );
+
+  assert!((side[0].distance() - 5.0).abs() < 1e-4);
+  assert_near(*side[0].normal(), Vec3::X);
+}
+
+#[test]
+fn camera_ray_goes_through_the_viewport() {
+  let position = Vec3::new(0.0, 0.0, 5.0);
+
+  let camera = PerspectiveCamera::builder()
+    .yaw(-90.0f32.to_radians())
+    .pitch(0.0)
+    .fov(1.0)
+    .aspect_ratio(2.0)
+    .position(position)
+    .rotation(Quat::IDENTITY)
+    .scale(Default::default())
+    .build();
+
+  let center = Ray::from_camera(&camera, Vec2::new(0.5, 0.5));
+  assert_near(*center.direction(), Vec3::NEG_Z);
+  // starts on the near plane, `0.1` in front of the camera
+  assert!((center.origin().distance(position) - 0.1).abs() < 1e-3);
+
+  let top_left = Ray::from_camera(&camera, Vec2::ZERO);
+  assert!(top_left.direction().x < 0.0);
+  assert!(top_left.direction().y > 0.0);
+  // with a 2:1 aspect ratio the horizontal extent is twice the vertical one
+  assert!((top_left.direction().x / top_left.direction().y + 2.0).abs() < 1e-3);
+}
+
+#[test]
+fn camera_ray_hits_what_the_scene_camera_sees() {
+  let mut scene = cuboid_scene();
+  let mesh = scene.insert_cuboid(&cuboid_material());
+
+  let center = scene.scene.camera_ray(Vec2::new(0.5, 0.5));
+  let hits = scene.scene.raycast(
+    &scene.geometry_loader,
+    *center.origin(),
+    *center.direction(),
+  );
+
+  assert_eq!(hits.first().map(|hit| *hit.id()), Some(mesh.id()));
+
+  let corner = scene.scene.camera_ray(Vec2::new(0.02, 0.02));
+  assert!(scene
+    .scene
+    .raycast(
+      &scene.geometry_loader,
+      *corner.origin(),
+      *corner.direction()
+    )
+    .is_empty());
+}
@@ -0,0 +1,63 @@
+use std::sync::{Arc, Mutex};
+
+use sand_castle_core::renderer::{Backend, DeviceLostReason, Offscreen, Renderer};
+use wgpu::{Maintain, TextureFormat, TextureUsages};
+
+#[test]
+fn auto_backend_resolves_to_active_backend() {
+  let renderer = pollster::block_on(
+    Renderer::builder()
+      .backend(Backend::Auto)
+      .offscreen(Offscreen::new(16, 16))
+      .build(),
+  )
+  .expect("no adapter available for `Backend::Auto`");
+
+  assert_ne!(*renderer.active_backend(), Backend::Auto);
+}
+
+#[test]
+fn scene_is_drawn_in_a_renderable_format() {
+  let renderer = pollster::block_on(
+    Renderer::builder()
+      .backend(Backend::Auto)
+      .offscreen(Offscreen::new(16, 16))
+      .build(),
+  )
+  .expect("no adapter available for `Backend::Auto`");
+
+  let format = *renderer.hdr_format();
+  assert!([TextureFormat::Rgba16Float, TextureFormat::Rgba8Unorm].contains(&format));
+  assert!(renderer
+    .adapter()
+    .get_texture_format_features(format)
+    .allowed_usages
+    .contains(TextureUsages::RENDER_ATTACHMENT));
+}
+
+#[test]
+fn destroyed_device_is_lost() {
+  let reasons = Arc::new(Mutex::new(vec![]));
+
+  let renderer = pollster::block_on({
+    let reasons = reasons.clone();
+
+    Renderer::builder()
+      .backend(Backend::Auto)
+      .offscreen(Offscreen::new(16, 16))
+      .on_device_lost(move |reason, _| reasons.lock().unwrap().push(reason))
+      .build()
+  })
+  .expect("no adapter available for `Backend::Auto`");
+
+  assert!(!renderer.is_lost());
+
+  renderer.device().destroy();
+  renderer.device().poll(Maintain::Wait);
+
+  assert!(renderer.is_lost());
+  assert_eq!(*reasons.lock().unwrap(), [DeviceLostReason::Destroyed]);
+
+  let rebuilt = pollster::block_on(renderer.rebuild()).expect("failed to rebuild `Renderer`");
+  assert!(!rebuilt.is_lost());
+}
@@ -0,0 +1,55 @@
+use sand_castle_core::{
+  resource::lighting::material::basic::BasicMaterial,
+  test_support::{headless_renderer_with, GoldenScene},
+  Vec3, Vec4,
+};
+use wgpu::{Features, Maintain};
+
+#[test]
+fn render_reports_draw_stats() {
+  let mut scene = GoldenScene::new(32, 32);
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+
+  let material = BasicMaterial::with_color(Vec4::new(0.2, 0.6, 0.9, 1.0));
+  scene.insert_cuboid(&material);
+  scene.insert_cuboid(&material);
+
+  let stats = scene.scene.render(&scene.renderer);
+
+  // the two cuboids and the fullscreen triangle of the tone mapping pass
+  assert_eq!(*stats.draw_calls(), 3);
+  assert_eq!(*stats.triangles(), 25);
+  // every inserted material gets its own pipeline, which rebinds all six groups, the tone mapping
+  // binds two
+  assert_eq!(*stats.pipeline_switches(), 3);
+  assert_eq!(*stats.bind_group_switches(), 14);
+  assert!(*stats.buffer_memory() > 0);
+  assert!(*stats.texture_memory() >= 32 * 32 * 4);
+  assert_eq!(*stats.gpu_time(), None);
+}
+
+#[test]
+fn gpu_timing_reports_when_supported() {
+  let mut scene = GoldenScene::with_renderer(headless_renderer_with(32, 32, |builder| {
+    builder.gpu_timing(true)
+  }));
+  scene.look_at(Vec3::new(2.0, 1.6, 2.6), Vec3::ZERO);
+  scene.insert_cuboid(&BasicMaterial::with_color(Vec4::new(0.2, 0.6, 0.9, 1.0)));
+
+  let supported = scene
+    .renderer
+    .device()
+    .features()
+    .contains(Features::TIMESTAMP_QUERY);
+
+  // results are read back without blocking, so they show up a few frames later
+  let gpu_time = (0..10)
+    .map(|_| {
+      scene.renderer.device().poll(Maintain::Wait);
+      *scene.scene.render(&scene.renderer).gpu_time()
+    })
+    .last()
+    .flatten();
+
+  assert_eq!(gpu_time.is_some(), supported);
+}
@@ -1,10 +1,12 @@
 use std::sync::Arc;
 
-use leptos::{html, prelude::*};
-use leptos_use::{use_raf_fn, use_resize_observer, utils::Pausable};
-use sand_castle_core::{renderer::Renderer, scene::Scene as CoreScene};
+use leptos::{ev, html, prelude::*};
+use leptos_use::{use_event_listener, use_raf_fn, use_resize_observer};
+use sand_castle_core::{renderer::Renderer, scene::Scene as CoreScene, stats::RenderStats};
 
-pub use sand_castle_core::renderer::Backend;
+use crate::pointer::{use_pointer_events, PointerTarget};
+
+pub use sand_castle_core::renderer::{Backend, CompositeAlphaMode, PresentMode};
 
 use std::ops::Deref;
 
@@ -12,71 +14,181 @@ use std::ops::Deref;
 pub struct RendererContextValue {
   pub renderer: Signal<Option<Arc<Renderer>>, LocalStorage>,
   pub scenes: RwSignal<Vec<RwSignal<Option<CoreScene>, LocalStorage>>, LocalStorage>,
+  /// Scenes hit-tested against pointer events on the canvas.
+  pub pointer_targets: RwSignal<Vec<PointerTarget>, LocalStorage>,
+  /// Stats of all scenes drawn in the last frame, see `use_render_stats`.
+  pub stats: RwSignal<RenderStats>,
+  /// Milliseconds elapsed between the last two frames.
+  pub frame_time: RwSignal<f64>,
 }
 
 #[component]
 pub fn Canvas(
   #[prop(optional, into)] backend: MaybeProp<Backend>,
+  #[prop(optional, into)] present_mode: MaybeProp<PresentMode>,
+  #[prop(optional, into)] alpha_mode: MaybeProp<CompositeAlphaMode>,
+  /// MSAA sample count, `4` enables antialiasing.
+  #[prop(optional, into)]
+  sample_count: MaybeProp<u32>,
+  /// Defaults to `window.devicePixelRatio`.
+  #[prop(optional, into)]
+  pixel_ratio: MaybeProp<f64>,
+  /// Called when the GPU device or WebGL context is lost, the `Renderer` and scenes are rebuilt
+  /// once it can be recreated.
+  #[prop(optional, into)]
+  on_context_lost: Option<Callback<()>>,
 
   #[prop(optional)] node_ref: NodeRef<html::Canvas>,
   #[prop(optional)] children: Option<Children>,
 ) -> impl IntoView {
   let scenes = RwSignal::new_local(vec![]);
+  let pointer_targets = RwSignal::new_local(vec![]);
+  let stats = RwSignal::new(RenderStats::default());
+  let frame_time = RwSignal::new(0.0);
+
+  // bumped to rebuild the `Renderer` after the device or context was lost
+  let generation = RwSignal::new(0u32);
+  let lost = RwSignal::new(false);
 
   let renderer = AsyncDerived::new_unsync(move || {
     let backend = backend.clone();
     async move {
+      generation.track();
+
       let backend = backend.get();
+      let present_mode = present_mode.get();
+      let alpha_mode = alpha_mode.get();
+      let sample_count = sample_count.get();
+      let pixel_ratio = pixel_ratio
+        .get()
+        .unwrap_or_else(|| window().device_pixel_ratio());
       let canvas = node_ref.get();
 
       let canvas = canvas?;
-      let renderer = Renderer::builder();
-
-      let renderer = if let Some(backend) = backend {
-        renderer.backend(backend)
-      } else {
-        renderer
-      };
-
-      Some(Arc::new(
-        renderer
-          .canvas(canvas.clone())
-          .build()
-          .await
-          .expect("Failed to build `Renderer`"),
-      ))
+      let mut renderer = Renderer::builder().pixel_ratio(pixel_ratio);
+
+      if let Some(backend) = backend {
+        renderer = renderer.backend(backend);
+      }
+
+      if let Some(present_mode) = present_mode {
+        renderer = renderer.present_mode(present_mode);
+      }
+
+      if let Some(alpha_mode) = alpha_mode {
+        renderer = renderer.alpha_mode(alpha_mode);
+      }
+
+      if let Some(sample_count) = sample_count {
+        renderer = renderer.sample_count(sample_count);
+      }
+
+      match renderer.canvas(canvas.clone()).build().await {
+        Ok(renderer) => Some(Arc::new(renderer)),
+        Err(err) => {
+          leptos::logging::error!("Failed to build `Renderer`: {err:?}");
+          None
+        }
+      }
     }
   });
 
   let renderer = Signal::derive_local(move || renderer.get().flatten());
 
   Effect::new(move |_| {
-    let Some(renderer) = renderer.get() else {
+    if renderer.with(Option::is_some) {
+      lost.set(false);
+    }
+  });
+
+  let on_lost = move || {
+    if lost.get_untracked() {
+      return;
+    }
+
+    lost.set(true);
+
+    if let Some(on_context_lost) = on_context_lost {
+      on_context_lost.run(());
+    }
+  };
+
+  _ = use_raf_fn(move |args| {
+    let Some(renderer) = renderer.get_untracked() else {
       return;
     };
 
-    _ = use_raf_fn(move |_| {
-      scenes.with(|scenes: &Vec<RwSignal<Option<CoreScene>, LocalStorage>>| {
-        for scene in scenes {
-          scene.with(|scene| {
-            if let Some(scene) = scene {
-              scene.render(&renderer);
-            }
-          });
-        }
-      });
+    if lost.get_untracked() {
+      return;
+    }
+
+    // WebGPU devices report the loss through wgpu and can be recreated right away
+    if renderer.is_lost() {
+      on_lost();
+      generation.update(|generation| *generation += 1);
+      return;
+    }
+
+    let mut frame_stats = RenderStats::default();
+
+    scenes.with_untracked(|scenes: &Vec<RwSignal<Option<CoreScene>, LocalStorage>>| {
+      for scene in scenes {
+        scene.update_untracked(|scene| {
+          if let Some(scene) = scene {
+            frame_stats += scene.render(&renderer);
+          }
+        });
+      }
     });
+
+    stats.set(frame_stats);
+    frame_time.set(args.delta);
   });
 
+  // WebGL contexts can only be recreated once the browser restores them, which it only does when
+  // the default action of `webglcontextlost` is prevented
+  _ = use_event_listener(
+    node_ref,
+    ev::Custom::<ev::Event>::new("webglcontextlost"),
+    move |event| {
+      event.prevent_default();
+      on_lost();
+    },
+  );
+
+  _ = use_event_listener(
+    node_ref,
+    ev::Custom::<ev::Event>::new("webglcontextrestored"),
+    move |_| generation.update(|generation| *generation += 1),
+  );
+
+  use_pointer_events(node_ref, pointer_targets);
+
   use_resize_observer(node_ref, move |_, _| {
     renderer.with(|renderer| {
       if let Some(renderer) = renderer {
         renderer.resize();
+
+        scenes.with_untracked(|scenes| {
+          for scene in scenes {
+            scene.update_untracked(|scene| {
+              if let Some(scene) = scene {
+                scene.resize(renderer);
+              }
+            });
+          }
+        });
       }
     });
   });
 
-  provide_context(RendererContextValue { renderer, scenes });
+  provide_context(RendererContextValue {
+    renderer,
+    scenes,
+    pointer_targets,
+    stats,
+    frame_time,
+  });
 
   view! {
     <canvas node_ref=node_ref>
@@ -0,0 +1,48 @@
+use leptos::prelude::*;
+use sand_castle_core::Vec3;
+
+use crate::scene::SceneContextValue;
+
+pub use sand_castle_core::fog::{Fog as FogMode, FOG_SHADER};
+
+#[component]
+pub fn Fog(
+  /// How the fog thickens with the distance to the camera, the scene stays clear while `None`.
+  #[prop(optional, into)]
+  mode: MaybeProp<FogMode>,
+  /// Linear color the fog fades into, usually the color of the `Scene` too.
+  #[prop(default = Vec3::ONE.into(), into)]
+  color: MaybeSignal<Vec3>,
+  /// How fast the fog thins out above `y = 0`, `0.0` keeps it as thick at every height.
+  #[prop(default = 0.0_f32.into(), into)]
+  height_falloff: MaybeSignal<f32>,
+) -> impl IntoView {
+  let SceneContextValue {
+    scene, renderer, ..
+  } = use_context().expect("`Fog` must be used in a `Scene` component");
+
+  Effect::new(move |_| {
+    // the scene is rebuilt along with the `Renderer`
+    if renderer.get().is_none() {
+      return;
+    }
+
+    let (mode, color, height_falloff) = (mode.get(), color.get(), height_falloff.get());
+
+    scene.update(|scene| {
+      if let Some(scene) = scene {
+        scene.set_fog(mode);
+        scene.set_fog_color(color);
+        scene.set_fog_height_falloff(height_falloff);
+      }
+    });
+  });
+
+  on_cleanup(move || {
+    scene.update(|scene| {
+      if let Some(scene) = scene {
+        scene.set_fog(None);
+      }
+    });
+  });
+}
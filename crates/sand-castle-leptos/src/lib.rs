@@ -1,5 +1,9 @@
 pub use sand_castle_core::{Quat, Vec2, Vec3, Vec4};
 
 pub mod canvas;
+pub mod fog;
+pub mod pointer;
+pub mod post_processing;
 pub mod resource;
 pub mod scene;
+pub mod stats;
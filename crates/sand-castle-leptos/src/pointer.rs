@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use leptos::{ev, html, prelude::*, web_sys::MouseEvent};
+use leptos_use::use_event_listener;
+use sand_castle_core::{
+  resource::{loader::geometry::GeometryLoader, Id},
+  scene::Scene as CoreScene,
+  Vec2, Vec3,
+};
+
+/// Payload of the pointer event props of `Mesh`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeshPointerEvent {
+  /// The mesh that was hit.
+  pub id: Id,
+  pub point: Vec3,
+  pub distance: f32,
+  pub normal: Vec3,
+  pub uv: Vec2,
+  /// Pointer position in normalized device coordinates.
+  pub ndc: Vec2,
+}
+
+pub type MeshPointerCallback = Callback<(MeshPointerEvent,)>;
+
+#[derive(Clone, Copy, Default)]
+pub struct MeshPointerHandlers {
+  pub on_click: Option<MeshPointerCallback>,
+  pub on_pointer_move: Option<MeshPointerCallback>,
+  pub on_pointer_enter: Option<MeshPointerCallback>,
+  pub on_pointer_leave: Option<MeshPointerCallback>,
+}
+
+impl MeshPointerHandlers {
+  pub fn is_empty(&self) -> bool {
+    self.on_click.is_none()
+      && self.on_pointer_move.is_none()
+      && self.on_pointer_enter.is_none()
+      && self.on_pointer_leave.is_none()
+  }
+}
+
+/// A scene whose meshes are hit-tested against pointer events on the `Canvas`.
+#[derive(Clone, Copy, PartialEq)]
+pub struct PointerTarget {
+  pub scene: RwSignal<Option<CoreScene>, LocalStorage>,
+  pub geometry_loader: RwSignal<Option<GeometryLoader>, LocalStorage>,
+  pub handlers: RwSignal<HashMap<Id, MeshPointerHandlers>, LocalStorage>,
+}
+
+impl PointerTarget {
+  fn handlers(&self, id: Id) -> Option<MeshPointerHandlers> {
+    self
+      .handlers
+      .with_untracked(|handlers| handlers.get(&id).copied())
+  }
+
+  /// Nearest hit of a ray through `screen` against the meshes of the scene.
+  fn hit_test(&self, screen: Vec2) -> Option<MeshPointerEvent> {
+    if self.handlers.with_untracked(HashMap::is_empty) {
+      return None;
+    }
+
+    self.scene.with_untracked(|scene| {
+      self.geometry_loader.with_untracked(|geometry_loader| {
+        let (scene, geometry_loader) = (scene.as_ref()?, geometry_loader.as_ref()?);
+
+        let ray = scene.camera_ray(screen);
+        let hit = scene
+          .raycast(geometry_loader, *ray.origin(), *ray.direction())
+          .into_iter()
+          .next()?;
+
+        Some(MeshPointerEvent {
+          id: *hit.id(),
+          point: *hit.point(),
+          distance: *hit.distance(),
+          normal: *hit.normal(),
+          uv: *hit.uv(),
+          ndc: Vec2::new(screen.x * 2.0 - 1.0, 1.0 - screen.y * 2.0),
+        })
+      })
+    })
+  }
+}
+
+type Hovered = Option<(PointerTarget, MeshPointerEvent)>;
+
+/// Dispatches the DOM pointer events of the canvas to the nearest mesh under the pointer.
+pub(crate) fn use_pointer_events(
+  node_ref: NodeRef<html::Canvas>,
+  targets: RwSignal<Vec<PointerTarget>, LocalStorage>,
+) {
+  let hovered = StoredValue::new(Hovered::None);
+
+  let hit_test = move |event: &MouseEvent| -> Hovered {
+    let canvas = node_ref.get_untracked()?;
+    let (width, height) = (canvas.client_width(), canvas.client_height());
+
+    if width <= 0 || height <= 0 {
+      return None;
+    }
+
+    let screen = Vec2::new(
+      event.offset_x() as f32 / width as f32,
+      event.offset_y() as f32 / height as f32,
+    );
+
+    targets.with_untracked(|targets| {
+      targets
+        .iter()
+        .filter_map(|target| target.hit_test(screen).map(|hit| (*target, hit)))
+        .min_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance))
+    })
+  };
+
+  let dispatch =
+    move |hit: Hovered, callback: fn(MeshPointerHandlers) -> Option<MeshPointerCallback>| {
+      if let Some((target, event)) = hit {
+        if let Some(callback) = target.handlers(event.id).and_then(callback) {
+          callback.run((event,));
+        }
+      }
+    };
+
+  let leave = move || {
+    dispatch(hovered.get_value(), |handlers| handlers.on_pointer_leave);
+    hovered.set_value(None);
+  };
+
+  _ = use_event_listener(node_ref, ev::click, move |event| {
+    dispatch(hit_test(&event), |handlers| handlers.on_click);
+  });
+
+  _ = use_event_listener(node_ref, ev::pointermove, move |event| {
+    let hit = hit_test(&event);
+
+    let hovered_id = hovered.with_value(|hovered| hovered.map(|(_, event)| event.id));
+    if hovered_id != hit.map(|(_, event)| event.id) {
+      leave();
+      dispatch(hit, |handlers| handlers.on_pointer_enter);
+    }
+
+    dispatch(hit, |handlers| handlers.on_pointer_move);
+    hovered.set_value(hit);
+  });
+
+  _ = use_event_listener(node_ref, ev::pointerleave, move |_| leave());
+}
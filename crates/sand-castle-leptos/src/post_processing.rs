@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use leptos::prelude::*;
+use sand_castle_core::post_processing::PostProcessingError;
+
+use crate::scene::SceneContextValue;
+
+pub use sand_castle_core::post_processing::{
+  tone_mapping::ToneMapping, PostEffect, PostPass, PRELUDE,
+};
+
+pub mod bloom;
+pub mod color_grading;
+pub mod fxaa;
+pub mod sharpen;
+pub mod vignette;
+
+/// Effect of a child of `PostProcessing`, `None` until it is first built.
+pub type PostEffectSignal = RwSignal<Option<Arc<dyn PostEffect>>, LocalStorage>;
+
+#[derive(Clone, Copy)]
+pub struct PostProcessingContextValue {
+  /// Effects of the children, in the order they were created.
+  pub effects: RwSignal<Vec<PostEffectSignal>, LocalStorage>,
+}
+
+/// Applies the effects of its children to the frame of the surrounding `Scene`, in order within
+/// each `PostStage`.
+#[component]
+pub fn PostProcessing(children: Children) -> impl IntoView {
+  let SceneContextValue {
+    scene,
+    renderer,
+    texture_loader,
+    ..
+  } = use_context().expect("`PostProcessing` must be used in a `Scene` component");
+
+  let effects = RwSignal::new_local(vec![]);
+
+  provide_context(PostProcessingContextValue { effects });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let effects = effects.with(|effects| {
+      effects
+        .iter()
+        .filter_map(|effect: &PostEffectSignal| effect.get())
+        .collect::<Vec<_>>()
+    });
+    let effects = effects.iter().map(Arc::as_ref).collect::<Vec<_>>();
+
+    texture_loader.with(|texture_loader| {
+      let Some(texture_loader) = texture_loader else {
+        return;
+      };
+
+      scene.update(|scene| {
+        let Some(scene) = scene else {
+          return;
+        };
+
+        match scene.set_post_processing(&renderer, texture_loader, &effects) {
+          Ok(()) => {}
+          // still loading, the effect runs again when the texture is inserted
+          Err(PostProcessingError::MissingTexture(_)) => {}
+        }
+      });
+    });
+  });
+
+  on_cleanup(move || {
+    let Some(renderer) = renderer.get_untracked() else {
+      return;
+    };
+
+    texture_loader.with_untracked(|texture_loader| {
+      let Some(texture_loader) = texture_loader else {
+        return;
+      };
+
+      scene.update(|scene| {
+        if let Some(scene) = scene {
+          let _ = scene.set_post_processing(&renderer, texture_loader, &[]);
+        }
+      });
+    });
+  });
+
+  children()
+}
+
+/// Adds the effect built by `effect` to the surrounding `PostProcessing`, after the ones of the
+/// components created before. The signals read by `effect` are tracked, the chain is rebuilt
+/// whenever they change.
+pub fn use_post_effect<E: PostEffect + 'static>(effect: impl Fn() -> E + 'static) {
+  let PostProcessingContextValue { effects } =
+    use_context().expect("post effects must be used in a `PostProcessing` component");
+
+  let entry = RwSignal::new_local(None);
+
+  effects.update(|effects| effects.push(entry));
+
+  Effect::new(move |_| {
+    let effect: Arc<dyn PostEffect> = Arc::new(effect());
+    entry.set(Some(effect));
+  });
+
+  on_cleanup(move || {
+    effects.update(|effects| effects.retain(|effect| *effect != entry));
+  });
+}
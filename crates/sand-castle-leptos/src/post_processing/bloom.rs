@@ -0,0 +1,26 @@
+use leptos::prelude::*;
+use sand_castle_core::post_processing::bloom::Bloom as CoreBloom;
+
+use super::use_post_effect;
+
+/// Makes the brightest parts of the frame glow.
+#[component]
+pub fn Bloom(
+  /// Brightness above which pixels glow.
+  #[prop(default = 0.8_f32.into(), into)]
+  threshold: MaybeSignal<f32>,
+  /// Scale of the glow added to the frame.
+  #[prop(default = 1.0_f32.into(), into)]
+  intensity: MaybeSignal<f32>,
+  /// Spread of the blur, in texels of the half resolution targets.
+  #[prop(default = 1.0_f32.into(), into)]
+  radius: MaybeSignal<f32>,
+) -> impl IntoView {
+  use_post_effect(move || {
+    CoreBloom::builder()
+      .threshold(threshold.get())
+      .intensity(intensity.get())
+      .radius(radius.get())
+      .build()
+  });
+}
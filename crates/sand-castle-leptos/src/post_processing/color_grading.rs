@@ -0,0 +1,29 @@
+use leptos::prelude::*;
+use sand_castle_core::{
+  post_processing::color_grading::ColorGrading as CoreColorGrading, resource::texture::TextureId,
+};
+
+use super::use_post_effect;
+
+/// Remaps colors through a lookup table.
+#[component]
+pub fn ColorGrading(
+  /// Table of `n` slices of `n` by `n` texels laid out in a row, usually from
+  /// `use_texture_loader`. Nothing is graded until it is loaded.
+  #[prop(optional, into)]
+  lut: MaybeProp<TextureId>,
+  /// Blend between the original colors at `0.0` and the graded ones at `1.0`.
+  #[prop(default = 1.0_f32.into(), into)]
+  intensity: MaybeSignal<f32>,
+) -> impl IntoView {
+  use_post_effect(move || {
+    let intensity = intensity.get();
+
+    lut.get().map(|lut| {
+      CoreColorGrading::builder()
+        .lut(lut)
+        .intensity(intensity)
+        .build()
+    })
+  });
+}
@@ -0,0 +1,22 @@
+use leptos::prelude::*;
+use sand_castle_core::post_processing::fxaa::Fxaa as CoreFxaa;
+
+use super::use_post_effect;
+
+/// Fast approximate anti-aliasing.
+#[component]
+pub fn Fxaa(
+  /// Longest blur along an edge, in pixels.
+  #[prop(default = 8.0_f32.into(), into)]
+  span: MaybeSignal<f32>,
+  /// Contrast below which an edge is left alone, relative to the brightness around it.
+  #[prop(default = 0.125_f32.into(), into)]
+  threshold: MaybeSignal<f32>,
+) -> impl IntoView {
+  use_post_effect(move || {
+    CoreFxaa::builder()
+      .span(span.get())
+      .threshold(threshold.get())
+      .build()
+  });
+}
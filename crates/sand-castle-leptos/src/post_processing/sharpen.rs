@@ -0,0 +1,14 @@
+use leptos::prelude::*;
+use sand_castle_core::post_processing::sharpen::Sharpen as CoreSharpen;
+
+use super::use_post_effect;
+
+/// Accentuates edges.
+#[component]
+pub fn Sharpen(
+  /// Weight of each of the four neighbours, `0.0` leaves the frame untouched.
+  #[prop(default = 0.25_f32.into(), into)]
+  amount: MaybeSignal<f32>,
+) -> impl IntoView {
+  use_post_effect(move || CoreSharpen::builder().amount(amount.get()).build());
+}
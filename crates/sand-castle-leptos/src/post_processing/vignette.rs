@@ -0,0 +1,26 @@
+use leptos::prelude::*;
+use sand_castle_core::post_processing::vignette::Vignette as CoreVignette;
+
+use super::use_post_effect;
+
+/// Darkens the frame towards its corners.
+#[component]
+pub fn Vignette(
+  /// How dark the corners get, from `0.0` to `1.0`.
+  #[prop(default = 0.5_f32.into(), into)]
+  intensity: MaybeSignal<f32>,
+  /// Distance from the center where the darkening is complete, `1.0` at the middle of the edges.
+  #[prop(default = 1.2_f32.into(), into)]
+  radius: MaybeSignal<f32>,
+  /// Width of the fade towards `radius`.
+  #[prop(default = 0.8_f32.into(), into)]
+  softness: MaybeSignal<f32>,
+) -> impl IntoView {
+  use_post_effect(move || {
+    CoreVignette::builder()
+      .intensity(intensity.get())
+      .radius(radius.get())
+      .softness(softness.get())
+      .build()
+  });
+}
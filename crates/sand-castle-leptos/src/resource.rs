@@ -1,5 +1,7 @@
 pub mod camera;
+pub mod environment;
 pub mod geometry;
+pub mod group;
 pub mod lighting;
 pub mod mesh;
 
@@ -70,7 +70,7 @@ pub fn PerspectiveCamera(
 
       camera.set_yaw(yaw);
 
-      scene.with(|scene| {
+      scene.update_untracked(|scene| {
         if let Some(scene) = scene {
           scene.update_camera(&renderer, camera);
         }
@@ -92,7 +92,7 @@ pub fn PerspectiveCamera(
 
       camera.set_pitch(pitch);
 
-      scene.with(|scene| {
+      scene.update_untracked(|scene| {
         if let Some(scene) = scene {
           scene.update_camera(&renderer, camera);
         }
@@ -114,7 +114,7 @@ pub fn PerspectiveCamera(
 
       camera.set_pos(position);
 
-      scene.with(|scene| {
+      scene.update_untracked(|scene| {
         if let Some(scene) = scene {
           scene.update_camera(&renderer, camera);
         }
@@ -0,0 +1,108 @@
+use leptos::prelude::*;
+use sand_castle_core::resource::environment::{Environment as CoreEnvironment, EnvironmentError};
+
+use crate::scene::SceneContextValue;
+
+pub use sand_castle_core::resource::environment::EnvironmentSource;
+
+#[component]
+pub fn Environment(
+  /// Textures of the environment, usually from `use_texture_loader`. The environment appears
+  /// once all of them are loaded.
+  #[prop(optional, into)]
+  source: MaybeProp<EnvironmentSource>,
+  /// Draws the environment behind all subjects instead of clearing to the scene color.
+  #[prop(default = true.into(), into)]
+  background: MaybeSignal<bool>,
+  /// Scales the lighting of PBR materials by the environment.
+  #[prop(default = 1.0_f32.into(), into)]
+  intensity: MaybeSignal<f32>,
+) -> impl IntoView {
+  let SceneContextValue {
+    scene,
+    renderer,
+    texture_loader,
+    ..
+  } = use_context().expect("`Environment` must be used in a `Scene` component");
+
+  let built = StoredValue::new_local(None::<(EnvironmentSource, bool)>);
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let (source, background) = (source.get(), background.get());
+    let wanted = source.map(|source| (source, background));
+
+    if built.get_value() == wanted {
+      return;
+    }
+
+    let environment = wanted.map(|(source, background)| {
+      CoreEnvironment::builder()
+        .source(source)
+        .background(background)
+        .build()
+    });
+
+    texture_loader.with(|texture_loader| {
+      let Some(texture_loader) = texture_loader else {
+        return;
+      };
+
+      scene.update(|scene| {
+        let Some(scene) = scene else {
+          return;
+        };
+
+        match scene.set_environment(&renderer, texture_loader, environment.as_ref()) {
+          Ok(()) => built.set_value(wanted),
+          // still loading, the effect runs again when the texture is inserted
+          Err(EnvironmentError::MissingTexture(_)) => {}
+          Err(err) => leptos::logging::warn!("Failed to set `Environment`: {err:?}"),
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let intensity = intensity.get();
+
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    scene.update(|scene| {
+      if let Some(scene) = scene {
+        scene.set_environment_intensity(&renderer, intensity);
+      }
+    });
+  });
+
+  on_cleanup(move || {
+    let Some(renderer) = renderer.get_untracked() else {
+      return;
+    };
+
+    scene.update(|scene| {
+      if let Some(scene) = scene {
+        scene.set_environment_intensity(&renderer, 1.0);
+      }
+    });
+
+    texture_loader.with_untracked(|texture_loader| {
+      let Some(texture_loader) = texture_loader else {
+        return;
+      };
+
+      scene.update(|scene| {
+        if let Some(scene) = scene {
+          let _ = scene.set_environment(&renderer, texture_loader, None);
+        }
+      });
+    });
+  });
+
+  ()
+}
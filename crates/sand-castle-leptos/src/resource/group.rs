@@ -0,0 +1,129 @@
+use leptos::prelude::*;
+
+use sand_castle_core::{
+  resource::{
+    object_3d::{group::Group as CoreGroup, Scale, SceneTransform},
+    Resource,
+  },
+  Quat, Vec3,
+};
+
+use crate::scene::SceneContextValue;
+
+#[derive(Clone, Copy)]
+pub struct GroupContextValue {
+  pub group: RwSignal<Option<CoreGroup>, LocalStorage>,
+}
+
+/// Applies its transform to the `Mesh` and `Group` components nested inside of it.
+#[component]
+pub fn Group(
+  #[prop(optional, into)] position: MaybeSignal<Vec3>,
+  #[prop(optional, into)] rotation: MaybeSignal<Quat>,
+  #[prop(optional, into)] scale: MaybeSignal<Scale>,
+
+  children: Children,
+) -> impl IntoView {
+  let group = RwSignal::new_local(None);
+
+  let SceneContextValue {
+    scene, renderer, ..
+  } = use_context().expect("`Group` must be used in a `Scene` component");
+
+  let parent = use_context::<GroupContextValue>();
+
+  let position = Memo::new(move |_| position.get());
+  let rotation = Memo::new(move |_| rotation.get());
+  let scale = Memo::new(move |_| scale.get());
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let group_desc = CoreGroup::builder()
+      .position(position.get_untracked())
+      .rotation(rotation.get_untracked())
+      .scale(scale.get_untracked())
+      .build();
+
+    let parent = parent.and_then(|GroupContextValue { group }| {
+      group.with_untracked(|group| group.as_ref().map(Resource::id))
+    });
+
+    scene.update_untracked(|scene| {
+      if let Some(scene) = scene {
+        scene.insert_group(&group_desc);
+        scene.set_parent(&renderer, group_desc.id(), parent);
+      }
+    });
+
+    group.set(Some(group_desc));
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let position = position.get();
+
+    group.update_untracked(|group| {
+      scene.update_untracked(|scene| {
+        if let (Some(scene), Some(group)) = (scene, group) {
+          group.update_pos(scene, &renderer, position);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let rotation = rotation.get();
+
+    group.update_untracked(|group| {
+      scene.update_untracked(|scene| {
+        if let (Some(scene), Some(group)) = (scene, group) {
+          group.update_rot(scene, &renderer, rotation);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let scale = scale.get();
+
+    group.update_untracked(|group| {
+      scene.update_untracked(|scene| {
+        if let (Some(scene), Some(group)) = (scene, group) {
+          group.update_scale(scene, &renderer, scale);
+        }
+      });
+    });
+  });
+
+  on_cleanup(move || {
+    let Some(renderer) = renderer.get_untracked() else {
+      return;
+    };
+
+    group.with_untracked(|group| {
+      scene.update_untracked(|scene| {
+        if let (Some(scene), Some(group)) = (scene, group) {
+          scene.remove(&renderer, group);
+        }
+      });
+    });
+  });
+
+  provide_context(GroupContextValue { group });
+
+  children().into_view()
+}
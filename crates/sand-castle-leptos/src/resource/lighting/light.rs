@@ -1,4 +1,8 @@
+pub use sand_castle_core::resource::lighting::light::shadow::Shadow;
+
 pub mod ambient_light;
 pub mod directional_light;
+pub mod hemisphere_light;
 pub mod point_light;
+pub mod rect_area_light;
 pub mod spot_light;
@@ -2,7 +2,10 @@ use leptos::prelude::*;
 
 use sand_castle_core::{
   resource::{
-    lighting::light::directional_light::DirectionalLight as CoreDirectionalLight, Resource,
+    lighting::light::{
+      directional_light::DirectionalLight as CoreDirectionalLight, shadow::Shadow,
+    },
+    Resource,
   },
   Vec3,
 };
@@ -13,6 +16,11 @@ use crate::scene::SceneContextValue;
 pub fn DirectionalLight(
   #[prop(default = Vec3::new(1.0, 1.0, 1.0).into(), into)] color: MaybeSignal<Vec3>,
   #[prop(optional, into)] direction: MaybeSignal<Vec3>,
+  /// Illuminance in lux.
+  #[prop(default = 1.0_f32.into(), into)]
+  intensity: MaybeSignal<f32>,
+  #[prop(optional, into)] cast_shadow: MaybeSignal<bool>,
+  #[prop(optional, into)] shadow: MaybeSignal<Shadow>,
 ) -> impl IntoView {
   let directional_light = RwSignal::<Option<CoreDirectionalLight>>::new(None);
 
@@ -36,6 +44,9 @@ pub fn DirectionalLight(
     let light = CoreDirectionalLight::builder()
       .direction(direction.get_untracked())
       .color(color.get_untracked())
+      .intensity(intensity.get_untracked())
+      .cast_shadow(cast_shadow.get_untracked())
+      .shadow(shadow.get_untracked())
       .build();
 
     directional_lights.update(|directional_lights| {
@@ -97,6 +108,60 @@ pub fn DirectionalLight(
     });
   });
 
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let cast_shadow = cast_shadow.get();
+    let shadow = shadow.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    directional_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_cast_shadow(cast_shadow);
+      light.set_shadow(shadow);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_directional_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let intensity = intensity.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    directional_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_intensity(intensity);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_directional_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
   on_cleanup(move || {
     directional_lights.update(|directional_lights| {
       if let Some(index_in_storage) = index_in_storage.get() {
@@ -0,0 +1,91 @@
+use leptos::prelude::*;
+use sand_castle_core::{
+  resource::lighting::light::hemisphere_light::HemisphereLight as CoreHemisphereLight, Vec3,
+};
+
+use crate::scene::SceneContextValue;
+
+#[component]
+pub fn HemisphereLight(
+  #[prop(default = Vec3::new(1.0, 1.0, 1.0).into(), into)] sky_color: MaybeSignal<Vec3>,
+  #[prop(default = Vec3::default().into(), into)] ground_color: MaybeSignal<Vec3>,
+  /// Direction of the sky, the ground color is seen from the opposite side.
+  #[prop(default = Vec3::Y.into(), into)]
+  up: MaybeSignal<Vec3>,
+  #[prop(default = 1.0_f32.into(), into)] intensity: MaybeSignal<f32>,
+) -> impl IntoView {
+  let hemisphere_light = RwSignal::<Option<CoreHemisphereLight>>::new(None);
+
+  let SceneContextValue {
+    scene, renderer, ..
+  } = use_context().expect("`HemisphereLight` must be used in a `Scene` component");
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let light = CoreHemisphereLight::builder()
+      .sky_color(sky_color.get_untracked())
+      .ground_color(ground_color.get_untracked())
+      .up(up.get_untracked())
+      .intensity(intensity.get_untracked())
+      .build();
+
+    scene.update(|scene| {
+      if let Some(scene) = scene {
+        scene.update_hemisphere_light(&renderer, &light);
+      }
+    });
+
+    hemisphere_light.set(Some(light));
+  });
+
+  Effect::new(move |_| {
+    let (sky_color, ground_color, up, intensity) = (
+      sky_color.get(),
+      ground_color.get(),
+      up.get(),
+      intensity.get(),
+    );
+
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    if hemisphere_light.with(|light| light.is_none()) {
+      return;
+    }
+
+    hemisphere_light.update_untracked(move |light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_sky_color(sky_color);
+      light.set_ground_color(ground_color);
+      light.set_up(up);
+      light.set_intensity(intensity);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_hemisphere_light(&renderer, light);
+        }
+      });
+    });
+  });
+
+  on_cleanup(move || {
+    let Some(renderer) = renderer.get_untracked() else {
+      return;
+    };
+
+    scene.update(|scene| {
+      if let Some(scene) = scene {
+        scene.update_hemisphere_light(&renderer, &CoreHemisphereLight::default());
+      }
+    });
+  });
+
+  ()
+}
@@ -11,6 +11,15 @@ use crate::scene::SceneContextValue;
 pub fn PointLight(
   #[prop(default = Vec3::new(0.0, 0.0, 0.0).into(), into)] position: MaybeSignal<Vec3>,
   #[prop(default = Vec3::new(1.0, 1.0, 1.0).into(), into)] color: MaybeSignal<Vec3>,
+  /// Luminous intensity in candela.
+  #[prop(default = 1.0_f32.into(), into)]
+  intensity: MaybeSignal<f32>,
+  /// Distance at which the light fades out completely, `0.0` for no cutoff.
+  #[prop(optional, into)]
+  range: MaybeSignal<f32>,
+  /// Exponent of the distance falloff, `2.0` is physically correct inverse-square.
+  #[prop(default = 2.0_f32.into(), into)]
+  decay: MaybeSignal<f32>,
 ) -> impl IntoView {
   let point_light = RwSignal::<Option<CorePointLight>>::new(None);
 
@@ -34,6 +43,9 @@ pub fn PointLight(
     let light = CorePointLight::builder()
       .position(position.get_untracked())
       .color(color.get_untracked())
+      .intensity(intensity.get_untracked())
+      .range(range.get_untracked())
+      .decay(decay.get_untracked())
       .build();
 
     point_lights.update(|point_lights| {
@@ -95,6 +107,84 @@ pub fn PointLight(
     });
   });
 
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let intensity = intensity.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    point_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_intensity(intensity);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_point_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let range = range.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    point_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_range(range);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_point_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let decay = decay.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    point_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_decay(decay);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_point_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
   on_cleanup(move || {
     point_lights.update(|point_lights| {
       if let Some(index_in_storage) = index_in_storage.get() {
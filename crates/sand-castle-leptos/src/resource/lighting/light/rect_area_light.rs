@@ -0,0 +1,223 @@
+use leptos::prelude::*;
+
+use sand_castle_core::{
+  resource::{lighting::light::rect_area_light::RectAreaLight as CoreRectAreaLight, Resource},
+  Quat, Vec3,
+};
+
+use crate::scene::SceneContextValue;
+
+/// Rectangle emitting light from its front face, spanning the local X and Y axes and facing local
+/// -Z.
+#[component]
+pub fn RectAreaLight(
+  #[prop(default = Vec3::new(0.0, 0.0, 0.0).into(), into)] position: MaybeSignal<Vec3>,
+  #[prop(default = Quat::IDENTITY.into(), into)] rotation: MaybeSignal<Quat>,
+  #[prop(default = 1.0_f32.into(), into)] width: MaybeSignal<f32>,
+  #[prop(default = 1.0_f32.into(), into)] height: MaybeSignal<f32>,
+  #[prop(default = Vec3::new(1.0, 1.0, 1.0).into(), into)] color: MaybeSignal<Vec3>,
+  /// Luminance in nits.
+  #[prop(default = 1.0_f32.into(), into)]
+  intensity: MaybeSignal<f32>,
+) -> impl IntoView {
+  let rect_area_light = RwSignal::<Option<CoreRectAreaLight>>::new(None);
+
+  let SceneContextValue {
+    rect_area_lights,
+    scene,
+    renderer,
+    ..
+  } = use_context().expect("`RectAreaLight` must be used in a `Scene` component");
+
+  let index_in_storage = Signal::derive(move || {
+    rect_area_lights.with(|lights| {
+      lights.iter().position(|light| {
+        light.with_untracked(|light| light.as_ref().map(|light| light.id()))
+          == rect_area_light.with_untracked(|light| light.as_ref().map(|light| light.id()))
+      })
+    })
+  });
+
+  Effect::new(move |_| {
+    let light = CoreRectAreaLight::builder()
+      .position(position.get_untracked())
+      .rotation(rotation.get_untracked())
+      .width(width.get_untracked())
+      .height(height.get_untracked())
+      .color(color.get_untracked())
+      .intensity(intensity.get_untracked())
+      .build();
+
+    rect_area_lights.update(|rect_area_lights| {
+      rect_area_lights.push(rect_area_light);
+    });
+
+    rect_area_light.set(Some(light));
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let position = position.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    rect_area_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_position(position);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_rect_area_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let rotation = rotation.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    rect_area_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_rotation(rotation);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_rect_area_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let width = width.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    rect_area_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_width(width);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_rect_area_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let height = height.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    rect_area_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_height(height);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_rect_area_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let color = color.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    rect_area_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_color(color);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_rect_area_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let intensity = intensity.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    rect_area_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_intensity(intensity);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_rect_area_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  on_cleanup(move || {
+    rect_area_lights.update(|rect_area_lights| {
+      if let Some(index_in_storage) = index_in_storage.get() {
+        rect_area_lights.remove(index_in_storage);
+      };
+    });
+  });
+
+  ()
+}
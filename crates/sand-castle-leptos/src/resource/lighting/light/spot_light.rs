@@ -1,7 +1,10 @@
 use leptos::prelude::*;
 
 use sand_castle_core::{
-  resource::{lighting::light::spot_light::SpotLight as CoreSpotLight, Resource},
+  resource::{
+    lighting::light::{shadow::Shadow, spot_light::SpotLight as CoreSpotLight},
+    Resource,
+  },
   Vec3,
 };
 
@@ -12,7 +15,23 @@ pub fn SpotLight(
   #[prop(default = Vec3::new(1.0, 1.0, 1.0).into(), into)] color: MaybeSignal<Vec3>,
   #[prop(optional, into)] position: MaybeSignal<Vec3>,
   #[prop(optional, into)] direction: MaybeSignal<Vec3>,
-  #[prop(default = 45.0_f32.into(), into)] cutoff_angle: MaybeSignal<f32>,
+  /// Luminous intensity in candela.
+  #[prop(default = 1.0_f32.into(), into)]
+  intensity: MaybeSignal<f32>,
+  /// Distance at which the light fades out completely, `0.0` for no cutoff.
+  #[prop(optional, into)]
+  range: MaybeSignal<f32>,
+  /// Exponent of the distance falloff, `2.0` is physically correct inverse-square.
+  #[prop(default = 2.0_f32.into(), into)]
+  decay: MaybeSignal<f32>,
+  /// Angle from `direction` in radians where the light starts to fade out.
+  #[prop(optional, into)]
+  inner_cone_angle: MaybeSignal<f32>,
+  /// Angle from `direction` in radians past which nothing is lit.
+  #[prop(default = std::f32::consts::FRAC_PI_4.into(), into)]
+  outer_cone_angle: MaybeSignal<f32>,
+  #[prop(optional, into)] cast_shadow: MaybeSignal<bool>,
+  #[prop(optional, into)] shadow: MaybeSignal<Shadow>,
 ) -> impl IntoView {
   let spot_light = RwSignal::<Option<CoreSpotLight>>::new(None);
 
@@ -37,7 +56,13 @@ pub fn SpotLight(
       .direction(direction.get_untracked())
       .position(position.get_untracked())
       .color(color.get_untracked())
-      .cutoff_angle(cutoff_angle.get_untracked())
+      .intensity(intensity.get_untracked())
+      .range(range.get_untracked())
+      .decay(decay.get_untracked())
+      .inner_cone_angle(inner_cone_angle.get_untracked())
+      .outer_cone_angle(outer_cone_angle.get_untracked())
+      .cast_shadow(cast_shadow.get_untracked())
+      .shadow(shadow.get_untracked())
       .build();
 
     spot_lights.update(|spot_lights| {
@@ -130,7 +155,7 @@ pub fn SpotLight(
       return;
     };
 
-    let cutoff_angle = cutoff_angle.get();
+    let outer_cone_angle = outer_cone_angle.get();
 
     let Some(index) = index_in_storage.get() else {
       return;
@@ -141,7 +166,139 @@ pub fn SpotLight(
         return;
       };
 
-      light.set_cutoff_angle(cutoff_angle);
+      light.set_outer_cone_angle(outer_cone_angle);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_spot_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let cast_shadow = cast_shadow.get();
+    let shadow = shadow.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    spot_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_cast_shadow(cast_shadow);
+      light.set_shadow(shadow);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_spot_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let intensity = intensity.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    spot_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_intensity(intensity);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_spot_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let range = range.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    spot_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_range(range);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_spot_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let decay = decay.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    spot_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_decay(decay);
+
+      scene.update_untracked(|scene| {
+        if let Some(scene) = scene {
+          scene.update_spot_light(&renderer, index, light);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let inner_cone_angle = inner_cone_angle.get();
+
+    let Some(index) = index_in_storage.get() else {
+      return;
+    };
+
+    spot_light.update_untracked(|light| {
+      let Some(light) = light else {
+        return;
+      };
+
+      light.set_inner_cone_angle(inner_cone_angle);
 
       scene.update_untracked(|scene| {
         if let Some(scene) = scene {
@@ -1,3 +1,5 @@
+pub use sand_castle_core::resource::lighting::material::AlphaMode;
+
 pub mod basic;
 pub mod pbr;
 pub mod phong;
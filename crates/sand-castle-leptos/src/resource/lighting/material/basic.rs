@@ -2,7 +2,7 @@ use leptos::prelude::*;
 
 use sand_castle_core::{
   resource::{
-    lighting::material::{basic::BasicMaterial as CoreBasicMaterial, ToMaterial},
+    lighting::material::{basic::BasicMaterial as CoreBasicMaterial, AlphaMode, ToMaterial},
     object_3d::Object3D,
     texture::TextureId,
   },
@@ -15,6 +15,8 @@ use crate::{resource::mesh::MeshContextValue, scene::SceneContextValue};
 pub fn BasicMaterial(
   #[prop(default = Vec4::new(0.0, 0.0, 0.0, 1.0).into(), into)] color: MaybeSignal<Vec4>,
   #[prop(optional, into)] diffuse_map_texture_id: MaybeProp<TextureId>,
+  #[prop(optional, into)] alpha_mode: MaybeProp<AlphaMode>,
+  #[prop(optional, into)] double_sided: MaybeProp<bool>,
 ) -> impl IntoView {
   let MeshContextValue {
     mesh, material_id, ..
@@ -35,6 +37,8 @@ pub fn BasicMaterial(
     let basic_material = CoreBasicMaterial::builder()
       .color(color.get_untracked())
       .diffuse_map_texture_id(diffuse_map_texture_id.get())
+      .alpha_mode(alpha_mode.get().unwrap_or_default())
+      .double_sided(double_sided.get().unwrap_or_default())
       .build();
 
     let material = basic_material.to_material();
@@ -55,12 +59,18 @@ pub fn BasicMaterial(
       return;
     };
 
-    let color = color.get();
+    let material = CoreBasicMaterial::builder()
+      .color(color.get())
+      .diffuse_map_texture_id(diffuse_map_texture_id.get_untracked())
+      .alpha_mode(alpha_mode.get_untracked().unwrap_or_default())
+      .double_sided(double_sided.get_untracked().unwrap_or_default())
+      .build()
+      .to_material();
 
     mesh.with(|mesh| {
       scene.with(|scene| {
         if let (Some(scene), Some(mesh)) = (scene, mesh) {
-          scene.update_material_data(&renderer, mesh, bytemuck::cast_slice(&[color]));
+          scene.update_material_data(&renderer, mesh, &material);
         }
       });
     });
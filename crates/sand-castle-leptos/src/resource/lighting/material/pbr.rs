@@ -2,7 +2,7 @@ use leptos::prelude::*;
 
 use sand_castle_core::{
   resource::{
-    lighting::material::{pbr::PbrMaterial as CorePbrMaterial, ToMaterial},
+    lighting::material::{pbr::PbrMaterial as CorePbrMaterial, AlphaMode, ToMaterial},
     object_3d::Object3D,
     texture::TextureId,
   },
@@ -17,6 +17,8 @@ pub fn PbrMaterial(
   #[prop(default = 1.0.into(), into)] roughness: MaybeSignal<f32>,
   #[prop(default = 0.0.into(), into)] metalness: MaybeSignal<f32>,
   #[prop(optional, into)] diffuse_map_texture_id: MaybeProp<TextureId>,
+  #[prop(optional, into)] alpha_mode: MaybeProp<AlphaMode>,
+  #[prop(optional, into)] double_sided: MaybeProp<bool>,
 ) -> impl IntoView {
   let MeshContextValue {
     mesh, material_id, ..
@@ -41,6 +43,8 @@ pub fn PbrMaterial(
       .metalness(metalness.get_untracked())
       .roughness(roughness.get_untracked())
       .diffuse_map_texture_id(diffuse_map_texture_id.get_untracked())
+      .alpha_mode(alpha_mode.get().unwrap_or_default())
+      .double_sided(double_sided.get().unwrap_or_default())
       .build();
 
     let material = pbr_material.to_material();
@@ -61,18 +65,20 @@ pub fn PbrMaterial(
       return;
     };
 
-    let color = color.get();
-    let roughness = roughness.get();
-    let metalness = metalness.get();
+    let material = CorePbrMaterial::builder()
+      .color(color.get())
+      .metalness(metalness.get())
+      .roughness(roughness.get())
+      .diffuse_map_texture_id(diffuse_map_texture_id.get_untracked())
+      .alpha_mode(alpha_mode.get().unwrap_or_default())
+      .double_sided(double_sided.get_untracked().unwrap_or_default())
+      .build()
+      .to_material();
 
     mesh.with(|mesh| {
       scene.with(|scene| {
         if let (Some(scene), Some(mesh)) = (scene, mesh) {
-          scene.update_material_data(
-            &renderer,
-            mesh,
-            bytemuck::cast_slice(&[color, Vec4::new(roughness, metalness, 0.0, 0.0)]),
-          );
+          scene.update_material_data(&renderer, mesh, &material);
         }
       });
     });
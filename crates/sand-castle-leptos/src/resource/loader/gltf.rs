@@ -5,21 +5,23 @@ use leptos::prelude::*;
 
 use derive_builder::Builder;
 
-pub use sand_castle_core::resource::loader::{gltf::LoadGltfError, LoadedTransform};
+pub use sand_castle_core::resource::loader::{
+  gltf::LoadGltfError, LoadedMaterial, LoadedTransform,
+};
 
 use sand_castle_core::{
   resource::{
     geometry::{Geometry, Vertex},
     loader::{
       geometry::GeometryLoader,
-      gltf::{import_buffers, Source},
+      gltf::{alpha_mode, import_buffers, Source},
       material::MaterialLoader,
       textures::TextureLoader,
     },
-    texture::{Texture, TextureId},
+    texture::Texture,
     Id,
   },
-  Quat, Vec2, Vec3, Vec4,
+  Mat4, Vec2, Vec3, Vec4,
 };
 
 pub use sand_castle_core::resource::loader::{
@@ -29,6 +31,9 @@ pub use sand_castle_core::resource::loader::{
 
 use crate::scene::SceneContextValue;
 
+/// Every primitive of a glTF model, with its transform, geometry and material.
+pub type LoadedGltf = Vec<(LoadedTransform, Id, LoadedMaterial)>;
+
 #[derive(Clone)]
 pub enum GltfSource {
   Url(MaybeProp<String>),
@@ -61,10 +66,7 @@ impl From<Vec<u8>> for GltfSource {
 
 pub fn use_gltf_loader(
   url: impl Into<MaybeProp<String>>,
-) -> Signal<
-  Option<Result<Vec<(LoadedTransform, Id, Vec4, Option<TextureId>)>, LoadGltfError>>,
-  LocalStorage,
-> {
+) -> Signal<Option<Result<LoadedGltf, LoadGltfError>>, LocalStorage> {
   let url: MaybeProp<String> = url.into();
 
   let SceneContextValue {
@@ -105,7 +107,7 @@ pub fn use_gltf_loader(
 
 pub fn use_gltf_loader_from_source(
   source: impl Into<GltfSource>,
-) -> Signal<Option<Result<Vec<(LoadedTransform, Id, Vec4, Option<TextureId>)>, LoadGltfError>>> {
+) -> Signal<Option<Result<LoadedGltf, LoadGltfError>>> {
   let source: GltfSource = source.into();
 
   let SceneContextValue {
@@ -159,7 +161,7 @@ async fn load_gltf(
   model: Gltf,
   geometry_loader: RwSignal<Option<GeometryLoader>, LocalStorage>,
   texture_loader: RwSignal<Option<TextureLoader>, LocalStorage>,
-) -> Result<Vec<(LoadedTransform, Id, Vec4, Option<TextureId>)>, LoadGltfError> {
+) -> Result<LoadedGltf, LoadGltfError> {
   let (document, blob) = (model.document, model.blob);
 
   let buffers = match import_buffers(&document, None, blob) {
@@ -169,15 +171,39 @@ async fn load_gltf(
     }
   };
 
+  let mut parents = vec![None; document.nodes().len()];
+
+  for node in document.nodes() {
+    for child in node.children() {
+      parents[child.index()] = Some(node.index());
+    }
+  }
+
+  let nodes = document.nodes().collect::<Vec<_>>();
+
+  // node transforms are relative to their parents, so they're composed into world transforms
+  let world_matrix = |index: usize| {
+    let mut matrix = Mat4::from_cols_array_2d(&nodes[index].transform().matrix());
+    let mut parent = parents[index];
+
+    while let Some(index) = parent {
+      matrix = Mat4::from_cols_array_2d(&nodes[index].transform().matrix()) * matrix;
+      parent = parents[index];
+    }
+
+    matrix
+  };
+
   let model = document
     .nodes()
     .filter_map(|node| {
-      let (translation, rotation, scale) = node.transform().decomposed();
+      let (scale, rotation, translation) =
+        world_matrix(node.index()).to_scale_rotation_translation();
 
       let transform = LoadedTransform {
-        translation: Vec3::from_array(translation),
-        rotation: Quat::from_array(rotation),
-        scale: Vec3::from_array(scale),
+        translation,
+        rotation,
+        scale,
       };
 
       node.mesh().map(|mesh| {
@@ -185,12 +211,9 @@ async fn load_gltf(
         let buffers = buffers.as_slice();
 
         mesh.primitives().map(move |primitive| {
-          let color = Vec4::from_array(
-            primitive
-              .material()
-              .pbr_metallic_roughness()
-              .base_color_factor(),
-          );
+          let material = primitive.material();
+          let color = Vec4::from_array(material.pbr_metallic_roughness().base_color_factor());
+          let (alpha_mode, double_sided) = (alpha_mode(&material), material.double_sided());
 
           let vertices = primitive.reader(|buffer| {
             buffers
@@ -231,12 +254,14 @@ async fn load_gltf(
             }
           });
 
-          let diffuse_map = primitive
-            .material()
-            .pbr_metallic_roughness()
-            .base_color_texture();
+          let diffuse_map = material.pbr_metallic_roughness().base_color_texture();
 
-          (geometry_id, transform, color, diffuse_map)
+          (
+            geometry_id,
+            transform,
+            (color, alpha_mode, double_sided),
+            diffuse_map,
+          )
         })
       })
     })
@@ -285,9 +310,20 @@ async fn load_gltf(
   let model = model
     .into_iter()
     .zip(textures.into_iter())
-    .map(|((geometry_id, transform, color, _), texture_id)| {
-      (transform, geometry_id, color, texture_id)
-    })
+    .map(
+      |((geometry_id, transform, (color, alpha_mode, double_sided), _), texture_id)| {
+        (
+          transform,
+          geometry_id,
+          LoadedMaterial {
+            color,
+            diffuse_map_texture_id: texture_id,
+            alpha_mode,
+            double_sided,
+          },
+        )
+      },
+    )
     .collect::<Vec<_>>();
 
   Ok(model)
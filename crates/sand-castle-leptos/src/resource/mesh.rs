@@ -2,17 +2,23 @@ use std::sync::Arc;
 
 use leptos::prelude::*;
 
+pub use sand_castle_core::resource::object_3d::Scale;
+
 use sand_castle_core::{
   resource::{
     geometry::{Geometry, ToGeometry},
     lighting::material::Material,
-    object_3d::{mesh::Mesh as CoreMesh, Scale, SceneTransform},
+    object_3d::{mesh::Mesh as CoreMesh, Object3D, SceneTransform},
     Id, Resource,
   },
   Quat, Vec3,
 };
 
-use crate::scene::SceneContextValue;
+use crate::{
+  pointer::{MeshPointerCallback, MeshPointerHandlers},
+  resource::group::GroupContextValue,
+  scene::SceneContextValue,
+};
 
 #[derive(Clone)]
 pub struct MeshContextValue {
@@ -27,9 +33,23 @@ pub fn Mesh(
   #[prop(optional, into)] rotation: MaybeSignal<Quat>,
   #[prop(optional, into)] scale: MaybeSignal<Scale>,
 
+  /// Draws the mesh into the shadow maps of lights casting shadows.
+  #[prop(optional, into)]
+  cast_shadow: MaybeSignal<bool>,
+  #[prop(optional, into)] receive_shadow: MaybeSignal<bool>,
+
   #[prop(optional, into)] geometry_id: MaybeProp<Id>,
   #[prop(optional, into)] material_id: MaybeProp<Id>,
 
+  /// Called when the mesh is the nearest one under a click on the `Canvas`.
+  #[prop(optional, into)]
+  on_click: Option<MeshPointerCallback>,
+  #[prop(optional, into)] on_pointer_move: Option<MeshPointerCallback>,
+  #[prop(optional, into)] on_pointer_enter: Option<MeshPointerCallback>,
+  /// Receives the last event the mesh got before the pointer left it.
+  #[prop(optional, into)]
+  on_pointer_leave: Option<MeshPointerCallback>,
+
   children: Children,
 ) -> impl IntoView {
   let mesh = RwSignal::new_local(None);
@@ -43,9 +63,19 @@ pub fn Mesh(
     geometry_loader,
     material_loader,
     texture_loader,
+    pointer_handlers,
     ..
   } = use_context().expect("`Mesh` must be used in a `Scene` component");
 
+  let handlers = MeshPointerHandlers {
+    on_click,
+    on_pointer_move,
+    on_pointer_enter,
+    on_pointer_leave,
+  };
+
+  let group = use_context::<GroupContextValue>();
+
   let position = Memo::new(move |_| position.get());
   let rotation = Memo::new(move |_| rotation.get());
   let scale = Memo::new(move |_| scale.get());
@@ -59,6 +89,8 @@ pub fn Mesh(
       .position(position.get_untracked())
       .rotation(rotation.get_untracked())
       .scale(scale.get_untracked())
+      .cast_shadow(cast_shadow.get_untracked())
+      .receive_shadow(receive_shadow.get_untracked())
       .build();
 
     geometry_loader.with_untracked(|geometry_loader| {
@@ -72,13 +104,15 @@ pub fn Mesh(
               Some(texture_loader),
             ) = (scene, geometry_loader, material_loader, texture_loader)
             {
-              scene.insert(
+              if let Err(err) = scene.insert(
                 &renderer,
                 geometry_loader,
                 material_loader,
                 texture_loader,
                 &mesh_desc,
-              );
+              ) {
+                leptos::logging::warn!("Failed to insert `Mesh`: {err}");
+              }
             }
           });
         });
@@ -134,13 +168,15 @@ pub fn Mesh(
             if let (Some(scene), Some(mesh), Some(material_loader), Some(texture_loader)) =
               (scene, mesh, material_loader, texture_loader)
             {
-              scene.update_material(
+              if let Err(err) = scene.update_material(
                 &renderer,
                 mesh,
                 texture_loader,
                 material_loader,
                 inner_material_id,
-              );
+              ) {
+                leptos::logging::warn!("Failed to update the material of `Mesh`: {err}");
+              }
             }
           });
         });
@@ -156,7 +192,7 @@ pub fn Mesh(
     let position = position.get();
 
     mesh.update(|mesh| {
-      scene.with(|scene| {
+      scene.update_untracked(|scene| {
         if let (Some(scene), Some(mesh)) = (scene, mesh) {
           mesh.update_pos(scene, &renderer, position);
         }
@@ -172,7 +208,7 @@ pub fn Mesh(
     let rotation = rotation.get();
 
     mesh.update(|mesh| {
-      scene.with(|scene| {
+      scene.update_untracked(|scene| {
         if let (Some(scene), Some(mesh)) = (scene, mesh) {
           mesh.update_rot(scene, &renderer, rotation);
         }
@@ -188,7 +224,7 @@ pub fn Mesh(
     let scale = scale.get();
 
     mesh.update(|mesh| {
-      scene.with(|scene| {
+      scene.update_untracked(|scene| {
         if let (Some(scene), Some(mesh)) = (scene, mesh) {
           mesh.update_scale(scene, &renderer, scale);
         }
@@ -196,11 +232,74 @@ pub fn Mesh(
     });
   });
 
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let cast_shadow = cast_shadow.get();
+    let receive_shadow = receive_shadow.get();
+
+    mesh.update(|mesh| {
+      scene.update_untracked(|scene| {
+        if let (Some(scene), Some(mesh)) = (scene, mesh) {
+          mesh.set_cast_shadow(cast_shadow);
+          mesh.set_receive_shadow(receive_shadow);
+          scene.update_shadows(&renderer, mesh);
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    let parent = group
+      .and_then(|GroupContextValue { group }| group.with(|group| group.as_ref().map(Resource::id)));
+
+    mesh.with(|mesh| {
+      scene.update_untracked(|scene| {
+        if let (Some(scene), Some(mesh)) = (scene, mesh) {
+          scene.set_parent(&renderer, mesh.id(), parent);
+        }
+      });
+    });
+  });
+
+  // the mesh gets a new id whenever the `Renderer` is rebuilt
+  Effect::new(move |previous_id: Option<Option<Id>>| {
+    let id = mesh.with(|mesh| mesh.as_ref().map(Resource::id));
+
+    pointer_handlers.update(|pointer_handlers| {
+      if let Some(previous_id) = previous_id.flatten() {
+        pointer_handlers.remove(&previous_id);
+      }
+
+      if let Some(id) = id.filter(|_| !handlers.is_empty()) {
+        pointer_handlers.insert(id, handlers);
+      }
+    });
+
+    id
+  });
+
   on_cleanup(move || {
+    if let Some(id) = mesh.with_untracked(|mesh| mesh.as_ref().map(Resource::id)) {
+      pointer_handlers.update(|pointer_handlers| {
+        pointer_handlers.remove(&id);
+      });
+    }
+
+    let Some(renderer) = renderer.get_untracked() else {
+      return;
+    };
+
     mesh.with(|mesh| {
       scene.update(|scene| {
         if let (Some(scene), Some(mesh)) = (scene, mesh) {
-          scene.remove(mesh);
+          scene.remove(&renderer, mesh);
         }
       });
     });
@@ -1,21 +1,27 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use leptos::prelude::*;
 
 use sand_castle_core::{
+  post_processing::tone_mapping::ToneMapping,
   renderer::Renderer,
   resource::{
     lighting::light::{
       directional_light::DirectionalLight as CoreDirectionalLight,
-      point_light::PointLight as CorePointLight, spot_light::SpotLight as CoreSpotLight,
+      point_light::PointLight as CorePointLight,
+      rect_area_light::RectAreaLight as CoreRectAreaLight, spot_light::SpotLight as CoreSpotLight,
     },
     loader::{geometry::GeometryLoader, material::MaterialLoader, textures::TextureLoader},
+    Id,
   },
   scene::Scene as CoreScene,
   Vec4,
 };
 
-use crate::canvas::RendererContextValue;
+use crate::{
+  canvas::RendererContextValue,
+  pointer::{MeshPointerHandlers, PointerTarget},
+};
 
 #[derive(Clone)]
 pub struct SceneContextValue {
@@ -27,11 +33,15 @@ pub struct SceneContextValue {
   pub directional_lights: RwSignal<Vec<RwSignal<Option<CoreDirectionalLight>>>, LocalStorage>,
   pub spot_lights: RwSignal<Vec<RwSignal<Option<CoreSpotLight>>>, LocalStorage>,
   pub point_lights: RwSignal<Vec<RwSignal<Option<CorePointLight>>>, LocalStorage>,
+  pub rect_area_lights: RwSignal<Vec<RwSignal<Option<CoreRectAreaLight>>>, LocalStorage>,
+  pub pointer_handlers: RwSignal<HashMap<Id, MeshPointerHandlers>, LocalStorage>,
 }
 
 #[component]
 pub fn Scene(
   #[prop(optional, into)] color: MaybeProp<Vec4>,
+  #[prop(optional, into)] tone_mapping: MaybeProp<ToneMapping>,
+  #[prop(optional, into)] exposure: MaybeProp<f32>,
   #[prop(optional)] children: Option<Children>,
 ) -> impl IntoView {
   let scene = RwSignal::new_local(None);
@@ -43,9 +53,24 @@ pub fn Scene(
   let directional_lights = RwSignal::new_local(vec![]);
   let point_lights = RwSignal::new_local(vec![]);
   let spot_lights = RwSignal::new_local(vec![]);
+  let rect_area_lights = RwSignal::new_local(vec![]);
+
+  let pointer_handlers = RwSignal::new_local(HashMap::new());
+
+  let RendererContextValue {
+    renderer,
+    scenes,
+    pointer_targets,
+    ..
+  } = use_context().expect("`Scene` must be used in a `Canvas` component");
+
+  let pointer_target = PointerTarget {
+    scene,
+    geometry_loader,
+    handlers: pointer_handlers,
+  };
 
-  let RendererContextValue { renderer, scenes } =
-    use_context().expect("`Scene` must be used in a `Canvas` component");
+  pointer_targets.update(|targets| targets.push(pointer_target));
 
   Effect::new(move |_| {
     let Some(renderer) = renderer.get() else {
@@ -61,10 +86,27 @@ pub fn Scene(
         scene_builder
       };
 
+      let scene_builder = if let Some(tone_mapping) = tone_mapping.get_untracked() {
+        scene_builder.tone_mapping(tone_mapping)
+      } else {
+        scene_builder
+      };
+
+      let scene_builder = if let Some(exposure) = exposure.get_untracked() {
+        scene_builder.exposure(exposure)
+      } else {
+        scene_builder
+      };
+
       *scene = Some(scene_builder.build());
     });
 
-    scenes.update(|scenes| scenes.push(scene));
+    // the scene is rebuilt whenever the `Renderer` is, but only registered once
+    scenes.update(|scenes| {
+      if !scenes.contains(&scene) {
+        scenes.push(scene);
+      }
+    });
   });
 
   Effect::new(move |_| {
@@ -85,6 +127,30 @@ pub fn Scene(
     });
   });
 
+  Effect::new(move |_| {
+    let Some(tone_mapping) = tone_mapping.get() else {
+      return;
+    };
+
+    scene.update(|scene| {
+      if let Some(scene) = scene {
+        scene.set_tone_mapping(tone_mapping);
+      }
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(exposure) = exposure.get() else {
+      return;
+    };
+
+    scene.update(|scene| {
+      if let Some(scene) = scene {
+        scene.set_exposure(exposure);
+      }
+    });
+  });
+
   Effect::new(move |_| {
     let Some(renderer) = renderer.get() else {
       return;
@@ -98,7 +164,9 @@ pub fn Scene(
 
       scene.update(|scene| {
         if let Some(scene) = scene {
-          scene.bind_point_lights(&renderer, &lights);
+          if let Err(err) = scene.bind_point_lights(&renderer, &lights) {
+            leptos::logging::warn!("{err}");
+          }
         }
       });
     });
@@ -117,7 +185,9 @@ pub fn Scene(
 
       scene.update(|scene| {
         if let Some(scene) = scene {
-          scene.bind_spot_lights(&renderer, &lights);
+          if let Err(err) = scene.bind_spot_lights(&renderer, &lights) {
+            leptos::logging::warn!("{err}");
+          }
         }
       });
     });
@@ -136,7 +206,30 @@ pub fn Scene(
 
       scene.update(|scene| {
         if let Some(scene) = scene {
-          scene.bind_directional_lights(&renderer, &lights);
+          if let Err(err) = scene.bind_directional_lights(&renderer, &lights) {
+            leptos::logging::warn!("{err}");
+          }
+        }
+      });
+    });
+  });
+
+  Effect::new(move |_| {
+    let Some(renderer) = renderer.get() else {
+      return;
+    };
+
+    rect_area_lights.with(|lights| {
+      let lights = lights
+        .iter()
+        .filter_map(|light: &RwSignal<Option<CoreRectAreaLight>>| light.get())
+        .collect::<Vec<_>>();
+
+      scene.update(|scene| {
+        if let Some(scene) = scene {
+          if let Err(err) = scene.bind_rect_area_lights(&renderer, &lights) {
+            leptos::logging::warn!("{err}");
+          }
         }
       });
     });
@@ -151,9 +244,13 @@ pub fn Scene(
     directional_lights,
     point_lights,
     spot_lights,
+    rect_area_lights,
+    pointer_handlers,
   });
 
   on_cleanup(move || {
+    pointer_targets.update(|targets| targets.retain(|target| *target != pointer_target));
+
     scene.with_untracked(|current_scene| {
       let Some(current_scene) = current_scene else {
         return;
@@ -0,0 +1,32 @@
+use leptos::prelude::*;
+
+pub use sand_castle_core::stats::RenderStats;
+
+use crate::canvas::RendererContextValue;
+
+pub struct UseRenderStatsReturn {
+  /// Stats of all scenes drawn in the last frame.
+  pub stats: Signal<RenderStats>,
+  /// Frames per second, derived from the time between the last two frames.
+  pub fps: Signal<f64>,
+}
+
+/// Reactive render stats of the surrounding `Canvas`, updated every frame.
+pub fn use_render_stats() -> UseRenderStatsReturn {
+  let RendererContextValue {
+    stats, frame_time, ..
+  } = use_context().expect("`use_render_stats` must be used in a `Canvas` component");
+
+  UseRenderStatsReturn {
+    stats: stats.into(),
+    fps: Signal::derive(move || {
+      let frame_time = frame_time.get();
+
+      if frame_time > 0.0 {
+        1000.0 / frame_time
+      } else {
+        0.0
+      }
+    }),
+  }
+}
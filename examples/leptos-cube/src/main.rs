@@ -15,6 +15,7 @@ use sand_castle_leptos::{
     },
     mesh::Mesh,
   },
+  pointer::MeshPointerEvent,
   scene::Scene,
   Quat, Vec3, Vec4,
 };
@@ -42,6 +43,16 @@ fn App() -> impl IntoView {
   let cube_rot =
     Signal::derive(move || Quat::from_axis_angle(Vec3::Y, cube_rot_angle.get().to_radians()));
 
+  let cube_hovered = RwSignal::new(false);
+  let cube_color = Signal::derive(move || {
+    if cube_hovered.get() {
+      Vec4::new(0.9, 0.4, 0.2, 1.0)
+    } else {
+      Vec4::new(0.5, 0.5, 0.5, 1.0)
+    }
+  });
+  let cube_clicked_at = RwSignal::new(None::<Vec3>);
+
   use_interval_fn(
     move || {
       cube_rot_angle.update(|angle| {
@@ -61,7 +72,7 @@ fn App() -> impl IntoView {
     >
       <Scene color=Vec4::new(0.1, 0.1, 0.1, 0.0)>
         <AmbientLight color=Vec3::new(0.1, 0.1, 0.1)/>
-        <PointLight position=Vec3::new(10.0, 10.0, 10.0)/>
+        <PointLight position=Vec3::new(10.0, 10.0, 10.0) intensity=300.0/>
 
         <PerspectiveCamera
           aspect_ratio=1080.0/720.0
@@ -73,9 +84,12 @@ fn App() -> impl IntoView {
         <Mesh
           rotation=cube_rot
           position=Vec3::new(0.0, 0.0, 0.0)
+          on_pointer_enter=move |_| cube_hovered.set(true)
+          on_pointer_leave=move |_| cube_hovered.set(false)
+          on_click=move |event: MeshPointerEvent| cube_clicked_at.set(Some(event.point))
         >
           <Cuboid />
-          <PhongMaterial color=Vec4::new(0.5, 0.5, 0.5, 1.0)/>
+          <PhongMaterial color=cube_color/>
         </Mesh>
       </Scene>
     </Canvas>
@@ -102,6 +116,10 @@ fn App() -> impl IntoView {
       <div>
         <span>{move || format!("rotation (y): {}°", cube_rot_angle.get())}</span>
       </div>
+
+      <div>
+        <span>{move || format!("clicked at: {:?}", cube_clicked_at.get())}</span>
+      </div>
     </div>
   }
 }
@@ -23,11 +23,11 @@ use sand_castle_leptos::{
       light::{ambient_light::AmbientLight, point_light::PointLight},
       material::{basic::BasicMaterial, pbr::PbrMaterial, phong::PhongMaterial},
     },
-    loader::{
-      gltf::{use_gltf_loader_from_source, Gltf, LoadGltfError, LoadedGeometry, LoadedTransform},
-      textures::TextureId,
+    loader::gltf::{
+      use_gltf_loader_from_source, Gltf, LoadGltfError, LoadedGeometry, LoadedMaterial,
+      LoadedTransform,
     },
-    mesh::Mesh,
+    mesh::{Mesh, Scale},
     Id,
   },
   scene::Scene,
@@ -64,7 +64,7 @@ fn GltfModel(
     <>
       {move || model
         .get()
-        .map(|model: Result<Vec<(LoadedTransform, Id, Vec4, Option<TextureId>)>, LoadGltfError>| {
+        .map(|model: Result<Vec<(LoadedTransform, Id, LoadedMaterial)>, LoadGltfError>| {
           let geometry_wrapper = std::iter::once(model);
 
           view! {
@@ -76,19 +76,22 @@ fn GltfModel(
                   <For
                     each=move || geometries.clone()
                     key=|(index, _)| *index
-                    children=move |(_, (LoadedTransform { translation, rotation, .. }, geometry_id, color, texture_id))| {
+                    children=move |(_, (LoadedTransform { translation, rotation, scale }, geometry_id, LoadedMaterial { color, diffuse_map_texture_id, alpha_mode, double_sided }))| {
                       view! {
                         <Mesh
                           geometry_id=geometry_id
                           position=translation
                           rotation=rotation
+                          scale=Scale::from(scale)
                         >
                           <Show
                             when=move || swap_material.get()
                             fallback=move || view! {
                               <PhongMaterial
                                 color=color
-                                diffuse_map_texture_id=texture_id
+                                diffuse_map_texture_id=diffuse_map_texture_id
+                                alpha_mode=alpha_mode
+                                double_sided=double_sided
                               />
                             }
                           >
@@ -96,7 +99,9 @@ fn GltfModel(
                               color=color
                               roughness=roughness
                               metalness=metalness
-                              diffuse_map_texture_id=texture_id
+                              diffuse_map_texture_id=diffuse_map_texture_id
+                              alpha_mode=alpha_mode
+                              double_sided=double_sided
                             />
                           </Show>
                         </Mesh>
@@ -306,7 +311,7 @@ fn App() -> impl IntoView {
       <Scene color=Vec4::new(0.01, 0.01, 0.01, 1.0)>
         <AmbientLight color=Vec3::new(0.1, 0.1, 0.1)/>
 
-        <PointLight position=Vec3::new(7.5, 7.5, 7.5)/>
+        <PointLight position=Vec3::new(7.5, 7.5, 7.5) intensity=170.0/>
 
         <PerspectiveCamera
           aspect_ratio=1080.0/720.0
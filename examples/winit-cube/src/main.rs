@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use sand_castle_core::{
+  renderer::{Backend, Renderer},
+  resource::{
+    camera::perspective::PerspectiveCamera,
+    geometry::{cuboid::Cuboid, ToGeometry},
+    lighting::{
+      light::{ambient_light::AmbientLight, point_light::PointLight},
+      material::{phong::PhongMaterial, ToMaterial},
+    },
+    loader::{geometry::GeometryLoader, material::MaterialLoader, textures::TextureLoader},
+    object_3d::mesh::Mesh,
+  },
+  scene::Scene,
+  Quat, Vec3, Vec4,
+};
+use winit::{
+  application::ApplicationHandler,
+  event::WindowEvent,
+  event_loop::{ActiveEventLoop, EventLoop},
+  window::{Window, WindowId},
+};
+
+struct Viewer {
+  renderer: Renderer,
+  scene: Scene,
+}
+
+#[derive(Default)]
+struct App {
+  window: Option<Arc<Window>>,
+  viewer: Option<Viewer>,
+}
+
+impl ApplicationHandler for App {
+  fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+    let window = Arc::new(
+      event_loop
+        .create_window(Window::default_attributes().with_title("sand-castle winit"))
+        .expect("failed to create window"),
+    );
+
+    let size = window.inner_size();
+
+    let renderer = pollster::block_on(
+      Renderer::builder()
+        .backend(Backend::Auto)
+        .window(window.clone(), size.width, size.height)
+        .build(),
+    )
+    .expect("failed to build `Renderer`");
+
+    let mut scene = Scene::builder(&renderer)
+      .color(Vec4::new(0.1, 0.1, 0.1, 1.0))
+      .build();
+
+    let mut geometry_loader = GeometryLoader::new();
+    let mut material_loader = MaterialLoader::new();
+    let texture_loader = TextureLoader::new();
+
+    let geometry = Cuboid::builder().build().to_geometry();
+    let geometry_id = *geometry.id();
+    geometry_loader.insert(geometry);
+
+    let material = PhongMaterial::with_color(Vec4::new(0.9, 0.4, 0.2, 1.0)).to_material();
+    let material_id = *material.id();
+    material_loader.insert(material);
+
+    let camera = PerspectiveCamera::builder()
+      .yaw(270.0f32.to_radians())
+      .pitch(-25.0f32.to_radians())
+      .fov(1.0)
+      .aspect_ratio(size.width as f32 / size.height.max(1) as f32)
+      .position(Vec3::new(0.0, 2.5, 5.0))
+      .rotation(Quat::IDENTITY)
+      .scale(Default::default())
+      .build();
+
+    scene.set_camera(&renderer, &camera);
+    scene.update_ambient_light(
+      &renderer,
+      &AmbientLight::builder()
+        .color(Vec3::new(0.2, 0.2, 0.2))
+        .build(),
+    );
+    scene.bind_point_lights(
+      &renderer,
+      &[PointLight::builder()
+        .position(Vec3::new(2.0, 3.0, 3.0))
+        .intensity(20.0)
+        .build()],
+    )
+    .expect("a single point light always fits");
+
+    let mesh = Mesh::builder()
+      .geometry_id(geometry_id)
+      .material_id(material_id)
+      .position(Vec3::ZERO)
+      .rotation(Quat::IDENTITY)
+      .scale(Default::default())
+      .build();
+
+    scene
+      .insert(
+        &renderer,
+        &geometry_loader,
+        &material_loader,
+        &texture_loader,
+        &mesh,
+      )
+      .expect("the phong material declares every light array");
+
+    self.window = Some(window);
+    self.viewer = Some(Viewer { renderer, scene });
+  }
+
+  fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+    let Some(Viewer { renderer, scene }) = &mut self.viewer else {
+      return;
+    };
+
+    match event {
+      WindowEvent::CloseRequested => event_loop.exit(),
+      WindowEvent::Resized(size) => {
+        renderer.set_window_size(size.width, size.height);
+        scene.resize(renderer);
+      }
+      WindowEvent::RedrawRequested => {
+        scene.render(renderer);
+
+        if let Some(window) = &self.window {
+          window.request_redraw();
+        }
+      }
+      _ => {}
+    }
+  }
+}
+
+fn main() {
+  let event_loop = EventLoop::new().expect("failed to create event loop");
+
+  event_loop
+    .run_app(&mut App::default())
+    .expect("event loop failed");
+}